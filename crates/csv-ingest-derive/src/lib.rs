@@ -0,0 +1,173 @@
+//! Derive macro for `csv_ingest::CsvRecord`.
+//!
+//! Use it through the `derive` feature of `csv_ingest` rather than depending on
+//! this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument, GenericParam, LitStr,
+    PathArguments, Result, Type,
+};
+
+/// Implement `csv_ingest::CsvRecord` for a struct of borrowed fields.
+///
+/// Fields must be `&str`, `&[u8]`, or an `Option` of either, borrowing the
+/// struct's single lifetime. A field reads the column named after it, or the
+/// name given by `#[csv(rename = "header")]`. Non-`Option` fields are required
+/// headers.
+#[proc_macro_derive(CsvRecord, attributes(csv))]
+pub fn derive_csv_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Clone, Copy)]
+enum FieldKind {
+    Str,
+    Bytes,
+}
+
+fn expand(input: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let lifetime = record_lifetime(input)?;
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            name,
+            "CsvRecord can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            name,
+            "CsvRecord requires a struct with named fields",
+        ));
+    };
+
+    let mut descriptors = Vec::with_capacity(fields.named.len());
+    let mut initializers = Vec::with_capacity(fields.named.len());
+    for (index, field) in fields.named.iter().enumerate() {
+        let ident = field.ident.as_ref().expect("named field");
+        let header =
+            header_name(field)?.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+        let (optional, kind) = field_kind(&field.ty)?;
+        let accessor = match (optional, kind) {
+            (false, FieldKind::Str) => quote!(str),
+            (false, FieldKind::Bytes) => quote!(bytes),
+            (true, FieldKind::Str) => quote!(optional_str),
+            (true, FieldKind::Bytes) => quote!(optional_bytes),
+        };
+        let required = !optional;
+        descriptors.push(quote! {
+            ::csv_ingest::CsvField { header: #header, required: #required }
+        });
+        initializers.push(quote! {
+            #ident: columns.#accessor(record, #index)?
+        });
+    }
+
+    Ok(quote! {
+        impl<#lifetime> ::csv_ingest::CsvRecord<#lifetime> for #name<#lifetime> {
+            const FIELDS: &'static [::csv_ingest::CsvField] = &[#(#descriptors),*];
+
+            fn from_record(
+                columns: &::csv_ingest::CsvColumns,
+                record: &#lifetime ::csv_ingest::ByteRecord,
+            ) -> ::csv_ingest::CsvResult<Self> {
+                ::core::result::Result::Ok(Self {
+                    #(#initializers),*
+                })
+            }
+        }
+    })
+}
+
+fn record_lifetime(input: &DeriveInput) -> Result<&syn::Lifetime> {
+    let mut lifetimes = Vec::new();
+    for param in &input.generics.params {
+        match param {
+            GenericParam::Lifetime(param) => lifetimes.push(&param.lifetime),
+            param => {
+                return Err(Error::new_spanned(
+                    param,
+                    "CsvRecord views cannot have type or const parameters",
+                ))
+            }
+        }
+    }
+    match lifetimes[..] {
+        [lifetime] => Ok(lifetime),
+        _ => Err(Error::new(
+            Span::call_site(),
+            "CsvRecord views must borrow from the record through exactly one lifetime parameter",
+        )),
+    }
+}
+
+fn header_name(field: &syn::Field) -> Result<Option<LitStr>> {
+    let mut header = None;
+    for attribute in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("csv"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                header = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported csv attribute; expected `rename`"))
+            }
+        })?;
+    }
+    Ok(header)
+}
+
+fn field_kind(ty: &Type) -> Result<(bool, FieldKind)> {
+    if let Some(inner) = option_inner(ty) {
+        return borrowed_kind(inner).map(|kind| (true, kind));
+    }
+    borrowed_kind(ty).map(|kind| (false, kind))
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        GenericArgument::Type(inner) if arguments.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+fn borrowed_kind(ty: &Type) -> Result<FieldKind> {
+    if let Type::Reference(reference) = ty {
+        if reference.mutability.is_none() {
+            match &*reference.elem {
+                Type::Path(path) if path.path.is_ident("str") => return Ok(FieldKind::Str),
+                Type::Slice(slice) => {
+                    if let Type::Path(path) = &*slice.elem {
+                        if path.path.is_ident("u8") {
+                            return Ok(FieldKind::Bytes);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Err(Error::new_spanned(
+        ty,
+        "CsvRecord fields must be `&str`, `&[u8]`, or an `Option` of either",
+    ))
+}
@@ -0,0 +1,818 @@
+use crate::io::{detect_compression, Compression};
+use crate::members::{not_compressed, MemberReader};
+use crate::{
+    reader_from_path, BoxedCsvReader, CompressedStreamError, CsvIngestError, CsvMeta, CsvResult,
+    TrailingDataPolicy, TrailingDataReport,
+};
+use bytes::Bytes;
+use flate2::bufread::{GzDecoder, MultiGzDecoder};
+use memchr::memmem;
+use memmap2::MmapOptions;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::thread;
+use tokio::sync::mpsc;
+use tokio_util::io::StreamReader;
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b\x08";
+/// How much of a possible gzip member must decode before it is trusted as one.
+const PROBE_LEN: u64 = 4096;
+
+/// How a compressed local file is divided between decompression threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvDecompressConfig {
+    /// The number of decompression threads. Defaults to the available
+    /// parallelism.
+    pub workers: Option<NonZeroUsize>,
+    /// The target compressed size of one unit of work. Units hold whole gzip
+    /// members or zstd frames, so a unit is never smaller than one of them.
+    pub segment_size: usize,
+    /// What to do with data after the last gzip member or zstd frame, as
+    /// `CsvMeta::trailing_data_policy` does for [`crate::build_csv_reader`].
+    pub trailing_data: TrailingDataPolicy,
+}
+
+impl Default for CsvDecompressConfig {
+    fn default() -> Self {
+        Self {
+            workers: None,
+            segment_size: 4 << 20,
+            trailing_data: TrailingDataPolicy::Error,
+        }
+    }
+}
+
+impl CsvDecompressConfig {
+    pub(crate) fn worker_count(&self) -> usize {
+        self.workers.map_or_else(
+            || thread::available_parallelism().map_or(1, usize::from),
+            usize::from,
+        )
+    }
+}
+
+/// Build a reader from a local file path, as [`reader_from_path`] does, that
+/// decompresses independent gzip members or zstd frames on several threads.
+///
+/// BGZF blocks and zstd frames, including those of the seekable zstd format,
+/// are located from their headers. Other gzip files are searched for member
+/// headers, and each candidate is confirmed by the member before it ending
+/// there. Decoded segments are returned in file order and every member or
+/// frame is decoded, so concatenated inputs yield all of their data. A file
+/// with a single member or frame, or without compression, is read serially.
+///
+/// Truncated input and trailing data are reported as by
+/// [`crate::build_csv_reader`], with `config.trailing_data` as the policy.
+pub async fn reader_from_path_parallel(
+    path: &Path,
+    config: &CsvDecompressConfig,
+) -> CsvResult<(BoxedCsvReader<'static>, CsvMeta)> {
+    let meta = CsvMeta {
+        name_hint: path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        trailing_data_policy: config.trailing_data,
+        ..Default::default()
+    };
+    let Some(compression) = detect_compression(&meta)? else {
+        return reader_from_path(path).await;
+    };
+
+    let file = std::fs::File::open(path)?;
+    let segment_size = config.segment_size;
+    let (map, segments) = tokio::task::spawn_blocking(move || -> CsvResult<_> {
+        // SAFETY: the map is read-only and is bounded by the file's length when mapped.
+        let map = unsafe { MmapOptions::new().map(&file)? };
+        let segments = plan(&map, compression, segment_size);
+        Ok((map, segments))
+    })
+    .await
+    .map_err(|_| CsvIngestError::DecompressWorkerPanicked)??;
+
+    if segments.len() < 2 {
+        // One unit of work gains nothing from threads, so stream it instead.
+        let reader = MemberReader::new(
+            tokio::fs::File::open(path).await?,
+            compression,
+            meta.trailing_data_policy,
+            meta.trailing_data.clone(),
+        );
+        return Ok((Box::new(reader), meta));
+    }
+
+    let workers = config.worker_count();
+    let policy = config.trailing_data;
+    let report = meta.trailing_data.clone();
+    let (chunks, receiver) = mpsc::channel(workers);
+    thread::Builder::new()
+        .name("csv-ingest-decompress".to_string())
+        .spawn(move || {
+            let trailing = (policy, &report);
+            decode_ordered(&map, &segments, workers, 2 * workers, trailing, |chunk| {
+                chunks.blocking_send(chunk.map(Bytes::from)).is_ok()
+            });
+        })?;
+    let chunks = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let chunk = receiver.recv().await?;
+        Some((chunk, receiver))
+    });
+    Ok((Box::new(StreamReader::new(Box::pin(chunks))), meta))
+}
+
+/// Decompress `data` on the configured threads, a few segments ahead of
+/// `emit`, and pass the decoded segments to it in order until it returns
+/// `false` or has been passed an error.
+#[cfg(any(test, feature = "fast_local"))]
+pub(crate) fn decompress_each(
+    data: &[u8],
+    compression: Compression,
+    config: &CsvDecompressConfig,
+    mut emit: impl FnMut(CsvResult<Vec<u8>>) -> bool,
+) {
+    let segments = plan(data, compression, config.segment_size);
+    let workers = config.worker_count();
+    decode_ordered(
+        data,
+        &segments,
+        workers,
+        2 * workers,
+        (config.trailing_data, &TrailingDataReport::default()),
+        |chunk| emit(chunk.map_err(unwrap_panic)),
+    );
+}
+
+/// Decompress all of `data` on the configured threads.
+#[cfg(test)]
+fn decompress(
+    data: &[u8],
+    compression: Compression,
+    config: &CsvDecompressConfig,
+) -> CsvResult<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let mut failure = None;
+    decompress_each(data, compression, config, |chunk| match chunk {
+        Ok(bytes) => {
+            decoded.extend_from_slice(&bytes);
+            true
+        }
+        Err(error) => {
+            failure = Some(error);
+            false
+        }
+    });
+    match failure {
+        Some(error) => Err(error),
+        None => Ok(decoded),
+    }
+}
+
+/// A range of compressed input decoded as one unit of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: usize,
+    end: usize,
+    kind: SegmentKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentKind {
+    /// Whole gzip members.
+    Gzip,
+    /// Whole zstd frames, or an incomplete or corrupt last frame.
+    Zstd,
+    /// Data after the last zstd frame that does not start another.
+    Trailing,
+    /// Gzip members with unknown boundaries. Decoding starts at the first
+    /// member found in the segment, or exactly at its start when `anchored`,
+    /// and continues to the first member boundary at or after its end.
+    Speculative { anchored: bool },
+}
+
+/// The output of one segment and where its members start and end.
+struct Decoded {
+    /// The first member decoded, if the segment holds the start of one.
+    start: Option<usize>,
+    /// Where decoding stopped, after the last whole member.
+    end: usize,
+    bytes: Vec<u8>,
+    error: Option<io::Error>,
+    /// Where data that does not start a member follows the last one.
+    trailing: Option<usize>,
+}
+
+fn plan(data: &[u8], compression: Compression, segment_size: usize) -> Vec<Segment> {
+    let (boundaries, kind) = match compression {
+        Compression::Gzip => (bgzf_boundaries(data), SegmentKind::Gzip),
+        Compression::Zstd => (zstd_boundaries(data), SegmentKind::Zstd),
+    };
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for &boundary in &boundaries {
+        if boundary - start >= segment_size {
+            segments.push(Segment {
+                start,
+                end: boundary,
+                kind,
+            });
+            start = boundary;
+        }
+    }
+    if let Some(&last) = boundaries.last() {
+        if start < last {
+            segments.push(Segment {
+                start,
+                end: last,
+                kind,
+            });
+            start = last;
+        }
+    }
+    if start == data.len() {
+        return segments;
+    }
+
+    match compression {
+        Compression::Gzip if has_later_member(data, start) => {
+            let step = segment_size.max(1);
+            let mut nominal = start;
+            while nominal < data.len() {
+                let end = nominal.saturating_add(step).min(data.len());
+                segments.push(Segment {
+                    start: nominal,
+                    end,
+                    kind: SegmentKind::Speculative {
+                        anchored: nominal == start,
+                    },
+                });
+                nominal = end;
+            }
+        }
+        Compression::Gzip => segments.push(Segment {
+            start,
+            end: data.len(),
+            kind: SegmentKind::Speculative { anchored: true },
+        }),
+        Compression::Zstd => segments.push(Segment {
+            start,
+            end: data.len(),
+            kind: if start > 0 && !compression.starts_member(&data[start..]) {
+                SegmentKind::Trailing
+            } else {
+                kind
+            },
+        }),
+    }
+    segments
+}
+
+/// The ends of the leading BGZF blocks, which record their own sizes.
+fn bgzf_boundaries(data: &[u8]) -> Vec<usize> {
+    let mut boundaries = Vec::new();
+    let mut position = 0;
+    while let Some(size) = bgzf_block_size(&data[position..]) {
+        position += size;
+        boundaries.push(position);
+    }
+    boundaries
+}
+
+fn bgzf_block_size(block: &[u8]) -> Option<usize> {
+    const FEXTRA: u8 = 0x04;
+    if block.len() < 18 || !block.starts_with(GZIP_MAGIC) || block[3] & FEXTRA == 0 {
+        return None;
+    }
+    let extra_len = usize::from(u16::from_le_bytes([block[10], block[11]]));
+    let mut extra = block.get(12..12 + extra_len)?;
+    while extra.len() >= 4 {
+        let field_len = usize::from(u16::from_le_bytes([extra[2], extra[3]]));
+        if extra[..2] == *b"BC" && field_len == 2 {
+            let block_size = usize::from(u16::from_le_bytes([*extra.get(4)?, *extra.get(5)?]));
+            return Some(block_size + 1).filter(|size| *size <= block.len());
+        }
+        extra = extra.get(4 + field_len..)?;
+    }
+    None
+}
+
+/// The ends of the leading zstd frames, skippable frames included.
+fn zstd_boundaries(data: &[u8]) -> Vec<usize> {
+    let mut boundaries = Vec::new();
+    let mut position = 0;
+    while position < data.len() {
+        match zstd::zstd_safe::find_frame_compressed_size(&data[position..]) {
+            Ok(size) if size > 0 => {
+                position += size;
+                boundaries.push(position);
+            }
+            _ => break,
+        }
+    }
+    boundaries
+}
+
+/// Whether a gzip member appears to start anywhere after the one at `start`.
+fn has_later_member(data: &[u8], start: usize) -> bool {
+    next_member(data, start + 1, data.len()).is_some()
+}
+
+/// The first offset in `start..end` where a gzip member appears to start.
+fn next_member(data: &[u8], start: usize, end: usize) -> Option<usize> {
+    const RESERVED_FLAGS: u8 = 0xe0;
+    let searched = &data[start.min(end)..(end + GZIP_MAGIC.len() - 1).min(data.len())];
+    memmem::find_iter(searched, GZIP_MAGIC)
+        .map(|offset| start + offset)
+        .take_while(|&candidate| candidate < end)
+        .find(|&candidate| {
+            data.get(candidate + 3)
+                .is_some_and(|flags| flags & RESERVED_FLAGS == 0)
+                && GzDecoder::new(&data[candidate..])
+                    .take(PROBE_LEN)
+                    .read_to_end(&mut Vec::new())
+                    .is_ok()
+        })
+}
+
+fn decode_segment(data: &[u8], segment: Segment) -> Decoded {
+    let input = &data[segment.start..segment.end];
+    let mut bytes = Vec::new();
+    let result = match segment.kind {
+        SegmentKind::Gzip => MultiGzDecoder::new(input).read_to_end(&mut bytes),
+        SegmentKind::Zstd => zstd::stream::read::Decoder::with_buffer(input)
+            .and_then(|mut decoder| decoder.read_to_end(&mut bytes))
+            .map_err(|error| classify(error, Compression::Zstd, segment.end)),
+        SegmentKind::Trailing => {
+            return Decoded {
+                start: Some(segment.start),
+                end: segment.start,
+                bytes,
+                error: None,
+                trailing: Some(segment.start),
+            };
+        }
+        SegmentKind::Speculative { anchored: true } => {
+            return decode_members(data, segment.start, segment.end);
+        }
+        SegmentKind::Speculative { anchored: false } => {
+            return match next_member(data, segment.start, segment.end) {
+                Some(start) => decode_members(data, start, segment.end),
+                None => Decoded {
+                    start: None,
+                    end: segment.start,
+                    bytes,
+                    error: None,
+                    trailing: None,
+                },
+            };
+        }
+    };
+    Decoded {
+        start: Some(segment.start),
+        end: segment.end,
+        bytes,
+        error: result.err(),
+        trailing: None,
+    }
+}
+
+/// Decode the gzip members from `start` up to the first boundary at or after `end`.
+fn decode_members(data: &[u8], start: usize, end: usize) -> Decoded {
+    let mut decoded = Decoded {
+        start: Some(start),
+        end: start,
+        bytes: Vec::new(),
+        error: None,
+        trailing: None,
+    };
+    while decoded.end < end {
+        let input = &data[decoded.end..];
+        if !Compression::Gzip.starts_member(input) {
+            if decoded.end == 0 {
+                decoded.error = Some(not_compressed(Compression::Gzip));
+            } else {
+                decoded.trailing = Some(decoded.end);
+            }
+            break;
+        }
+        let mut decoder = GzDecoder::new(input);
+        if let Err(error) = decoder.read_to_end(&mut decoded.bytes) {
+            decoded.error = Some(classify(error, Compression::Gzip, data.len()));
+            break;
+        }
+        decoded.end = data.len() - decoder.into_inner().len();
+    }
+    decoded
+}
+
+fn segment_compression(kind: SegmentKind) -> Compression {
+    match kind {
+        SegmentKind::Zstd | SegmentKind::Trailing => Compression::Zstd,
+        SegmentKind::Gzip | SegmentKind::Speculative { .. } => Compression::Gzip,
+    }
+}
+
+/// Report a decoder running out of input, which ends at `len`, as truncation.
+fn classify(error: io::Error, compression: Compression, len: usize) -> io::Error {
+    if error.kind() != io::ErrorKind::UnexpectedEof {
+        return error;
+    }
+    CompressedStreamError::Truncated {
+        compression: compression.name(),
+        offset: len as u64,
+    }
+    .into()
+}
+
+/// The decoding progress shared by the workers and the thread emitting output.
+struct Queue {
+    next: usize,
+    emitted: usize,
+    decoded: BTreeMap<usize, Decoded>,
+    stopped: bool,
+}
+
+/// Decode `segments` on `workers` threads, at most `window` segments ahead of
+/// the output, and pass the output to `emit` in file order until it returns
+/// `false`, an error has been passed, or trailing data is handled as
+/// `trailing` directs.
+fn decode_ordered(
+    data: &[u8],
+    segments: &[Segment],
+    workers: usize,
+    window: usize,
+    trailing: (TrailingDataPolicy, &TrailingDataReport),
+    mut emit: impl FnMut(io::Result<Vec<u8>>) -> bool,
+) {
+    let queue = Mutex::new(Queue {
+        next: 0,
+        emitted: 0,
+        decoded: BTreeMap::new(),
+        stopped: false,
+    });
+    let changed = Condvar::new();
+    let lock = || queue.lock().unwrap_or_else(PoisonError::into_inner);
+
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, segments.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = {
+                    let mut state = lock();
+                    while !state.stopped
+                        && state.next < segments.len()
+                        && state.next >= state.emitted + window.max(1)
+                    {
+                        state = changed.wait(state).unwrap_or_else(PoisonError::into_inner);
+                    }
+                    if state.stopped || state.next == segments.len() {
+                        return;
+                    }
+                    state.next += 1;
+                    state.next - 1
+                };
+                let segment = segments[index];
+                let decoded =
+                    panic::catch_unwind(AssertUnwindSafe(|| decode_segment(data, segment)))
+                        .unwrap_or_else(|_| Decoded {
+                            start: Some(segment.start),
+                            end: segment.start,
+                            bytes: Vec::new(),
+                            error: Some(io::Error::other(CsvIngestError::DecompressWorkerPanicked)),
+                            trailing: None,
+                        });
+                lock().decoded.insert(index, decoded);
+                changed.notify_all();
+            });
+        }
+
+        let mut position = segments.first().map_or(0, |segment| segment.start);
+        for (index, segment) in segments.iter().enumerate() {
+            let decoded = {
+                let mut state = lock();
+                loop {
+                    if let Some(decoded) = state.decoded.remove(&index) {
+                        state.emitted = index + 1;
+                        break decoded;
+                    }
+                    state = changed.wait(state).unwrap_or_else(PoisonError::into_inner);
+                }
+            };
+            changed.notify_all();
+            let decoded = match segment.kind {
+                // A member decoded by an earlier segment spans this one.
+                SegmentKind::Speculative { .. } if position >= segment.end => continue,
+                // The member this segment found is not the one that follows
+                // the previous segment's output, so decode from there instead.
+                SegmentKind::Speculative { .. } if decoded.start != Some(position) => {
+                    decode_members(data, position, segment.end)
+                }
+                _ => decoded,
+            };
+            position = decoded.end;
+            let mut more = decoded.bytes.is_empty() || emit(Ok(decoded.bytes));
+            if let Some(error) = decoded.error {
+                if more {
+                    emit(Err(error));
+                }
+                more = false;
+            }
+            if let Some(offset) = decoded.trailing.filter(|_| more) {
+                let (policy, report) = trailing;
+                match policy {
+                    TrailingDataPolicy::Error => {
+                        let error = CompressedStreamError::TrailingData {
+                            compression: segment_compression(segment.kind).name(),
+                            offset: offset as u64,
+                        };
+                        emit(Err(error.into()));
+                    }
+                    TrailingDataPolicy::Ignore => {}
+                    TrailingDataPolicy::Warn => report.record(offset as u64),
+                }
+                more = false;
+            }
+            if !more {
+                break;
+            }
+        }
+        lock().stopped = true;
+        changed.notify_all();
+    });
+}
+
+/// Recover the typed panic error that workers report through `io::Error`.
+#[cfg(any(test, feature = "fast_local"))]
+fn unwrap_panic(error: io::Error) -> CsvIngestError {
+    match error.get_ref() {
+        Some(inner) if inner.is::<CsvIngestError>() => CsvIngestError::DecompressWorkerPanicked,
+        _ => error.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use std::io::Write;
+
+    const BGZF_EOF: &[u8] =
+        b"\x1f\x8b\x08\x04\0\0\0\0\0\xff\x06\0BC\x02\0\x1b\0\x03\0\0\0\0\0\0\0\0\0";
+
+    fn sample(rows: usize) -> Vec<u8> {
+        let mut data = b"id,name\n".to_vec();
+        for row in 0..rows {
+            data.extend_from_slice(format!("{row},row {}\n", row * 7919 % 1000).as_bytes());
+        }
+        data
+    }
+
+    fn gzip(bytes: &[u8], level: u32) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+        encoder.write_all(bytes).expect("write gzip input");
+        encoder.finish().expect("finish gzip member")
+    }
+
+    fn bgzf(bytes: &[u8], block_len: usize) -> Vec<u8> {
+        let mut file = Vec::new();
+        for block in bytes.chunks(block_len) {
+            let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(block).expect("write block");
+            let deflated = encoder.finish().expect("finish block");
+            let block_size = (18 + deflated.len() + 8 - 1) as u16;
+            file.extend_from_slice(b"\x1f\x8b\x08\x04\0\0\0\0\0\xff\x06\0BC\x02\0");
+            file.extend_from_slice(&block_size.to_le_bytes());
+            file.extend_from_slice(&deflated);
+            file.extend_from_slice(&crc32fast::hash(block).to_le_bytes());
+            file.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        }
+        file.extend_from_slice(BGZF_EOF);
+        file
+    }
+
+    /// Independent zstd frames followed by a seekable-format seek table.
+    fn seekable_zstd(bytes: &[u8], frame_len: usize) -> Vec<u8> {
+        let mut file = Vec::new();
+        let mut entries = Vec::new();
+        for frame in bytes.chunks(frame_len) {
+            let compressed = zstd::encode_all(frame, 3).expect("compress frame");
+            entries.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            entries.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            file.extend_from_slice(&compressed);
+        }
+        let frames = bytes.chunks(frame_len).count() as u32;
+        file.extend_from_slice(&0x184d_2a5e_u32.to_le_bytes());
+        file.extend_from_slice(&(entries.len() as u32 + 9).to_le_bytes());
+        file.extend_from_slice(&entries);
+        file.extend_from_slice(&frames.to_le_bytes());
+        file.push(0);
+        file.extend_from_slice(&0x8f92_eab1_u32.to_le_bytes());
+        file
+    }
+
+    fn decode_all(data: &[u8], compression: Compression, segment_size: usize) -> Vec<Vec<u8>> {
+        (1..=4)
+            .map(|workers| {
+                let config = CsvDecompressConfig {
+                    workers: NonZeroUsize::new(workers),
+                    segment_size,
+                    ..CsvDecompressConfig::default()
+                };
+                decompress(data, compression, &config).expect("decompress")
+            })
+            .collect()
+    }
+
+    fn assert_covers(segments: &[Segment], len: usize) {
+        assert_eq!(segments.first().map(|segment| segment.start), Some(0));
+        assert_eq!(segments.last().map(|segment| segment.end), Some(len));
+        for pair in segments.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+
+    #[test]
+    fn bgzf_blocks_are_planned_from_their_headers() {
+        let expected = sample(2_000);
+        let file = bgzf(&expected, 1_000);
+
+        let blocks = plan(&file, Compression::Gzip, 0);
+        assert_covers(&blocks, file.len());
+        assert_eq!(blocks.len(), expected.len().div_ceil(1_000) + 1);
+        assert!(blocks
+            .iter()
+            .all(|segment| segment.kind == SegmentKind::Gzip));
+        assert_eq!(plan(&file, Compression::Gzip, file.len()).len(), 1);
+
+        for segment_size in [0, 100, 5_000, 1 << 20] {
+            for decoded in decode_all(&file, Compression::Gzip, segment_size) {
+                assert_eq!(decoded, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn zstd_frames_and_seek_tables_are_planned_and_decoded() {
+        let expected = sample(2_000);
+        let file = seekable_zstd(&expected, 1_500);
+
+        let frames = plan(&file, Compression::Zstd, 0);
+        assert_covers(&frames, file.len());
+        assert_eq!(frames.len(), expected.len().div_ceil(1_500) + 1);
+        assert!(frames
+            .iter()
+            .all(|segment| segment.kind == SegmentKind::Zstd));
+
+        for segment_size in [0, 100, 5_000, 1 << 20] {
+            for decoded in decode_all(&file, Compression::Zstd, segment_size) {
+                assert_eq!(decoded, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn concatenated_gzip_members_are_found_speculatively() {
+        // A stored member whose data contains a complete gzip member, which
+        // a worker may mistake for the start of the next real one.
+        let decoy = gzip(b"decoy", 6);
+        let mut first = sample(300);
+        first.extend_from_slice(&decoy);
+        first.extend_from_slice(b"9,tail\n");
+        let members = [first, sample(50), sample(1_000), Vec::new(), sample(10)];
+        let file: Vec<u8> = members
+            .iter()
+            .enumerate()
+            .flat_map(|(index, member)| gzip(member, if index == 0 { 0 } else { 6 }))
+            .collect();
+        let expected = members.concat();
+
+        let segments = plan(&file, Compression::Gzip, 64);
+        assert_covers(&segments, file.len());
+        assert_eq!(
+            segments[0].kind,
+            SegmentKind::Speculative { anchored: true }
+        );
+        for segment_size in [0, 1, 64, 997, 4_096, 1 << 20] {
+            for decoded in decode_all(&file, Compression::Gzip, segment_size) {
+                assert_eq!(decoded, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn a_single_gzip_member_is_one_segment() {
+        let expected = sample(1_000);
+        let file = gzip(&expected, 6);
+
+        assert_eq!(
+            plan(&file, Compression::Gzip, 64),
+            [Segment {
+                start: 0,
+                end: file.len(),
+                kind: SegmentKind::Speculative { anchored: true },
+            }]
+        );
+        for decoded in decode_all(&file, Compression::Gzip, 64) {
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn corrupt_and_truncated_input_fails() {
+        let data = sample(1_000);
+        let truncated_gzip = [gzip(&data, 6), gzip(&data, 6)].concat();
+        let truncated_bgzf = bgzf(&data, 1_000);
+        let truncated_zstd = seekable_zstd(&data, 1_500);
+        let mut corrupt_zstd = seekable_zstd(&data, 1_500);
+        corrupt_zstd[20] ^= 0xff;
+
+        for (file, compression, truncated) in [
+            (
+                &truncated_gzip[..truncated_gzip.len() - 5],
+                Compression::Gzip,
+                true,
+            ),
+            (
+                &truncated_bgzf[..truncated_bgzf.len() - 40],
+                Compression::Gzip,
+                true,
+            ),
+            (&truncated_zstd[..200], Compression::Zstd, true),
+            (&corrupt_zstd[..], Compression::Zstd, false),
+        ] {
+            for segment_size in [0, 64, 1 << 20] {
+                let config = CsvDecompressConfig {
+                    workers: NonZeroUsize::new(3),
+                    segment_size,
+                    ..CsvDecompressConfig::default()
+                };
+                let error =
+                    decompress(file, compression, &config).expect_err("corrupt input must fail");
+                if truncated {
+                    let expected = CompressedStreamError::Truncated {
+                        compression: compression.name(),
+                        offset: file.len() as u64,
+                    };
+                    assert!(
+                        matches!(error, CsvIngestError::CompressedStream(found) if found == expected),
+                        "{error:?}"
+                    );
+                } else {
+                    assert!(matches!(error, CsvIngestError::Io(_)), "{error:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trailing_data_follows_the_policy() {
+        let data = sample(1_000);
+        let gzip_file = [gzip(&data[..5_000], 6), gzip(&data[5_000..], 6)].concat();
+        let bgzf_file = bgzf(&data, 1_000);
+        let zstd_file = seekable_zstd(&data, 1_500);
+
+        for (file, compression) in [
+            (gzip_file, Compression::Gzip),
+            (bgzf_file, Compression::Gzip),
+            (zstd_file, Compression::Zstd),
+        ] {
+            let mut padded = file.clone();
+            padded.extend_from_slice(b"\0\0\0\0garbage");
+            for segment_size in [0, 64, 1 << 20] {
+                let config = |trailing_data| CsvDecompressConfig {
+                    workers: NonZeroUsize::new(3),
+                    segment_size,
+                    trailing_data,
+                };
+                let error = decompress(&padded, compression, &config(TrailingDataPolicy::Error))
+                    .expect_err("trailing data must fail");
+                let expected = CompressedStreamError::TrailingData {
+                    compression: compression.name(),
+                    offset: file.len() as u64,
+                };
+                assert!(
+                    matches!(error, CsvIngestError::CompressedStream(found) if found == expected),
+                    "{error:?}"
+                );
+                for policy in [TrailingDataPolicy::Ignore, TrailingDataPolicy::Warn] {
+                    let decoded =
+                        decompress(&padded, compression, &config(policy)).expect("decompress");
+                    assert_eq!(decoded, data);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_input_decodes_to_nothing() {
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            assert!(plan(&[], compression, 64).is_empty());
+            let decoded = decompress(&[], compression, &CsvDecompressConfig::default())
+                .expect("decompress empty input");
+            assert!(decoded.is_empty());
+        }
+    }
+}
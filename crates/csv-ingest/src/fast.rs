@@ -1,25 +1,245 @@
+use crate::filter::CompiledFilters;
+#[cfg(feature = "parallel_decompress")]
+use crate::io::detect_compression;
+use crate::projection::CompiledProjection;
+use crate::schema::CompiledSchema;
+#[cfg(feature = "parallel_decompress")]
+use crate::{CsvDecompressConfig, CsvMeta};
 use crate::{
-    CsvHeaderMode, CsvIngestError, CsvIngestSummary, CsvOptions, CsvResult, CsvTerminator,
+    CsvDiagnostic, CsvFilter, CsvHeaderMode, CsvIngestError, CsvIngestSummary, CsvOptions,
+    CsvProjection, CsvQuarantine, CsvResult, CsvSchema, CsvSelector, CsvTerminator, Position,
 };
 use crc32fast::Hasher as Crc32;
-use memchr::{memchr, memchr2, memchr_iter};
-use memmap2::MmapOptions;
+use memchr::{memchr2, memchr3, memchr_iter};
 use std::fs::File;
+use std::num::NonZeroUsize;
 use std::path::Path;
-use std::thread;
+
+mod pool;
+#[cfg(feature = "arrow")]
+mod record_batch;
+mod records;
+mod source;
+mod split;
+
+use self::pool::{run_all, ScopedThreads};
+pub use self::pool::{FastLocalPool, FastLocalTask};
+#[cfg(feature = "arrow")]
+pub use self::record_batch::fast_local_record_batches;
+#[cfg(feature = "parquet")]
+pub(crate) use self::record_batch::record_batches_each;
+pub use self::records::{
+    fast_local_fold, fast_local_records, fast_local_visit, FastLocalRecord, FastLocalRecords,
+    FastLocalVisitor,
+};
+#[cfg(feature = "parallel_decompress")]
+use self::source::Decompressed;
+pub use self::source::FastLocalAccess;
+use self::source::{FileData, Input};
+use self::split::{next_record_terminator, FieldBuf, Row};
 
 const FIELD_SEPARATOR: u8 = 0x1f;
 const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
 
-struct ChunkResult {
+struct ChunkResult<T> {
     row_count: u64,
+    filtered: u64,
+    rejected: Vec<RejectedRow>,
     crc: Option<Crc32>,
+    output: T,
+    /// The absolute start of a record the chunk's end cut inside a quoted
+    /// field, which the next chunk must be reprocessed from.
+    unfinished: Option<usize>,
+}
+
+/// Where the window of the input in memory starts.
+#[derive(Clone, Copy, Default)]
+struct WindowOrigin {
+    /// The absolute offset of the window's first byte: zero, or the last
+    /// field byte before the first record the window is processed from.
+    start: usize,
+    /// The number of line feeds before `start`.
+    lines: u64,
 }
 
-/// Fast local parser for uncompressed UTF-8 CSV files using mmap and parallel chunking.
+/// A record rejected by a worker, with its absolute byte range in the file.
+struct RejectedRow {
+    start: usize,
+    end: usize,
+    error: CsvIngestError,
+}
+
+/// Optional fast-local behavior beyond the shared [`CsvOptions`].
 ///
-/// This specialized path accepts only unquoted CSV. A quote byte anywhere in the
-/// parsed portion of the file is rejected instead of being interpreted incorrectly.
+/// [`fast_local_process`] uses the default configuration.
+#[derive(Default)]
+pub struct FastLocalConfig<'a> {
+    /// The number of chunks the file is split into, and so the number of
+    /// tasks that parse it. Defaults to the parallelism of `pool`, or to the
+    /// available parallelism.
+    pub workers: Option<NonZeroUsize>,
+    /// The smallest chunk worth its own task, in bytes. Smaller files are
+    /// split into fewer chunks than `workers`; zero sets no minimum.
+    pub min_chunk_size: usize,
+    /// Runs the parsing tasks, such as a shared `rayon::ThreadPool` with the
+    /// `rayon` feature. Without a pool, each task gets a scoped thread.
+    pub pool: Option<&'a dyn FastLocalPool>,
+    /// Whether a file is mapped or copied into memory, and so what happens
+    /// when another process truncates it during the parse.
+    pub access: FastLocalAccess,
+    /// How a file mapped with `FastLocalAccess::Map` will be read, passed to
+    /// the kernel on Unix.
+    pub advice: FastLocalAdvice,
+    /// The most bytes of a file to map or read at once. A larger file is
+    /// parsed one window at a time, each split into chunks as a whole file
+    /// would be, and a record cut by a window's end is carried over to the
+    /// next, so counts and the CRC are unchanged. A window grows past this size
+    /// only to hold a single longer record. Defaults to 8 MiB per worker, or
+    /// to the whole file when it is mapped with `FastLocalAccess::Map` and its
+    /// outputs are not consumed while it is parsed, as they are by
+    /// `fast_local_records`; data already in memory is never windowed.
+    pub window_size: Option<NonZeroUsize>,
+    /// Checks that every byte of the body is UTF-8, on the workers and with
+    /// SIMD where the CPU has it, failing with
+    /// `CsvIngestError::InvalidUtf8Row` at the first invalid byte. Headers
+    /// are always checked; without this, data rows are not.
+    pub validate_utf8: bool,
+    /// Names the input in record-level errors, as
+    /// `CsvParser::with_source_name` does. Defaults to the file name when the
+    /// input is opened by path.
+    pub source_name: Option<&'a str>,
+    /// Receives the original bytes of rows rejected by a skipping
+    /// `CsvOptions::error_policy`, in file order, after all workers finish.
+    pub quarantine: Option<&'a mut dyn CsvQuarantine>,
+    /// Validates every row, as `CsvParser::with_schema` does.
+    pub schema: Option<&'a CsvSchema>,
+    /// Keeps only the selected columns, as `CsvParser::with_projection` does.
+    ///
+    /// The summary lists the projected headers and the CRC covers only the
+    /// projected fields, in selection order. Unselected fields are not trimmed
+    /// or hashed, and in flexible mode a row is scanned only up to the last
+    /// column that is selected, required, or validated by the schema.
+    pub projection: Option<&'a CsvProjection>,
+    /// Skips rows that any filter rejects, as `CsvParser::with_filter` does.
+    ///
+    /// Filtered rows are validated for shape but are not checked against the
+    /// schema, hashed, or counted in `row_count`.
+    pub filters: &'a [CsvFilter],
+}
+
+/// How a fast-local file will be read, passed to the kernel with `madvise` on
+/// Unix and ignored elsewhere. Hints never change results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FastLocalAdvice {
+    /// Give no hint.
+    #[default]
+    Normal,
+    /// Each worker reads its chunk front to back, so read ahead aggressively.
+    Sequential,
+    /// Start reading the whole file into the page cache now.
+    WillNeed,
+}
+
+impl<'a> FastLocalConfig<'a> {
+    fn pool(&self) -> &'a dyn FastLocalPool {
+        self.pool.unwrap_or(&ScopedThreads)
+    }
+
+    fn worker_count(&self) -> usize {
+        self.workers
+            .map_or_else(|| self.pool().parallelism(), usize::from)
+    }
+
+    /// `window_size`, or 8 MiB per worker when it is unset.
+    fn bounded_window_size(&self) -> NonZeroUsize {
+        self.window_size.unwrap_or_else(|| {
+            let size = self
+                .worker_count()
+                .max(1)
+                .saturating_mul(STREAM_WINDOW_PER_WORKER);
+            NonZeroUsize::new(size).unwrap_or(NonZeroUsize::MIN)
+        })
+    }
+}
+
+/// Collects the accepted rows of a file, one [`ChunkSink`] per worker.
+trait RowSink: Sync {
+    type Chunk: ChunkSink;
+
+    /// Called once the headers and row plan are resolved, with the body to be
+    /// processed. A sink that reads fields beyond `plan.field_limit` raises it.
+    fn prepare(&mut self, headers: &[String], plan: &mut RowPlan<'_>, body: &[u8])
+        -> CsvResult<()>;
+
+    fn chunk(&self) -> Self::Chunk;
+}
+
+/// Receives the accepted rows of one chunk, in order.
+trait ChunkSink: Send {
+    type Output: Send;
+
+    /// Take one accepted row, which starts at the absolute byte `offset` and
+    /// is the chunk's `row_number`th row.
+    fn accept(&mut self, row: &Row<'_>, offset: usize, row_number: u64) -> CsvResult<()>;
+
+    fn finish(self) -> CsvResult<Self::Output>;
+}
+
+/// What one chunk's sink produces.
+type SinkOutput<S> = <<S as RowSink>::Chunk as ChunkSink>::Output;
+
+impl RowSink for () {
+    type Chunk = ();
+
+    fn prepare(&mut self, _: &[String], _: &mut RowPlan<'_>, _: &[u8]) -> CsvResult<()> {
+        Ok(())
+    }
+
+    fn chunk(&self) {}
+}
+
+impl ChunkSink for () {
+    type Output = ();
+
+    fn accept(&mut self, _: &Row<'_>, _: usize, _: u64) -> CsvResult<()> {
+        Ok(())
+    }
+
+    fn finish(self) -> CsvResult<()> {
+        Ok(())
+    }
+}
+
+/// The per-row work shared by every worker, resolved once against the headers.
+struct RowPlan<'a> {
+    options: &'a CsvOptions,
+    /// Required columns in the order the streaming parser checks them.
+    required: Vec<(usize, String)>,
+    filters: CompiledFilters,
+    schema: Option<CompiledSchema>,
+    /// Selected field indices paired with their output slots, by field index.
+    projection: Option<Vec<(usize, usize)>>,
+    projected_width: usize,
+    /// The width every row must have, unless `options.flexible` is set.
+    expected_width: Option<usize>,
+    /// How many leading fields of a flexible row need to be split.
+    field_limit: Option<usize>,
+    validate_utf8: bool,
+}
+
+/// Fast local parser for uncompressed UTF-8 CSV files using parallel reads and chunking.
+///
+/// Quoted fields, doubled quotes, `options.escape`, and delimiters and
+/// terminators inside quoted fields are read as the streaming parser reads
+/// them. Chunk boundaries are placed by the parity of the unescaped quote
+/// bytes before them; when a quote inside an unquoted field misleads that
+/// guess, the rest of the window is processed again in parallel from the last
+/// record start, so the summary and CRC never depend on the number of workers.
+/// Record-level errors report the same [`Position`] as the streaming parser and
+/// use the file name as their source name. `options.error_policy` applies as in
+/// the streaming parser, and rejected rows do not contribute to the CRC.
+/// Only the header is checked for UTF-8 unless
+/// [`FastLocalConfig::validate_utf8`] is set.
 pub fn fast_local_process(
     path: &Path,
     required_headers: &[&str],
@@ -27,19 +247,161 @@ pub fn fast_local_process(
     verify_crc: bool,
     limit_rows: Option<u64>,
 ) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
-    let workers = thread::available_parallelism()
-        .map(usize::from)
-        .unwrap_or(1);
-    fast_local_process_with_workers(
+    fast_local_process_with_config(
         path,
         required_headers,
         options,
         verify_crc,
         limit_rows,
-        workers,
+        FastLocalConfig::default(),
+    )
+}
+
+/// Run [`fast_local_process`] with additional [`FastLocalConfig`] behavior.
+pub fn fast_local_process_with_config(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
+    process_path(
+        path,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        config,
+    )
+}
+
+/// Run [`fast_local_process_with_config`] over data already in memory.
+///
+/// The data is parsed in place, with the same chunking, validation, and CRC as
+/// a mapped file, and error positions are offsets into `data`. Errors are named
+/// after `config.source_name`, and `config.advice` is ignored.
+pub fn fast_local_process_bytes(
+    data: &[u8],
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
+    let source_name = config.source_name;
+    process_into(
+        |_| Ok(data),
+        source_name,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        config,
+        &mut (),
     )
+    .map(|(summary, crc, _)| (summary, crc))
 }
 
+/// Run [`fast_local_process_with_config`] over an open file, such as a
+/// `memfd` or a file opened with custom flags.
+///
+/// The file is read from its start, whatever its current position, and is
+/// checked for changes through the handle. Errors are named after
+/// `config.source_name`.
+pub fn fast_local_process_file(
+    file: &File,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
+    let source_name = config.source_name;
+    process_into(
+        |config| FileData::new(file, config, source_name),
+        source_name,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        config,
+        &mut (),
+    )
+    .map(|(summary, crc, _)| (summary, crc))
+}
+
+/// Run [`fast_local_process_with_config`] over a gzip or zstd file,
+/// decompressing its independent members or frames in parallel.
+///
+/// Compression is selected by the file extension, as [`crate::reader_from_path`]
+/// does, and an uncompressed file is processed as by
+/// [`fast_local_process_with_config`]. The compressed file is read into
+/// memory, and decoded segments are parsed as they arrive, in windows of
+/// `config.window_size`, 8 MiB per worker unless it is set, while
+/// `decompress.workers` threads decode the segments ahead of them. Positions
+/// in record-level errors are offsets into the decompressed data, as the
+/// streaming parser reports them.
+#[cfg(feature = "parallel_decompress")]
+pub fn fast_local_process_compressed(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+    decompress: &CsvDecompressConfig,
+) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
+    let source_name = config
+        .source_name
+        .or_else(|| path.file_name().and_then(|name| name.to_str()));
+    let meta = CsvMeta {
+        name_hint: path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        ..CsvMeta::default()
+    };
+    let Some(compression) = detect_compression(&meta)? else {
+        return process_path(
+            path,
+            required_headers,
+            options,
+            verify_crc,
+            limit_rows,
+            config,
+        );
+    };
+    let config = streamed(config);
+    let compressed = FileData::open(path, &config, source_name)?;
+    let contents = compressed.window(0, usize::MAX, &config)?;
+    let contents = &*contents;
+    let (sender, segments) = std::sync::mpsc::sync_channel(decompress.worker_count());
+    std::thread::scope(|scope| {
+        let decoding = scope.spawn(move || {
+            crate::decompress::decompress_each(contents, compression, decompress, |segment| {
+                sender.send(segment).is_ok()
+            });
+        });
+        let result = process_into(
+            |config| Ok(Decompressed::new(&compressed, segments, config)),
+            source_name,
+            required_headers,
+            options,
+            verify_crc,
+            limit_rows,
+            config,
+            &mut (),
+        );
+        decoding
+            .join()
+            .map_err(|_| CsvIngestError::DecompressWorkerPanicked)?;
+        result.map(|(summary, crc, _)| (summary, crc))
+    })
+}
+
+#[cfg(test)]
 fn fast_local_process_with_workers(
     path: &Path,
     required_headers: &[&str],
@@ -47,6 +409,157 @@ fn fast_local_process_with_workers(
     verify_crc: bool,
     limit_rows: Option<u64>,
     workers: usize,
+) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
+    process_path(
+        path,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        FastLocalConfig {
+            workers: NonZeroUsize::new(workers),
+            ..FastLocalConfig::default()
+        },
+    )
+}
+
+fn process_path(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
+    process_path_into(
+        path,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        config,
+        &mut (),
+    )
+    .map(|(summary, crc, _)| (summary, crc))
+}
+
+/// Process `path`, passing the accepted rows of each chunk to `sink` and
+/// returning each chunk's output in file order.
+fn process_path_into<S: RowSink>(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+    sink: &mut S,
+) -> CsvResult<(CsvIngestSummary, Option<u32>, Vec<SinkOutput<S>>)> {
+    let mut outputs = Vec::new();
+    let (summary, crc) = process_path_each(
+        path,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        config,
+        sink,
+        |output| {
+            outputs.push(output);
+            Ok(())
+        },
+    )?;
+    Ok((summary, crc, outputs))
+}
+
+/// Process `path` as [`process_path_into`] does, passing each chunk's output
+/// to `each` in file order as soon as the chunks before it are merged.
+#[allow(clippy::too_many_arguments)]
+fn process_path_each<S: RowSink>(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+    sink: &mut S,
+    each: impl FnMut(SinkOutput<S>) -> CsvResult<()>,
+) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
+    let source_name = source_name(path, config.source_name);
+    process_each(
+        |config| FileData::open(path, config, source_name),
+        source_name,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        config,
+        sink,
+        each,
+    )
+}
+
+/// The bytes per worker in each window of a copied file, or of a run whose
+/// outputs are consumed while it parses, unless
+/// `FastLocalConfig::window_size` is set.
+const STREAM_WINDOW_PER_WORKER: usize = 8 << 20;
+
+/// Bound the windows of `config` so that a run holds only a few windows of
+/// output at once.
+pub(crate) fn streamed(mut config: FastLocalConfig<'_>) -> FastLocalConfig<'_> {
+    config.window_size = Some(config.bounded_window_size());
+    config
+}
+
+/// The name errors report for `path`, unless one is configured.
+fn source_name<'a>(path: &'a Path, configured: Option<&'a str>) -> Option<&'a str> {
+    configured.or_else(|| path.file_name().and_then(|name| name.to_str()))
+}
+
+/// Process the bytes `load` returns once the configuration has been checked,
+/// naming errors after `source_name`, and check that they did not change.
+#[allow(clippy::too_many_arguments)]
+fn process_into<S: RowSink, D: Input>(
+    load: impl FnOnce(&FastLocalConfig<'_>) -> CsvResult<D>,
+    source_name: Option<&str>,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+    sink: &mut S,
+) -> CsvResult<(CsvIngestSummary, Option<u32>, Vec<SinkOutput<S>>)> {
+    let mut outputs = Vec::new();
+    let (summary, crc) = process_each(
+        load,
+        source_name,
+        required_headers,
+        options,
+        verify_crc,
+        limit_rows,
+        config,
+        sink,
+        |output| {
+            outputs.push(output);
+            Ok(())
+        },
+    )?;
+    Ok((summary, crc, outputs))
+}
+
+/// Process as [`process_into`] does, passing each chunk's output to `each` in
+/// file order as soon as the chunks before it are merged, before later
+/// windows are read.
+#[allow(clippy::too_many_arguments)]
+fn process_each<S: RowSink, D: Input>(
+    load: impl FnOnce(&FastLocalConfig<'_>) -> CsvResult<D>,
+    source_name: Option<&str>,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    verify_crc: bool,
+    limit_rows: Option<u64>,
+    mut config: FastLocalConfig<'_>,
+    sink: &mut S,
+    mut each: impl FnMut(SinkOutput<S>) -> CsvResult<()>,
 ) -> CsvResult<(CsvIngestSummary, Option<u32>)> {
     options.validate()?;
     if options.headers == CsvHeaderMode::Absent && !required_headers.is_empty() {
@@ -54,187 +567,417 @@ fn fast_local_process_with_workers(
             "required headers cannot be validated when headers are absent".to_string(),
         ));
     }
-
-    let file = File::open(path)?;
-    let metadata = file.metadata()?;
-    let len = metadata.len() as usize;
-    // SAFETY: the map is read-only and remains bounded by the file length captured above.
-    let mmap = unsafe { MmapOptions::new().map(&file)? };
-    let data: &[u8] = &mmap[..];
-
-    let data_start = usize::from(data.starts_with(UTF8_BOM)) * UTF8_BOM.len();
-    if data_start == len {
-        if options.headers == CsvHeaderMode::Present {
-            if let Some(required) = required_headers.first() {
-                return Err(CsvIngestError::MissingHeader((*required).to_string()));
-            }
-        }
-        return Ok((
-            CsvIngestSummary {
-                row_count: 0,
-                headers: vec![],
-            },
-            verify_crc.then_some(0),
+    let schema_columns = config.schema.map_or(&[][..], |schema| &schema.columns);
+    if options.headers == CsvHeaderMode::Absent && !schema_columns.is_empty() {
+        return Err(CsvIngestError::UnsupportedDialect(
+            "schema columns cannot be resolved when headers are absent".to_string(),
         ));
     }
+    let projection = config.projection;
+    let projected = projection.map_or(&[][..], |projection| &projection.columns);
+    let mut selectors = projected
+        .iter()
+        .chain(config.filters.iter().map(CsvFilter::column));
+    if options.headers == CsvHeaderMode::Absent
+        && selectors
+            .clone()
+            .any(|selector| matches!(selector, CsvSelector::Header(_)))
+    {
+        return Err(CsvIngestError::UnsupportedDialect(
+            "columns cannot be selected by header when headers are absent".to_string(),
+        ));
+    }
+    let first_required = required_headers
+        .first()
+        .map(|header| (*header).to_string())
+        .or_else(|| {
+            selectors.next().map(|selector| match selector {
+                CsvSelector::Header(name) => name.clone(),
+                CsvSelector::Index(index) => format!("column {index}"),
+            })
+        })
+        .or_else(|| schema_columns.first().map(|column| column.name.clone()));
+
+    let loaded = load(&config)?;
+    let result = (|| {
+        let window_size = loaded.window_size().unwrap_or(usize::MAX);
+        // The first window grows until it holds a whole record, so that the
+        // header and the width of a headerless file are never cut.
+        let holds_record = |data: &[u8]| {
+            let data_start = usize::from(data.starts_with(UTF8_BOM)) * UTF8_BOM.len();
+            data.len() >= UTF8_BOM.len()
+                && next_nonempty_record(data, data_start, data.len(), options)
+                    .is_some_and(|(_, _, next)| next < data.len())
+        };
+        // A window shorter than requested ends the input.
+        let mut requested = window_size;
+        let mut window = loaded.window(0, requested, &config)?;
+        while window.len() == requested && !holds_record(&window) {
+            requested = requested.saturating_mul(2);
+            drop(window);
+            window = loaded.window(0, requested, &config)?;
+        }
+        let data: &[u8] = &window;
+        let len = data.len();
+
+        let data_start = usize::from(data.starts_with(UTF8_BOM)) * UTF8_BOM.len();
+        if data_start == len {
+            if options.headers == CsvHeaderMode::Present {
+                if let Some(required) = first_required {
+                    return Err(CsvIngestError::MissingHeader(required));
+                }
+            }
+            return Ok((CsvIngestSummary::default(), verify_crc.then_some(0)));
+        }
 
-    let (headers, body_start, expected_width) = if options.headers == CsvHeaderMode::Present {
-        let Some((header_start, header_end, body_start)) =
-            next_nonempty_record(data, data_start, len, options.terminator)
-        else {
-            if let Some(required) = required_headers.first() {
-                return Err(CsvIngestError::MissingHeader((*required).to_string()));
+        let mut fields = FieldBuf::default();
+        let (headers, body_start, expected_width) = if options.headers == CsvHeaderMode::Present {
+            let Some((header_start, header_end, body_start)) =
+                next_nonempty_record(data, data_start, len, options)
+            else {
+                if let Some(required) = first_required {
+                    return Err(CsvIngestError::MissingHeader(required));
+                }
+                return Ok((CsvIngestSummary::default(), verify_crc.then_some(0)));
+            };
+            let header = fields.split(&data[header_start..header_end], options, None);
+            let headers = parse_header(&header, options.trims_headers())?;
+            let expected_width = Some(headers.len());
+            (headers, body_start, expected_width)
+        } else {
+            let expected_width = (!options.flexible)
+                .then(|| next_nonempty_record(data, data_start, len, options))
+                .flatten()
+                .map(|(record_start, record_end, _)| {
+                    fields
+                        .split(&data[record_start..record_end], options, None)
+                        .len()
+                });
+            (Vec::new(), data_start, expected_width)
+        };
+
+        let mut required = required_headers
+            .iter()
+            .map(|required| {
+                headers
+                    .iter()
+                    .position(|header| header == required)
+                    .map(|index| (index, (*required).to_string()))
+                    .ok_or_else(|| CsvIngestError::MissingHeader((*required).to_string()))
+            })
+            .collect::<CsvResult<Vec<_>>>()?;
+        let mut require = |index: usize, header: &str| {
+            if !required.iter().any(|(required, _)| *required == index) {
+                required.push((index, header.to_string()));
             }
-            return Ok((
-                CsvIngestSummary {
-                    row_count: 0,
-                    headers: vec![],
-                },
-                verify_crc.then_some(0),
-            ));
         };
-        let raw_header = &data[header_start..header_end];
-        reject_quotes(raw_header, header_start, options)?;
-        let headers = parse_header(raw_header, options.delimiter, options.trims_headers())?;
-        let expected_width = Some(headers.len());
-        (headers, body_start, expected_width)
-    } else {
-        let expected_width = (!options.flexible)
-            .then(|| next_nonempty_record(data, data_start, len, options.terminator))
-            .flatten()
-            .map(|(record_start, record_end, _)| {
-                memchr_iter(options.delimiter, &data[record_start..record_end]).count() + 1
-            });
-        (Vec::new(), data_start, expected_width)
-    };
 
-    let required_fields = required_headers
-        .iter()
-        .map(|required| {
-            headers
+        let projection = projection
+            .map(|projection| {
+                CompiledProjection::new(
+                    projection,
+                    (options.headers == CsvHeaderMode::Present).then_some(&headers[..]),
+                )
+            })
+            .transpose()?;
+        if let Some(projection) = &projection {
+            for (index, name) in projection.columns() {
+                require(*index, name);
+            }
+        }
+        let filters = CompiledFilters::new(
+            config.filters,
+            (options.headers == CsvHeaderMode::Present).then_some(&headers[..]),
+        )?;
+        for filter in filters.filters() {
+            require(filter.index, &filter.name);
+        }
+        let schema = config
+            .schema
+            .map(|schema| CompiledSchema::new(schema, &headers))
+            .transpose()?;
+        if let Some(schema) = &schema {
+            for column in schema.columns() {
+                require(column.index, &column.name);
+            }
+        }
+
+        let field_limit = (options.flexible && (projection.is_some() || !verify_crc)).then(|| {
+            required
                 .iter()
-                .position(|header| header == required)
-                .map(|index| (index, (*required).to_string()))
-                .ok_or_else(|| CsvIngestError::MissingHeader((*required).to_string()))
-        })
-        .collect::<CsvResult<Vec<_>>>()?;
-    let required_field = required_fields.into_iter().max_by_key(|(index, _)| *index);
-    let required_field = required_field
-        .as_ref()
-        .map(|(index, header)| (*index, header.as_str()));
-
-    let body_end = limited_body_end(data, body_start, len, options.terminator, limit_rows);
-    let bounds = chunk_bounds(data, body_start, body_end, options.terminator, workers);
-
-    let chunk_results = thread::scope(|scope| -> CsvResult<Vec<CsvResult<ChunkResult>>> {
-        let mut handles = Vec::with_capacity(bounds.len().saturating_sub(1));
-        for window in bounds.windows(2) {
-            let start = window[0];
-            let end = window[1];
-            let slice = &data[start..end];
-            handles.push(scope.spawn(move || {
+                .map(|(index, _)| index + 1)
+                .max()
+                .unwrap_or_default()
+        });
+        let headers = match &projection {
+            Some(projection) if options.headers == CsvHeaderMode::Present => projection
+                .columns()
+                .iter()
+                .map(|(_, name)| name.clone())
+                .collect(),
+            Some(_) => Vec::new(),
+            None => headers,
+        };
+        let mut plan = RowPlan {
+            options,
+            required,
+            filters,
+            schema,
+            projected_width: projection
+                .as_ref()
+                .map_or(0, |projection| projection.columns().len()),
+            projection: projection.map(|projection| {
+                let mut slots: Vec<_> = projection
+                    .columns()
+                    .iter()
+                    .enumerate()
+                    .map(|(slot, (index, _))| (*index, slot))
+                    .collect();
+                slots.sort_unstable();
+                slots
+            }),
+            expected_width,
+            field_limit,
+            validate_utf8: config.validate_utf8,
+        };
+
+        let mut origin = WindowOrigin::default();
+        let mut start = body_start;
+        let mut remaining = limit_rows;
+        let (mut body_end, mut window_rows) =
+            limited_body_end(data, start, len, options, remaining);
+        sink.prepare(&headers, &mut plan, &data[start..body_end])?;
+        let plan = &plan;
+        let sink = &*sink;
+        let pool = config.pool();
+        let header_records = u64::from(options.headers == CsvHeaderMode::Present);
+        let mut rows_scanned = 0u64;
+        let mut filtered_rows = 0u64;
+        let mut rejected_rows = 0u64;
+        let mut diagnostics = Vec::new();
+        let mut combined_crc = verify_crc.then(Crc32::new);
+        loop {
+            let data: &[u8] = &window;
+            let last = data.len() < requested || remaining == Some(window_rows);
+            let process = |start: usize, end: usize| {
                 process_chunk(
-                    slice,
-                    start,
-                    options,
-                    required_field,
-                    expected_width,
+                    &data[start..end],
+                    origin.start + start,
+                    last && end == body_end,
+                    plan,
                     verify_crc,
+                    sink.chunk(),
                 )
-            }));
-        }
-
-        handles
-            .into_iter()
-            .map(|handle| {
-                handle
-                    .join()
-                    .map_err(|_| CsvIngestError::FastLocalWorkerPanicked)
-            })
-            .collect()
-    })?;
+            };
+            let locate = |error, row_offset| {
+                locate_error(
+                    error,
+                    row_offset,
+                    header_records,
+                    &data[..body_end],
+                    origin,
+                    data_start,
+                    options,
+                )
+                .with_source_name(source_name)
+            };
+            let mut unfinished = None;
+            let mut resume = Some(start - origin.start);
+            let mut resumed = false;
+            while let Some(resume_at) = resume.take() {
+                // A resumed round gives its first chunk the whole cut record,
+                // so that every round moves past at least one record.
+                let split_at = if resumed {
+                    next_record_terminator(data, resume_at, body_end, options)
+                        .map_or(body_end, |(_, next)| next)
+                } else {
+                    resume_at
+                };
+                let mut bounds = chunk_bounds(
+                    data,
+                    split_at,
+                    body_end,
+                    options,
+                    config.worker_count(),
+                    config.min_chunk_size,
+                    pool,
+                );
+                bounds[0] = resume_at;
+                if bounds.len() == 1 && resume_at < body_end {
+                    bounds.push(body_end);
+                }
+                let chunk_results = run_all(
+                    pool,
+                    bounds
+                        .windows(2)
+                        .map(|pair| move || process(pair[0], pair[1])),
+                );
+                for result in chunk_results {
+                    // The previous chunk ended inside a quoted field, so this
+                    // chunk and the ones after it did not start at records. The
+                    // rest of the window is split again from the cut record.
+                    if let Some(cut) = unfinished.take() {
+                        resume = Some(cut - origin.start);
+                        resumed = true;
+                        break;
+                    }
+                    let result = result
+                        .ok_or(CsvIngestError::FastLocalWorkerPanicked)?
+                        .map_err(|error| locate(error, rows_scanned))?;
+                    unfinished = result.unfinished;
+                    for rejected in result.rejected {
+                        let error = locate(rejected.error, rows_scanned);
+                        rejected_rows += 1;
+                        if diagnostics.len() < options.max_diagnostics {
+                            diagnostics.push(CsvDiagnostic::new(&error));
+                        }
+                        if let Some(quarantine) = config.quarantine.as_mut() {
+                            quarantine.quarantine(
+                                &data[rejected.start - origin.start..rejected.end - origin.start],
+                                error.position(),
+                                &error,
+                            )?;
+                        }
+                        options
+                            .error_policy
+                            .check_count(rejected_rows, error.row().unwrap_or_default())?;
+                    }
+                    rows_scanned += result.row_count;
+                    filtered_rows += result.filtered;
+                    if let (Some(combined), Some(chunk_crc)) = (&mut combined_crc, result.crc) {
+                        combined.combine(&chunk_crc);
+                    }
+                    each(result.output)?;
+                }
+            }
+            if last {
+                break;
+            }
 
-    let mut row_count = 0u64;
-    let mut combined_crc = verify_crc.then(Crc32::new);
-    for result in chunk_results {
-        let result = result.map_err(|error| offset_row(error, row_count))?;
-        row_count += result.row_count;
-        if let (Some(combined), Some(chunk_crc)) = (&mut combined_crc, result.crc) {
-            combined.combine(&chunk_crc);
+            // The record the window's end cut is carried over to the next
+            // window, which starts at the last field byte before it so that
+            // its position can still be found.
+            let window_end = origin.start + data.len();
+            start = unfinished.unwrap_or(window_end);
+            let from = data_start.max(origin.start) - origin.start;
+            let next = data[from..start - origin.start]
+                .iter()
+                .rposition(|&byte| !options.is_terminator(byte))
+                .map_or(origin.start, |last| origin.start + from + last);
+            origin.lines += memchr_iter(b'\n', &data[..next - origin.start]).count() as u64;
+            origin.start = next;
+            // A record longer than a window grows the next one until it fits.
+            let mut size = window_size;
+            while next.saturating_add(size) <= window_end {
+                size = size.saturating_mul(2);
+            }
+            remaining = remaining.map(|remaining| remaining - window_rows);
+            drop(window);
+            requested = next.saturating_add(size) - next;
+            window = loaded.window(next, next + requested, &config)?;
+            (body_end, window_rows) =
+                limited_body_end(&window, start - next, window.len(), options, remaining);
         }
-    }
+        if let Some(quarantine) = config.quarantine.as_mut() {
+            quarantine.finish()?;
+        }
+        options
+            .error_policy
+            .check_finished(rejected_rows, rows_scanned)?;
 
-    Ok((
-        CsvIngestSummary { row_count, headers },
-        combined_crc.map(Crc32::finalize),
-    ))
+        Ok((
+            CsvIngestSummary {
+                row_count: rows_scanned - filtered_rows - rejected_rows,
+                headers,
+                scanned_rows: rows_scanned,
+                filtered_rows,
+                rejected_rows,
+                diagnostics,
+            },
+            combined_crc.map(Crc32::finalize),
+        ))
+    })();
+    // A file that changed while it was parsed can fail the parse or pass with
+    // wrong counts, so the change is reported in place of either.
+    loaded.verify()?;
+    result
 }
 
-fn parse_header(header: &[u8], delimiter: u8, trim: bool) -> CsvResult<Vec<String>> {
-    let mut headers = Vec::new();
-    let mut start = 0usize;
-    for end in memchr_iter(delimiter, header) {
-        let value = trim_ascii_if(&header[start..end], trim);
-        headers.push(std::str::from_utf8(value)?.to_string());
-        start = end + 1;
-    }
-    let value = trim_ascii_if(&header[start..], trim);
-    headers.push(std::str::from_utf8(value)?.to_string());
-    Ok(headers)
+fn parse_header(header: &Row<'_>, trim: bool) -> CsvResult<Vec<String>> {
+    header
+        .iter()
+        .map(|value| Ok(std::str::from_utf8(trim_ascii_if(value, trim))?.to_string()))
+        .collect()
 }
 
+/// Return where the body ends after `limit_rows` nonempty records, and how
+/// many terminated records precede that end when there is a limit.
 fn limited_body_end(
     data: &[u8],
     body_start: usize,
     len: usize,
-    terminator: CsvTerminator,
+    options: &CsvOptions,
     limit_rows: Option<u64>,
-) -> usize {
+) -> (usize, u64) {
     let Some(limit) = limit_rows else {
-        return len;
+        return (len, 0);
     };
     if limit == 0 {
-        return body_start;
+        return (body_start, 0);
     }
 
     let mut rows = 0u64;
     let mut cursor = body_start;
-    while let Some((record_end, next_record)) =
-        next_record_terminator(data, cursor, len, terminator)
-    {
+    while let Some((record_end, next_record)) = next_record_terminator(data, cursor, len, options) {
         if record_end > cursor {
             rows += 1;
             if rows == limit {
-                return next_record;
+                return (next_record, rows);
             }
         }
         cursor = next_record;
     }
 
-    len
+    (len, rows)
 }
 
+/// Split the body into at most `requested_workers` chunks of whole records,
+/// each at least `min_chunk_size` bytes long unless the body is shorter.
+///
+/// A split point inside a quoted field, judged by the parity of the unescaped
+/// quote bytes before it, moves past the end of that field before moving to
+/// the end of the record. Quotes inside unquoted fields can mislead the
+/// parity; [`process_chunk`] reports the cut record and the rest of the window
+/// is split and processed again from it.
 fn chunk_bounds(
     data: &[u8],
     body_start: usize,
     body_end: usize,
-    terminator: CsvTerminator,
+    options: &CsvOptions,
     requested_workers: usize,
+    min_chunk_size: usize,
+    pool: &dyn FastLocalPool,
 ) -> Vec<usize> {
     if body_start == body_end {
         return vec![body_start];
     }
 
     let body_len = body_end - body_start;
-    let workers = requested_workers.max(1).min(body_len);
+    let workers = requested_workers
+        .min(body_len / min_chunk_size.max(1))
+        .max(1);
     let mut bounds = Vec::with_capacity(workers + 1);
     bounds.push(body_start);
 
-    for worker in 1..workers {
-        let approximate = body_start + body_len.saturating_mul(worker) / workers;
-        let next = next_record_terminator(data, approximate, body_end, terminator)
-            .map(|(_, next)| next)
-            .unwrap_or(body_end);
+    let points: Vec<_> = (1..workers)
+        .map(|worker| body_start + body_len.saturating_mul(worker) / workers)
+        .collect();
+    for (point, (quoted, escaped)) in points
+        .iter()
+        .zip(quote_parity(data, body_start, &points, options, pool))
+    {
+        let from = point + usize::from(escaped);
+        let next = record_after(data, from, body_end, options, quoted).unwrap_or(body_end);
         if next > *bounds.last().expect("body start is present") && next < body_end {
             bounds.push(next);
         }
@@ -244,188 +987,437 @@ fn chunk_bounds(
     bounds
 }
 
-fn process_chunk(
+/// Return whether an odd number of unescaped quote bytes precede each of
+/// `points`, and whether an escape before the point hides its byte, counting
+/// the quotes between consecutive points in parallel.
+fn quote_parity(
+    data: &[u8],
+    start: usize,
+    points: &[usize],
+    options: &CsvOptions,
+    pool: &dyn FastLocalPool,
+) -> Vec<(bool, bool)> {
+    if !options.quoting {
+        return vec![(false, false); points.len()];
+    }
+    let counts = points.iter().scan(start, |previous, &point| {
+        let segment = &data[*previous..point];
+        *previous = point;
+        // The previous segment may end with an escape that hides this
+        // segment's first byte, so both cases are counted.
+        Some(move || {
+            let whole = segment_parity(segment, options);
+            let skipped = match segment.first() {
+                Some(&byte) if byte == options.quote || Some(byte) == options.escape => {
+                    segment_parity(&segment[1..], options)
+                }
+                _ => whole,
+            };
+            [whole, skipped]
+        })
+    });
+    run_all(pool, counts)
+        .into_iter()
+        // A lost count only costs a reprocessed chunk.
+        .scan((false, false), |(quoted, escaped), parity| {
+            let (odd, dangling) =
+                parity.map_or((false, false), |parity| parity[usize::from(*escaped)]);
+            *quoted ^= odd;
+            *escaped = dangling;
+            Some((*quoted, *escaped))
+        })
+        .collect()
+}
+
+/// Return whether `segment` holds an odd number of unescaped quote bytes, and
+/// whether it ends with an escape that hides the byte after it.
+///
+/// A segment does not know whether it starts inside a quoted field, so every
+/// escape is taken to hide the byte after it, as it does inside one.
+fn segment_parity(segment: &[u8], options: &CsvOptions) -> (bool, bool) {
+    let Some(escape) = options.escape.filter(|&escape| escape != options.quote) else {
+        return (memchr_iter(options.quote, segment).count() % 2 == 1, false);
+    };
+    let mut odd = false;
+    let mut cursor = 0;
+    while let Some(offset) = memchr2(options.quote, escape, &segment[cursor..]) {
+        cursor += offset + 1;
+        if segment[cursor - 1] == options.quote {
+            odd = !odd;
+        } else if cursor == segment.len() {
+            return (odd, true);
+        } else {
+            cursor += 1;
+        }
+    }
+    (odd, false)
+}
+
+/// Return the start of the first record after `start`, treating quote bytes
+/// as opening and closing quoted fields from the `quoted` state.
+fn record_after(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    options: &CsvOptions,
+    mut quoted: bool,
+) -> Option<usize> {
+    let (first, second) = match options.terminator {
+        CsvTerminator::CrLf => (b'\r', b'\n'),
+        CsvTerminator::Any(byte) => (byte, byte),
+    };
+    let quote = if options.quoting {
+        options.quote
+    } else {
+        first
+    };
+    let escape = options.escape.filter(|&escape| escape != quote);
+    let mut cursor = start;
+    while cursor < end {
+        if quoted {
+            let offset = memchr2(quote, escape.unwrap_or(quote), &data[cursor..end])?;
+            cursor += offset;
+            // An escape inside a quoted field hides the byte after it.
+            if Some(data[cursor]) == escape {
+                cursor += 2;
+                continue;
+            }
+            quoted = false;
+        } else {
+            cursor += memchr3(quote, first, second, &data[cursor..end])?;
+            if options.quoting && data[cursor] == quote {
+                quoted = true;
+            } else {
+                let crlf = options.terminator == CsvTerminator::CrLf
+                    && data[cursor] == b'\r'
+                    && data.get(cursor + 1) == Some(&b'\n')
+                    && cursor + 1 < end;
+                return Some(cursor + 1 + usize::from(crlf));
+            }
+        }
+        cursor += 1;
+    }
+    None
+}
+
+/// Process the records of `slice`, which starts at the absolute byte
+/// `absolute_start` and ends the body if `last` is set. A record that a
+/// non-final chunk's end cuts inside a quoted field is left unprocessed and
+/// reported as `unfinished`.
+fn process_chunk<C: ChunkSink>(
     slice: &[u8],
     absolute_start: usize,
-    options: &CsvOptions,
-    required_field: Option<(usize, &str)>,
-    expected_width: Option<usize>,
+    last: bool,
+    plan: &RowPlan<'_>,
     verify_crc: bool,
-) -> CsvResult<ChunkResult> {
-    reject_quotes(slice, absolute_start, options)?;
-
+    mut sink: C,
+) -> CsvResult<ChunkResult<C::Output>> {
+    let options = plan.options;
+    let skip_errors = options.error_policy.skips();
     let mut row_count = 0u64;
+    let mut filtered = 0u64;
+    let mut rejected = Vec::new();
+    let mut unfinished = None;
     let mut cursor = 0usize;
     let mut crc = verify_crc.then(Crc32::new);
-
-    while let Some((record_end, next_record)) =
-        next_record_terminator(slice, cursor, slice.len(), options.terminator)
-    {
-        let row = &slice[cursor..record_end];
-        if !row.is_empty() {
-            process_row(
-                row,
-                options,
-                required_field,
-                expected_width,
-                crc.as_mut(),
-                row_count + 1,
-            )?;
+    let mut slots = vec![(0, 0); plan.projected_width];
+    let mut fields = FieldBuf::default();
+    let invalid_utf8 = plan
+        .validate_utf8
+        .then(|| first_invalid_utf8(slice, last))
+        .flatten();
+
+    while cursor < slice.len() {
+        let Some((record_end, next_record)) =
+            next_record_terminator(slice, cursor, slice.len(), options)
+                .or_else(|| last.then_some((slice.len(), slice.len())))
+        else {
+            unfinished = Some(absolute_start + cursor);
+            break;
+        };
+        let raw = &slice[cursor..record_end];
+        if !raw.is_empty() {
+            if let Some(offset) = invalid_utf8.filter(|&offset| offset < next_record) {
+                let error = CsvIngestError::InvalidUtf8Row {
+                    row: row_count + 1,
+                    offset: (absolute_start + offset) as u64,
+                    position: None,
+                    source_name: None,
+                };
+                return Err(at_record_start(error, absolute_start + cursor));
+            }
+            let row = fields.split(raw, options, plan.field_limit);
+            let accepted_crc = if skip_errors { crc.clone() } else { None };
+            match process_row(&row, plan, crc.as_mut(), &mut slots, row_count + 1) {
+                Ok(true) => sink.accept(&row, absolute_start + cursor, row_count + 1)?,
+                Ok(false) => filtered += 1,
+                Err(error) if skip_errors && error.is_record_error() => {
+                    crc = accepted_crc;
+                    rejected.push(RejectedRow {
+                        start: absolute_start + cursor,
+                        end: absolute_start + record_end,
+                        error: at_record_start(error, absolute_start + cursor),
+                    });
+                    // Rows after an exhausted count budget cannot change the outcome.
+                    if options
+                        .error_policy
+                        .check_count(rejected.len() as u64, row_count + 1)
+                        .is_err()
+                    {
+                        return Ok(ChunkResult {
+                            row_count: row_count + 1,
+                            filtered,
+                            rejected,
+                            crc,
+                            output: sink.finish()?,
+                            unfinished: None,
+                        });
+                    }
+                }
+                Err(error) => return Err(at_record_start(error, absolute_start + cursor)),
+            }
             row_count += 1;
         }
         cursor = next_record;
     }
 
-    if cursor < slice.len() {
-        process_row(
-            &slice[cursor..],
-            options,
-            required_field,
-            expected_width,
-            crc.as_mut(),
-            row_count + 1,
-        )?;
-        row_count += 1;
-    }
+    Ok(ChunkResult {
+        row_count,
+        filtered,
+        rejected,
+        crc,
+        output: sink.finish()?,
+        unfinished,
+    })
+}
 
-    Ok(ChunkResult { row_count, crc })
+/// Return the offset of the first byte of `slice` that is not UTF-8.
+///
+/// Chunks start and end at record boundaries, so a multibyte sequence is only
+/// cut by the end of a chunk that does not end the body, inside the record it
+/// leaves unfinished. That sequence is validated with the record instead.
+fn first_invalid_utf8(slice: &[u8], last: bool) -> Option<usize> {
+    if simdutf8::basic::from_utf8(slice).is_ok() {
+        return None;
+    }
+    let error = simdutf8::compat::from_utf8(slice).expect_err("the slice is not UTF-8");
+    (error.error_len().is_some() || last).then(|| error.valid_up_to())
 }
 
+/// Validate one row and add its fields, or its projected fields, to `crc`.
+///
+/// Returns whether the row passed the filters. `slots` receives the trimmed
+/// range of each projected field.
 fn process_row(
-    row: &[u8],
-    options: &CsvOptions,
-    required_field: Option<(usize, &str)>,
-    expected_width: Option<usize>,
+    row: &Row<'_>,
+    plan: &RowPlan<'_>,
     crc: Option<&mut Crc32>,
+    slots: &mut [(usize, usize)],
     row_number: u64,
-) -> CsvResult<()> {
-    if crc.is_none() && options.flexible {
-        if let Some((required_index, required_header)) = required_field {
-            let delimiter_count = memchr_iter(options.delimiter, row)
-                .take(required_index)
-                .count();
-            if delimiter_count < required_index {
-                return Err(CsvIngestError::MissingRequiredField {
-                    row: row_number,
-                    header: required_header.to_string(),
-                });
+) -> CsvResult<bool> {
+    let options = plan.options;
+    if !plan.filters.filters().is_empty() && !filter_row(row, plan, row_number)? {
+        return Ok(false);
+    }
+    let trim = options.trims_fields();
+    let schema_columns = plan
+        .schema
+        .as_ref()
+        .map_or(&[][..], CompiledSchema::columns);
+    let projected = plan.projection.as_deref();
+    let hash_every_field = projected.is_none();
+    let mut crc = crc;
+    let mut schema_column = 0usize;
+    let mut projected_field = 0usize;
+    // Schema violations are reported only after the row's shape is valid.
+    let mut violation = None;
+    let mut field_count = 0usize;
+    for raw in row.iter() {
+        if plan.field_limit == Some(field_count) {
+            break;
+        }
+        if hash_every_field {
+            if let Some(crc) = crc.as_mut() {
+                if field_count > 0 {
+                    crc.update(&[FIELD_SEPARATOR]);
+                }
+                crc.update(trim_ascii_if(raw, trim));
             }
         }
-        return Ok(());
+        while let Some(&(_, slot)) = projected
+            .and_then(|projected| projected.get(projected_field))
+            .filter(|(index, _)| *index == field_count)
+        {
+            slots[slot] = row.range_of(trim_ascii_if(raw, trim));
+            projected_field += 1;
+        }
+        while let Some(column) = schema_columns
+            .get(schema_column)
+            .filter(|column| column.index == field_count)
+        {
+            if violation.is_none() {
+                violation = column.validate(trim_ascii_if(raw, trim), row_number).err();
+            }
+            schema_column += 1;
+        }
+        field_count += 1;
     }
 
-    let mut crc = crc;
-    let mut field_start = 0usize;
-    let mut field_count = 0usize;
-    for field_end in memchr_iter(options.delimiter, row) {
-        if let Some(crc) = crc.as_mut() {
-            if field_count > 0 {
+    check_shape(plan, field_count, row_number)?;
+    if let Some(violation) = violation {
+        return Err(violation);
+    }
+
+    if let (Some(crc), false) = (crc, hash_every_field) {
+        for (slot, &(start, end)) in slots.iter().enumerate() {
+            if slot > 0 {
                 crc.update(&[FIELD_SEPARATOR]);
             }
-            crc.update(trim_ascii_if(
-                &row[field_start..field_end],
-                options.trims_fields(),
-            ));
+            crc.update(&row.bytes()[start..end]);
         }
-        field_count += 1;
-        field_start = field_end + 1;
     }
-    if let Some(crc) = crc.as_mut() {
-        if field_count > 0 {
-            crc.update(&[FIELD_SEPARATOR]);
+    Ok(true)
+}
+
+/// Return whether every filter accepts `row`, in column order. A rejected row
+/// is still checked for a valid shape.
+fn filter_row(row: &Row<'_>, plan: &RowPlan<'_>, row_number: u64) -> CsvResult<bool> {
+    let options = plan.options;
+    let filters = plan.filters.filters();
+    let last_filtered = filters
+        .iter()
+        .map(|filter| filter.index)
+        .max()
+        .unwrap_or_default();
+    let mut accepted = 0usize;
+    for (index, field) in row.iter().enumerate().take(last_filtered + 1) {
+        let field = trim_ascii_if(field, options.trims_fields());
+        for filter in filters.iter().filter(|filter| filter.index == index) {
+            if !filter.accepts(field) {
+                check_shape(plan, row.len(), row_number)?;
+                return Ok(false);
+            }
+            accepted += 1;
         }
-        crc.update(trim_ascii_if(&row[field_start..], options.trims_fields()));
     }
-    field_count += 1;
+    if accepted < filters.len() {
+        // A filtered column is missing, which the shape check reports.
+        check_shape(plan, row.len(), row_number)?;
+    }
+    Ok(true)
+}
 
-    if !options.flexible {
-        if let Some(expected_width) = expected_width {
+/// Check a row's width and required columns, as the streaming parser does
+/// before any field is inspected.
+fn check_shape(plan: &RowPlan<'_>, field_count: usize, row_number: u64) -> CsvResult<()> {
+    if !plan.options.flexible {
+        if let Some(expected_width) = plan.expected_width {
             if field_count != expected_width {
                 return Err(CsvIngestError::RaggedRow {
                     row: Some(row_number),
                     expected: expected_width as u64,
                     actual: field_count as u64,
+                    position: None,
+                    source_name: None,
                 });
             }
         }
-    } else if let Some((required_index, required_header)) = required_field {
-        if field_count <= required_index {
-            return Err(CsvIngestError::MissingRequiredField {
-                row: row_number,
-                header: required_header.to_string(),
-            });
-        }
     }
-
+    if let Some((_, header)) = plan
+        .required
+        .iter()
+        .find(|(index, _)| field_count <= *index)
+    {
+        return Err(CsvIngestError::MissingRequiredField {
+            row: row_number,
+            header: header.clone(),
+            position: None,
+            source_name: None,
+        });
+    }
     Ok(())
 }
 
-fn reject_quotes(bytes: &[u8], absolute_start: usize, options: &CsvOptions) -> CsvResult<()> {
-    if options.quoting {
-        if let Some(offset) = memchr(options.quote, bytes) {
-            return Err(CsvIngestError::UnsupportedDialect(format!(
-                "fast-local supports only unquoted CSV; found quote byte {:?} at offset {}",
-                options.quote as char,
-                absolute_start + offset
-            )));
-        }
+/// Record the absolute offset of the first byte of the record that failed.
+fn at_record_start(mut error: CsvIngestError, offset: usize) -> CsvIngestError {
+    if let CsvIngestError::MissingRequiredField { position, .. }
+    | CsvIngestError::RaggedRow { position, .. }
+    | CsvIngestError::SchemaViolation { position, .. }
+    | CsvIngestError::InvalidUtf8Row { position, .. } = &mut error
+    {
+        *position = Some(Position::new().set_byte(offset as u64).clone());
     }
-    Ok(())
+    error
 }
 
-fn offset_row(error: CsvIngestError, offset: u64) -> CsvIngestError {
-    match error {
-        CsvIngestError::MissingRequiredField { row, header } => {
-            CsvIngestError::MissingRequiredField {
-                row: row + offset,
-                header,
-            }
-        }
+/// Convert a chunk-relative row and record start into a global row and the
+/// position `csv_async` reports for the same record, from the window `data`.
+fn locate_error(
+    mut error: CsvIngestError,
+    row_offset: u64,
+    header_records: u64,
+    data: &[u8],
+    origin: WindowOrigin,
+    data_start: usize,
+    options: &CsvOptions,
+) -> CsvIngestError {
+    let (row, position) = match &mut error {
+        CsvIngestError::MissingRequiredField { row, position, .. }
+        | CsvIngestError::SchemaViolation { row, position, .. }
+        | CsvIngestError::InvalidValue { row, position, .. }
+        | CsvIngestError::InvalidUtf8Row { row, position, .. } => (row, position),
         CsvIngestError::RaggedRow {
             row: Some(row),
-            expected,
-            actual,
-        } => CsvIngestError::RaggedRow {
-            row: Some(row + offset),
-            expected,
-            actual,
-        },
-        error => error,
+            position,
+            ..
+        } => (row, position),
+        _ => return error,
+    };
+    *row += row_offset;
+    if let Some(record_start) = position.as_ref().map(|position| position.byte() as usize) {
+        *position = Some(record_position(
+            data,
+            origin,
+            data_start,
+            record_start,
+            *row - 1 + header_records,
+            options,
+        ));
     }
+    error
 }
 
-fn next_record_terminator(
+/// `csv_async` positions a record immediately after the first terminator byte of
+/// the previous record, so skipped blank lines belong to the following record.
+fn record_position(
     data: &[u8],
-    start: usize,
-    end: usize,
-    terminator: CsvTerminator,
-) -> Option<(usize, usize)> {
-    let record_end = match terminator {
-        CsvTerminator::CrLf => memchr2(b'\r', b'\n', &data[start..end])? + start,
-        CsvTerminator::Any(byte) => memchr(byte, &data[start..end])? + start,
-    };
-    let next_record = if terminator == CsvTerminator::CrLf
-        && data[record_end] == b'\r'
-        && record_end + 1 < end
-        && data[record_end + 1] == b'\n'
-    {
-        record_end + 2
-    } else {
-        record_end + 1
-    };
-    Some((record_end, next_record))
+    origin: WindowOrigin,
+    data_start: usize,
+    record_start: usize,
+    record: u64,
+    options: &CsvOptions,
+) -> Position {
+    let from = data_start.max(origin.start);
+    let byte = data[from - origin.start..record_start - origin.start]
+        .iter()
+        .rposition(|&byte| !options.is_terminator(byte))
+        .map_or(0, |last| from + last + 2);
+    let line = origin.lines + memchr_iter(b'\n', &data[..byte - origin.start]).count() as u64 + 1;
+    Position::new()
+        .set_byte(byte as u64)
+        .set_line(line)
+        .set_record(record)
+        .clone()
 }
 
 fn next_nonempty_record(
     data: &[u8],
     start: usize,
     end: usize,
-    terminator: CsvTerminator,
+    options: &CsvOptions,
 ) -> Option<(usize, usize, usize)> {
     let mut cursor = start;
-    while let Some((record_end, next_record)) =
-        next_record_terminator(data, cursor, end, terminator)
-    {
+    while let Some((record_end, next_record)) = next_record_terminator(data, cursor, end, options) {
         if record_end > cursor {
             return Some((cursor, record_end, next_record));
         }
@@ -456,6 +1448,13 @@ mod tests {
     use std::io::Write;
     use tempfile::NamedTempFile;
 
+    fn with_workers<'a>(count: usize) -> FastLocalConfig<'a> {
+        FastLocalConfig {
+            workers: NonZeroUsize::new(count),
+            ..FastLocalConfig::default()
+        }
+    }
+
     fn fixture(contents: &[u8]) -> NamedTempFile {
         let mut file = NamedTempFile::new().expect("create fixture");
         file.write_all(contents).expect("write fixture");
@@ -528,19 +1527,135 @@ mod tests {
     }
 
     #[test]
-    fn quote_bytes_are_rejected() {
-        let file = fixture(b"sku,value\nA,\"quoted,field\"\n");
-        let error = fast_local_process_with_workers(
-            file.path(),
-            &["sku"],
-            &CsvOptions::default(),
-            false,
+    fn quoted_fields_are_unquoted_and_verified() {
+        let file =
+            fixture(b"sku,value\nA,\"quoted,field\"\n\"B\",\"two\nlines, \"\"quoted\"\"\"\n");
+        for workers in [1, 2, 8] {
+            let (summary, crc) = fast_local_process_with_workers(
+                file.path(),
+                &["value"],
+                &CsvOptions::default(),
+                true,
+                None,
+                workers,
+            )
+            .expect("parse quoted fixture");
+
+            assert_eq!(summary.row_count, 2);
+            assert_eq!(
+                crc,
+                Some(expected_crc(&[
+                    &[b"A", b"quoted,field"],
+                    &[b"B", b"two\nlines, \"quoted\""],
+                ]))
+            );
+        }
+    }
+
+    #[test]
+    fn escaped_quotes_do_not_mislead_chunk_boundaries() {
+        let options = CsvOptions {
+            escape: Some(b'\\'),
+            ..CsvOptions::default()
+        };
+        let data = b"1,\"a \\\" b\n1\"\n2,\"\\\\\"\n3,x\n";
+        let points = [8, 13, 20];
+        assert_eq!(
+            quote_parity(data, 0, &points, &options, &ScopedThreads),
+            [(true, false), (false, false), (false, false)]
+        );
+        assert_eq!(record_after(data, 8, data.len(), &options, true), Some(13));
+        assert_eq!(record_after(data, 16, data.len(), &options, true), Some(20));
+        // A split between an escape and the quote it hides.
+        assert_eq!(
+            quote_parity(data, 0, &[6, 17, 19], &options, &ScopedThreads),
+            [(true, true), (true, true), (false, false)]
+        );
+    }
+
+    #[test]
+    fn chunks_cut_inside_quoted_fields_are_reprocessed() {
+        // Escaped quotes no longer mislead the quote parity, so every chunk is
+        // parsed once.
+        let mut contents = b"id,text\n".to_vec();
+        for row in 0..200 {
+            contents.extend_from_slice(format!("{row},\"a \\\" b\n{row}\"\n").as_bytes());
+        }
+        let file = fixture(&contents);
+        let options = CsvOptions {
+            escape: Some(b'\\'),
+            ..CsvOptions::default()
+        };
+        let expected =
+            fast_local_process_with_workers(file.path(), &["text"], &options, true, None, 1)
+                .expect("parse serially");
+        assert_eq!(expected.0.row_count, 200);
+        for workers in [2, 3, 7, 16] {
+            let parallel = fast_local_process_with_workers(
+                file.path(),
+                &["text"],
+                &options,
+                true,
+                None,
+                workers,
+            )
+            .expect("parse in parallel");
+            assert_eq!(parallel, expected, "{workers} workers");
+        }
+
+        let limited =
+            fast_local_process_with_workers(file.path(), &["text"], &options, true, Some(3), 4)
+                .expect("parse limited rows");
+        assert_eq!(limited.0.row_count, 3);
+
+        let on_pool = |contents: &[u8], options: &CsvOptions| {
+            let pool = CountingPool {
+                runs: Default::default(),
+                lossy: false,
+            };
+            let parsed = fast_local_process_bytes(
+                contents,
+                &["text"],
+                options,
+                true,
+                None,
+                FastLocalConfig {
+                    workers: NonZeroUsize::new(4),
+                    pool: Some(&pool),
+                    ..FastLocalConfig::default()
+                },
+            )
+            .expect("parse on the pool");
+            (parsed, pool.runs.into_inner().expect("lock runs"))
+        };
+        let (parsed, runs) = on_pool(&contents, &options);
+        assert_eq!(parsed, expected);
+        assert_eq!(runs, [3, 4]);
+
+        // A quote inside an unquoted field flips the parity of every later
+        // split, so the rest of each window is split again on the pool.
+        let mut contents = b"id,size,text\n".to_vec();
+        for row in 0..200 {
+            let text = "a".repeat(40);
+            contents.extend_from_slice(format!("{row},5\" wide,\"{text}\n{text}\"\n").as_bytes());
+        }
+        let options = CsvOptions::default();
+        let expected = fast_local_process_bytes(
+            &contents,
+            &["text"],
+            &options,
+            true,
             None,
-            4,
+            FastLocalConfig {
+                workers: NonZeroUsize::new(1),
+                ..FastLocalConfig::default()
+            },
         )
-        .expect_err("quoted input must be rejected");
-
-        assert!(error.to_string().contains("only unquoted CSV"));
+        .expect("parse serially");
+        assert_eq!(expected.0.row_count, 200);
+        let (parsed, runs) = on_pool(&contents, &options);
+        assert_eq!(parsed, expected);
+        assert!(runs.len() > 2, "{runs:?}");
     }
 
     #[test]
@@ -661,7 +1776,8 @@ mod tests {
             CsvIngestError::RaggedRow {
                 row: Some(1),
                 expected: 2,
-                actual: 1
+                actual: 1,
+                ..
             }
         ));
     }
@@ -685,6 +1801,343 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn errors_report_streaming_positions_and_the_file_name() {
+        let file = tempfile::Builder::new()
+            .suffix(".csv")
+            .tempfile()
+            .expect("create fixture");
+        std::fs::write(file.path(), b"sku,value\r\nA,1\r\n\nB,2\nC\nD,4\n").expect("write fixture");
+
+        for workers in [1, 2, 8] {
+            let error = fast_local_process_with_workers(
+                file.path(),
+                &["sku"],
+                &CsvOptions::default(),
+                false,
+                None,
+                workers,
+            )
+            .expect_err("ragged row must fail");
+
+            assert!(matches!(
+                error,
+                CsvIngestError::RaggedRow { row: Some(3), .. }
+            ));
+            let position = error.position().expect("ragged position");
+            assert_eq!(
+                (position.byte(), position.line(), position.record()),
+                (21, 5, 3)
+            );
+            assert_eq!(
+                error.source_name(),
+                file.path().file_name().and_then(|name| name.to_str())
+            );
+        }
+    }
+
+    #[test]
+    fn skipped_rows_are_reported_and_excluded_from_the_crc() {
+        let file = fixture(b"sku,value\nA,1\nB\nC,3\nD,4,5\nE,5\n");
+        let options = CsvOptions {
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+
+        for workers in [1, 2, 8] {
+            let (summary, crc) = fast_local_process_with_workers(
+                file.path(),
+                &["sku"],
+                &options,
+                true,
+                None,
+                workers,
+            )
+            .expect("skip ragged rows");
+
+            assert_eq!(summary.row_count, 3);
+            assert_eq!(summary.rejected_rows, 2);
+            let rows: Vec<_> = summary.diagnostics.iter().map(|d| d.row).collect();
+            assert_eq!(rows, [2, 4]);
+            assert_eq!(
+                crc,
+                Some(expected_crc(&[&[b"A", b"1"], &[b"C", b"3"], &[b"E", b"5"]]))
+            );
+
+            let capped = CsvOptions {
+                max_diagnostics: 1,
+                ..options
+            };
+            let (capped, _) = fast_local_process_with_workers(
+                file.path(),
+                &["sku"],
+                &capped,
+                false,
+                None,
+                workers,
+            )
+            .expect("skip ragged rows");
+            assert_eq!(capped.rejected_rows, 2);
+            assert_eq!(capped.diagnostics, summary.diagnostics[..1]);
+        }
+
+        let budget = CsvOptions {
+            error_policy: crate::CsvErrorPolicy::SkipUpTo(1),
+            ..CsvOptions::default()
+        };
+        let error = fast_local_process_with_workers(file.path(), &["sku"], &budget, false, None, 8)
+            .expect_err("second rejected row exceeds the budget");
+        assert!(matches!(
+            error,
+            CsvIngestError::ErrorBudgetExceeded {
+                rejected: 2,
+                scanned: 4
+            }
+        ));
+    }
+
+    #[test]
+    fn rejected_rows_are_quarantined_in_file_order() {
+        let file = fixture(b"sku,value\r\nA,1\r\nB\r\nC,3\r\nD,4,5\r\nE");
+        let options = CsvOptions {
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let mut writer = crate::CsvQuarantineWriter::new(Vec::new()).expect("create writer");
+
+        let (summary, _) = process_path(
+            file.path(),
+            &["sku"],
+            &options,
+            false,
+            None,
+            FastLocalConfig {
+                quarantine: Some(&mut writer),
+                ..with_workers(8)
+            },
+        )
+        .expect("quarantine ragged rows");
+
+        assert_eq!(summary.rejected_rows, 3);
+        let output = writer.into_inner().expect("flush");
+        let lines: Vec<_> = output.split(|&byte| byte == b'\n').collect();
+        assert_eq!(
+            lines[1],
+            br#"2,15,2,2,"Ragged row: got 1 fields, expected 2","B""#
+        );
+        assert!(lines[2].starts_with(b"4,"));
+        assert!(lines[2].ends_with(br#","D,4,5""#));
+        assert!(lines[3].ends_with(br#","E""#));
+    }
+
+    #[test]
+    fn schema_violations_are_located_and_skippable() {
+        let schema = CsvSchema {
+            columns: vec![
+                crate::CsvColumnSchema::new(
+                    "qty",
+                    [crate::CsvRule::Integer {
+                        min: Some(0),
+                        max: None,
+                    }],
+                ),
+                crate::CsvColumnSchema::new("status", [crate::CsvRule::NotEmpty]),
+            ],
+        };
+        let file = fixture(b"sku,qty,status\nA, 1 ,open\nB,-1,open\nC,2\nD,3, \n");
+        let options = CsvOptions {
+            flexible: true,
+            trim: crate::CsvTrim::All,
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+
+        let (summary, crc) = process_path(
+            file.path(),
+            &[],
+            &options,
+            true,
+            None,
+            FastLocalConfig {
+                schema: Some(&schema),
+                ..with_workers(4)
+            },
+        )
+        .expect("skip schema violations");
+
+        assert_eq!(summary.row_count, 1);
+        let messages: Vec<_> = summary
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.row, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (2, "Row 2 column 'qty' violates rule: integer >= 0"),
+                (3, "Row 3 is missing required field: status"),
+                (4, "Row 4 column 'status' violates rule: not empty"),
+            ]
+        );
+        assert_eq!(
+            summary.diagnostics[0].position.as_ref().map(Position::byte),
+            Some(26)
+        );
+        assert_eq!(crc, Some(expected_crc(&[&[b"A", b"1", b"open"]])));
+
+        let empty = fixture(b"");
+        let headerless = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        let config = || FastLocalConfig {
+            schema: Some(&schema),
+            ..FastLocalConfig::default()
+        };
+        assert!(matches!(
+            process_path(empty.path(), &[], &CsvOptions::default(), false, None, config()),
+            Err(CsvIngestError::MissingHeader(header)) if header == "qty"
+        ));
+        assert!(matches!(
+            process_path(file.path(), &[], &headerless, false, None, config()),
+            Err(CsvIngestError::UnsupportedDialect(_))
+        ));
+    }
+
+    #[test]
+    fn projections_hash_selected_fields_in_selection_order() {
+        let projection = CsvProjection::new([CsvSelector::from("qty"), CsvSelector::from(0)]);
+        let file = fixture(b"sku,qty,note\nA, 1 ,x\nB,2,y,extra,fields\nC\n");
+        let options = CsvOptions {
+            flexible: true,
+            trim: crate::CsvTrim::All,
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let config = || FastLocalConfig {
+            projection: Some(&projection),
+            ..with_workers(2)
+        };
+
+        let (summary, crc) = process_path(file.path(), &[], &options, true, None, config())
+            .expect("project columns");
+
+        assert_eq!(summary.headers, ["qty", "sku"]);
+        assert_eq!(summary.row_count, 2);
+        assert_eq!(
+            summary.diagnostics[0].message,
+            "Row 3 is missing required field: qty"
+        );
+        assert_eq!(crc, Some(expected_crc(&[&[b"1", b"A"], &[b"2", b"B"]])));
+
+        let (_, unprojected) =
+            process_path(file.path(), &[], &options, true, None, with_workers(2))
+                .expect("hash every column");
+        assert_ne!(unprojected, crc);
+
+        let empty = fixture(b"");
+        assert!(matches!(
+            process_path(empty.path(), &[], &CsvOptions::default(), false, None, config()),
+            Err(CsvIngestError::MissingHeader(header)) if header == "qty"
+        ));
+        let headerless = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        assert!(matches!(
+            process_path(file.path(), &[], &headerless, false, None, config()),
+            Err(CsvIngestError::UnsupportedDialect(_))
+        ));
+    }
+
+    #[test]
+    fn filtered_rows_skip_schema_rules_and_the_crc() {
+        let schema = CsvSchema {
+            columns: vec![crate::CsvColumnSchema::new(
+                "qty",
+                [crate::CsvRule::Integer {
+                    min: None,
+                    max: None,
+                }],
+            )],
+        };
+        let filters = [CsvFilter::equals("country", "US")];
+        let file = fixture(b"sku,country,qty\nA,US,1\nB,CA,bad\nC,US,x\nD,MX\nE,US,2\n");
+        let options = CsvOptions {
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+
+        for workers in [1, 3] {
+            let (summary, crc) = process_path(
+                file.path(),
+                &[],
+                &options,
+                true,
+                None,
+                FastLocalConfig {
+                    schema: Some(&schema),
+                    filters: &filters,
+                    ..with_workers(workers)
+                },
+            )
+            .expect("filter rows");
+
+            assert_eq!(summary.row_count, 2);
+            assert_eq!(summary.scanned_rows, 5);
+            assert_eq!(summary.filtered_rows, 1);
+            assert_eq!(summary.rejected_rows, 2);
+            assert_eq!(
+                crc,
+                Some(expected_crc(&[&[b"A", b"US", b"1"], &[b"E", b"US", b"2"]]))
+            );
+        }
+
+        let headerless = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        let empty = fixture(b"");
+        assert!(matches!(
+            process_path(
+                empty.path(),
+                &[],
+                &headerless,
+                false,
+                None,
+                FastLocalConfig {
+                    filters: &filters,
+                    ..with_workers(1)
+                },
+            ),
+            Err(CsvIngestError::UnsupportedDialect(_))
+        ));
+    }
+
+    #[test]
+    fn missing_required_fields_are_reported_in_declaration_order() {
+        let file = fixture(b"a,b,c\n1\n");
+        let options = CsvOptions {
+            flexible: true,
+            ..CsvOptions::default()
+        };
+        for verify_crc in [false, true] {
+            let error = fast_local_process_with_workers(
+                file.path(),
+                &["b", "c"],
+                &options,
+                verify_crc,
+                None,
+                1,
+            )
+            .expect_err("short row must fail");
+            assert!(matches!(
+                error,
+                CsvIngestError::MissingRequiredField { header, .. } if header == "b"
+            ));
+        }
+    }
+
     #[test]
     fn unverified_row_must_contain_the_last_required_column() {
         let file = fixture(b"sku,value\nA\n");
@@ -698,7 +2151,7 @@ mod tests {
 
         assert!(matches!(
             error,
-            CsvIngestError::MissingRequiredField { row: 1, header } if header == "value"
+            CsvIngestError::MissingRequiredField { row: 1, header, .. } if header == "value"
         ));
     }
 
@@ -720,19 +2173,20 @@ mod tests {
     }
 
     #[test]
-    fn quotes_in_the_header_are_rejected() {
-        let file = fixture(b"\"sku\",value\nA,1\n");
-        let error = fast_local_process_with_workers(
+    fn quoted_headers_are_unquoted() {
+        let file = fixture(b"\"sku\",\"unit, price\"\nA,1\n");
+        let (summary, _) = fast_local_process_with_workers(
             file.path(),
-            &["sku"],
+            &["sku", "unit, price"],
             &CsvOptions::default(),
             false,
             None,
             2,
         )
-        .expect_err("quoted header must fail");
+        .expect("parse quoted header");
 
-        assert!(error.to_string().contains("only unquoted CSV"));
+        assert_eq!(summary.headers, ["sku", "unit, price"]);
+        assert_eq!(summary.row_count, 1);
     }
 
     #[test]
@@ -784,7 +2238,8 @@ mod tests {
             summary,
             CsvIngestSummary {
                 row_count: 2,
-                headers: vec![]
+                scanned_rows: 2,
+                ..CsvIngestSummary::default()
             }
         );
 
@@ -809,6 +2264,396 @@ mod tests {
         assert_eq!(crc, Some(expected_crc(&[&[b"A", b"a\"b"]])));
     }
 
+    #[test]
+    fn bytes_and_open_files_match_the_path() {
+        use std::io::{Seek, SeekFrom};
+
+        let contents = b"sku,note\na,\"x,\ny\"\nb\nc,z\n";
+        let file = fixture(contents);
+        let options = CsvOptions {
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let expected =
+            fast_local_process_with_workers(file.path(), &["sku"], &options, true, None, 3)
+                .expect("process the path");
+        assert_eq!(expected.0.rejected_rows, 1);
+
+        for workers in [1, 3] {
+            let bytes = fast_local_process_bytes(
+                contents,
+                &["sku"],
+                &options,
+                true,
+                None,
+                with_workers(workers),
+            )
+            .expect("process bytes");
+            assert_eq!(bytes, expected);
+
+            let mut open = File::open(file.path()).expect("open fixture");
+            open.seek(SeekFrom::Start(4)).expect("seek");
+            let mapped = fast_local_process_file(
+                &open,
+                &["sku"],
+                &options,
+                true,
+                None,
+                with_workers(workers),
+            )
+            .expect("process open file");
+            assert_eq!(mapped, expected);
+        }
+
+        let named = |source_name| FastLocalConfig {
+            source_name,
+            ..FastLocalConfig::default()
+        };
+        let ragged = b"sku\na,b\n";
+        let options = CsvOptions::default();
+        let error = fast_local_process_bytes(ragged, &[], &options, false, None, named(None))
+            .expect_err("the row is ragged");
+        assert_eq!(error.source_name(), None);
+        let error =
+            fast_local_process_bytes(ragged, &[], &options, false, None, named(Some("download")))
+                .expect_err("the row is ragged");
+        assert_eq!(error.source_name(), Some("download"));
+
+        let file = fixture(ragged);
+        let error = fast_local_process_with_config(
+            file.path(),
+            &[],
+            &options,
+            false,
+            None,
+            named(Some("renamed.csv")),
+        )
+        .expect_err("the row is ragged");
+        assert_eq!(error.source_name(), Some("renamed.csv"));
+    }
+
+    #[test]
+    fn files_changed_during_the_parse_are_reported() {
+        let contents = (0..200)
+            .map(|row| format!("{row},\"note {row}\"\n"))
+            .collect::<String>();
+        let contents = format!("id,note\n{contents}");
+        let options = CsvOptions::default();
+        for access in [FastLocalAccess::Map, FastLocalAccess::Read] {
+            let config = || FastLocalConfig {
+                workers: NonZeroUsize::new(3),
+                access,
+                ..FastLocalConfig::default()
+            };
+            let file = fixture(contents.as_bytes());
+            let expected =
+                fast_local_process_with_workers(file.path(), &["id"], &options, true, None, 1)
+                    .expect("process the file");
+            let read = fast_local_process_with_config(
+                file.path(),
+                &["id"],
+                &options,
+                true,
+                None,
+                config(),
+            )
+            .expect("process the file");
+            assert_eq!(read, expected);
+
+            let appended = std::sync::Once::new();
+            let error = fast_local_visit(file.path(), &["id"], &options, None, config(), || {
+                |_: &FastLocalRecord<'_>| {
+                    appended.call_once(|| {
+                        let mut append = std::fs::OpenOptions::new()
+                            .append(true)
+                            .open(file.path())
+                            .expect("open fixture");
+                        writeln!(append, "200,late").expect("append row");
+                    });
+                    Ok(())
+                }
+            })
+            .expect_err("the file grew");
+            assert!(
+                matches!(error, CsvIngestError::SourceModified { .. }),
+                "{error:?}"
+            );
+            assert_eq!(
+                error.source_name(),
+                file.path().file_name().and_then(|name| name.to_str())
+            );
+        }
+    }
+
+    #[test]
+    fn windows_match_the_whole_file() {
+        let mut contents = String::from("\u{feff}sku,note\r\n");
+        for row in 0..300 {
+            match row % 5 {
+                0 => contents.push_str(&format!("{row},\"two\r\nlines, \"\"{row}\"\"\"\r\n")),
+                1 => contents.push_str(&format!("{row}\r\n\r\n")),
+                2 => contents.push_str(&format!("{row},{}\r\n", "x".repeat(row))),
+                _ => contents.push_str(&format!("{row},n{row}\r\n")),
+            }
+        }
+        let file = fixture(contents.as_bytes());
+        let options = CsvOptions {
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let run = |access, window_size: Option<usize>, limit| {
+            let config = || FastLocalConfig {
+                access,
+                window_size: window_size.and_then(NonZeroUsize::new),
+                ..with_workers(3)
+            };
+            let mut writer = crate::CsvQuarantineWriter::new(Vec::new()).expect("create writer");
+            let (summary, offsets) = fast_local_fold(
+                file.path(),
+                &["sku"],
+                &options,
+                limit,
+                FastLocalConfig {
+                    quarantine: Some(&mut writer),
+                    ..config()
+                },
+                Vec::new,
+                |mut offsets, record| {
+                    offsets.push(record.offset());
+                    Ok(offsets)
+                },
+                |mut offsets, chunk| {
+                    offsets.extend(chunk);
+                    offsets
+                },
+            )
+            .expect("fold the file");
+            let (records_summary, record_offsets) = fast_local_records(
+                file.path(),
+                &["sku"],
+                &options,
+                limit,
+                config(),
+                |records| {
+                    let mut offsets = Vec::new();
+                    while let Some(record) = records.next_record() {
+                        offsets.push(record.offset());
+                    }
+                    offsets
+                },
+            )
+            .expect("read records");
+            assert_eq!(records_summary, summary);
+            assert_eq!(record_offsets, offsets);
+            let (crc_summary, crc) = fast_local_process_with_config(
+                file.path(),
+                &["sku"],
+                &options,
+                true,
+                limit,
+                config(),
+            )
+            .expect("process the file");
+            assert_eq!(crc_summary, summary);
+            let quarantined = writer.into_inner().expect("flush");
+            (summary, crc, offsets, quarantined)
+        };
+
+        for limit in [None, Some(1), Some(123)] {
+            let expected = run(FastLocalAccess::Map, None, limit);
+            let rejected = match limit {
+                None => 60,
+                Some(1) => 0,
+                _ => 25,
+            };
+            assert_eq!(expected.0.rejected_rows, rejected);
+            assert_eq!(expected.2.len() as u64, expected.0.row_count);
+            for access in [FastLocalAccess::Map, FastLocalAccess::Read] {
+                for window_size in [1, 40, 333, 4096] {
+                    assert_eq!(
+                        run(access, Some(window_size), limit),
+                        expected,
+                        "{access:?}, window_size={window_size}, limit={limit:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_in_the_body_is_located_when_validated() {
+        let mut contents = "name,note\n".as_bytes().to_vec();
+        for row in 0..200 {
+            contents.extend(format!("{row},\"caf\u{e9} \u{20ac}\n\u{1f600}\"\n").as_bytes());
+        }
+        let valid = contents.clone();
+        let row_start = contents.len();
+        contents.extend(b"200,ok\xe2\x82\n");
+        contents.extend(b"201,\xff\n");
+        let options = CsvOptions::default();
+        let validated = |workers, window_size| FastLocalConfig {
+            validate_utf8: true,
+            window_size: NonZeroUsize::new(window_size),
+            ..with_workers(workers)
+        };
+
+        let file = fixture(&valid);
+        for workers in [1, 3] {
+            // Small windows cut multibyte sequences, which are carried over.
+            for window_size in [0, 7, 64] {
+                let (summary, _) = fast_local_process_with_config(
+                    file.path(),
+                    &[],
+                    &options,
+                    false,
+                    None,
+                    validated(workers, window_size),
+                )
+                .expect("the body is UTF-8");
+                assert_eq!(summary.row_count, 200);
+            }
+        }
+
+        let file = fixture(&contents);
+        let (summary, _) =
+            fast_local_process_with_workers(file.path(), &[], &options, false, None, 3)
+                .expect("the body is not validated by default");
+        assert_eq!(summary.row_count, 202);
+        for workers in [1, 3] {
+            for window_size in [0, 7, 64] {
+                let error = fast_local_process_with_config(
+                    file.path(),
+                    &[],
+                    &options,
+                    false,
+                    None,
+                    validated(workers, window_size),
+                )
+                .expect_err("row 201 is not UTF-8");
+                let CsvIngestError::InvalidUtf8Row {
+                    row,
+                    offset,
+                    ref position,
+                    ..
+                } = error
+                else {
+                    panic!("unexpected error: {error:?}");
+                };
+                assert_eq!(row, 201);
+                assert_eq!(offset, (row_start + 6) as u64);
+                let position = position.as_ref().expect("position");
+                assert_eq!(position.byte(), row_start as u64);
+                assert_eq!(position.line(), 402);
+                assert_eq!(position.record(), 201);
+                assert_eq!(
+                    error.source_name(),
+                    file.path().file_name().and_then(|name| name.to_str())
+                );
+            }
+        }
+
+        let truncated = b"name\ncaf\xc3";
+        let error =
+            fast_local_process_bytes(truncated, &[], &options, false, None, validated(2, 0))
+                .expect_err("the file ends inside a sequence");
+        assert!(matches!(
+            error,
+            CsvIngestError::InvalidUtf8Row {
+                row: 1,
+                offset: 8,
+                ..
+            }
+        ));
+    }
+
+    /// Records the number of tasks in every run, then runs them unless it is
+    /// lossy.
+    struct CountingPool {
+        runs: std::sync::Mutex<Vec<usize>>,
+        lossy: bool,
+    }
+
+    impl FastLocalPool for CountingPool {
+        fn parallelism(&self) -> usize {
+            3
+        }
+
+        fn run(&self, tasks: Vec<FastLocalTask<'_>>) {
+            self.runs.lock().expect("lock runs").push(tasks.len());
+            if !self.lossy {
+                ScopedThreads.run(tasks);
+            }
+        }
+    }
+
+    #[test]
+    fn config_sets_the_pool_workers_and_minimum_chunk_size() {
+        let mut contents = b"sku,qty\n".to_vec();
+        for row in 0..100 {
+            contents.extend(format!("s{row},{row}\n").as_bytes());
+        }
+        let file = fixture(&contents);
+        let (expected, expected_crc) = fast_local_process_with_workers(
+            file.path(),
+            &["sku"],
+            &CsvOptions::default(),
+            true,
+            None,
+            1,
+        )
+        .expect("process serially");
+
+        let cases = [
+            (None, 0, FastLocalAdvice::Normal, [2, 3]),
+            (NonZeroUsize::new(5), 0, FastLocalAdvice::Sequential, [4, 5]),
+            (NonZeroUsize::new(5), 200, FastLocalAdvice::WillNeed, [2, 3]),
+            (None, contents.len(), FastLocalAdvice::Normal, [0, 1]),
+        ];
+        for (workers, min_chunk_size, advice, runs) in cases {
+            let pool = CountingPool {
+                runs: Default::default(),
+                lossy: false,
+            };
+            let (summary, crc) = process_path(
+                file.path(),
+                &["sku"],
+                &CsvOptions::default(),
+                true,
+                None,
+                FastLocalConfig {
+                    workers,
+                    min_chunk_size,
+                    pool: Some(&pool),
+                    access: FastLocalAccess::Map,
+                    advice,
+                    ..FastLocalConfig::default()
+                },
+            )
+            .expect("process on the pool");
+            assert_eq!(summary, expected);
+            assert_eq!(crc, expected_crc);
+            assert_eq!(*pool.runs.lock().expect("lock runs"), runs, "{workers:?}");
+        }
+
+        let lossy = CountingPool {
+            runs: Default::default(),
+            lossy: true,
+        };
+        let error = process_path(
+            file.path(),
+            &["sku"],
+            &CsvOptions::default(),
+            false,
+            None,
+            FastLocalConfig {
+                pool: Some(&lossy),
+                ..FastLocalConfig::default()
+            },
+        )
+        .expect_err("chunks that never run fail the parse");
+        assert!(matches!(error, CsvIngestError::FastLocalWorkerPanicked));
+    }
+
     #[test]
     fn invalid_options_fail_before_parsing() {
         let file = fixture(b"sku,value\nA,1\n");
@@ -0,0 +1,123 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+/// One unit of fast-local work handed to a [`FastLocalPool`].
+pub type FastLocalTask<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// Runs the parallel work of a fast-local parse, such as an application's
+/// shared thread pool.
+///
+/// With the `rayon` feature, `rayon::ThreadPool` is a pool. Without a pool,
+/// every task runs on its own scoped thread.
+pub trait FastLocalPool: Sync {
+    /// The number of tasks the pool runs at once, used as the worker count
+    /// when `FastLocalConfig::workers` is unset.
+    fn parallelism(&self) -> usize;
+
+    /// Run every task, in any order and on any threads, and return once all
+    /// of them have finished.
+    ///
+    /// A task that panics or is not run fails the parse with
+    /// `CsvIngestError::FastLocalWorkerPanicked`.
+    fn run(&self, tasks: Vec<FastLocalTask<'_>>);
+}
+
+/// Runs every task on its own scoped thread.
+pub(super) struct ScopedThreads;
+
+impl FastLocalPool for ScopedThreads {
+    fn parallelism(&self) -> usize {
+        thread::available_parallelism().map_or(1, usize::from)
+    }
+
+    fn run(&self, tasks: Vec<FastLocalTask<'_>>) {
+        thread::scope(|scope| {
+            for task in tasks {
+                scope.spawn(task);
+            }
+        });
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl FastLocalPool for rayon::ThreadPool {
+    fn parallelism(&self) -> usize {
+        self.current_num_threads()
+    }
+
+    fn run(&self, tasks: Vec<FastLocalTask<'_>>) {
+        self.scope(|scope| {
+            for task in tasks {
+                scope.spawn(move |_| task());
+            }
+        });
+    }
+}
+
+/// Run `tasks` on `pool` and return each one's result in order, or `None` for
+/// a task that panicked or was not run.
+pub(super) fn run_all<T: Send>(
+    pool: &dyn FastLocalPool,
+    tasks: impl IntoIterator<Item = impl FnOnce() -> T + Send>,
+) -> Vec<Option<T>> {
+    let tasks: Vec<_> = tasks.into_iter().collect();
+    let mut results: Vec<Option<T>> = tasks.iter().map(|_| None).collect();
+    let boxed = results
+        .iter_mut()
+        .zip(tasks)
+        .map(|(result, task)| -> FastLocalTask<'_> {
+            Box::new(move || *result = panic::catch_unwind(AssertUnwindSafe(task)).ok())
+        })
+        .collect();
+    pool.run(boxed);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drops every other task without running it.
+    struct Lossy;
+
+    impl FastLocalPool for Lossy {
+        fn parallelism(&self) -> usize {
+            2
+        }
+
+        fn run(&self, tasks: Vec<FastLocalTask<'_>>) {
+            for task in tasks.into_iter().step_by(2) {
+                task();
+            }
+        }
+    }
+
+    #[test]
+    fn panicking_and_dropped_tasks_have_no_result() {
+        let results = run_all(
+            &ScopedThreads,
+            (0..4).map(|index| {
+                move || {
+                    assert_ne!(index, 2, "task 2 panics");
+                    index * 10
+                }
+            }),
+        );
+        assert_eq!(results, [Some(0), Some(10), None, Some(30)]);
+
+        let results = run_all(&Lossy, (0..3).map(|index| move || index));
+        assert_eq!(results, [Some(0), None, Some(2)]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_pools_run_every_task() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .expect("build pool");
+        assert_eq!(pool.parallelism(), 2);
+        let results = run_all(&pool, (0..5).map(|index| move || index + 1));
+        assert_eq!(results, [Some(1), Some(2), Some(3), Some(4), Some(5)]);
+    }
+}
@@ -1,6 +1,8 @@
-use super::{fast_local_process_with_workers, FIELD_SEPARATOR};
+use super::{process_path, FastLocalConfig, FIELD_SEPARATOR};
 use crate::{
-    ByteRecord, CsvHeaderMode, CsvIngestError, CsvIngestSummary, CsvOptions, CsvParser, CsvResult,
+    ByteRecord, CsvColumnSchema, CsvErrorPolicy, CsvFilter, CsvHeaderMode, CsvIngestError,
+    CsvIngestSummary, CsvOptions, CsvParser, CsvProjection, CsvResult, CsvRule, CsvSchema,
+    CsvSelector, Position,
 };
 use crc32fast::Hasher as Crc32;
 use proptest::prelude::*;
@@ -9,6 +11,8 @@ use std::sync::OnceLock;
 use tempfile::NamedTempFile;
 
 const WORKER_COUNTS: [usize; 3] = [1, 2, 8];
+/// Whole files, windows shorter than most records, and a few records each.
+const WINDOW_SIZES: [Option<usize>; 3] = [None, Some(5), Some(61)];
 
 #[derive(Clone, Copy, Debug)]
 enum LineEnding {
@@ -25,6 +29,17 @@ impl LineEnding {
     }
 }
 
+/// How generated fields are written when quoting is enabled.
+#[derive(Clone, Copy, Debug)]
+enum QuoteStyle {
+    /// RFC 4180: fields that need it are quoted, with doubled quotes.
+    Doubled,
+    /// Fields that need it are quoted, with quotes and escapes escaped.
+    Escaped,
+    /// Fields are written as generated, so stray quotes are left to the parser.
+    Literal,
+}
+
 #[derive(Clone, Debug)]
 struct DifferentialCase {
     width: usize,
@@ -37,6 +52,11 @@ struct DifferentialCase {
     bom: bool,
     delimiter: u8,
     limit: Option<u64>,
+    error_policy: CsvErrorPolicy,
+    schema: bool,
+    projection: bool,
+    filter: bool,
+    quote_style: Option<QuoteStyle>,
 }
 
 impl DifferentialCase {
@@ -49,7 +69,10 @@ impl DifferentialCase {
                 CsvHeaderMode::Absent
             },
             flexible: self.flexible,
-            quoting: false,
+            quoting: self.quote_style.is_some(),
+            escape: matches!(self.quote_style, Some(QuoteStyle::Escaped)).then_some(b'\\'),
+            double_quote: !matches!(self.quote_style, Some(QuoteStyle::Escaped)),
+            error_policy: self.error_policy,
             ..CsvOptions::default()
         }
     }
@@ -62,6 +85,43 @@ impl DifferentialCase {
         }
     }
 
+    fn schema(&self) -> Option<CsvSchema> {
+        (self.headers && self.schema).then(|| CsvSchema {
+            columns: vec![
+                CsvColumnSchema::new(format!("column_{}", self.width - 1), [CsvRule::NotEmpty]),
+                CsvColumnSchema::new(
+                    "column_0",
+                    [
+                        #[cfg(feature = "schema_regex")]
+                        CsvRule::Pattern {
+                            pattern: "[a-m0-9 ]*".to_string(),
+                        },
+                        #[cfg(not(feature = "schema_regex"))]
+                        CsvRule::MaxWidth { max: 3 },
+                    ],
+                ),
+            ],
+        })
+    }
+
+    fn projection(&self) -> Option<CsvProjection> {
+        self.projection.then(|| {
+            let mut columns = vec![CsvSelector::Index(self.width / 2), CsvSelector::Index(0)];
+            if self.headers {
+                columns.insert(0, CsvSelector::Header(format!("column_{}", self.width - 1)));
+            }
+            CsvProjection { columns }
+        })
+    }
+
+    fn filters(&self) -> Vec<CsvFilter> {
+        if self.filter {
+            vec![CsvFilter::new(self.width / 2, |field| field.len() % 2 == 0)]
+        } else {
+            Vec::new()
+        }
+    }
+
     fn render(&self) -> Vec<u8> {
         let mut records = Vec::with_capacity(self.rows.len() + usize::from(self.headers));
         if self.headers {
@@ -83,7 +143,7 @@ impl DifferentialCase {
                 if field_index > 0 {
                     bytes.push(self.delimiter);
                 }
-                bytes.extend_from_slice(field);
+                self.render_field(field, &mut bytes);
             }
             if record_index < last_record || self.final_terminator {
                 bytes.extend_from_slice(self.line_ending.bytes());
@@ -91,6 +151,27 @@ impl DifferentialCase {
         }
         bytes
     }
+
+    fn render_field(&self, field: &[u8], bytes: &mut Vec<u8>) {
+        let special =
+            |byte: &u8| matches!(byte, b'"' | b'\\' | b'\r' | b'\n') || *byte == self.delimiter;
+        let (escape, escaped): (u8, &[u8]) = match self.quote_style {
+            Some(QuoteStyle::Doubled) if field.iter().any(special) => (b'"', b"\""),
+            Some(QuoteStyle::Escaped) if field.iter().any(special) => (b'\\', b"\"\\"),
+            _ => {
+                bytes.extend_from_slice(field);
+                return;
+            }
+        };
+        bytes.push(b'"');
+        for &byte in field {
+            if escaped.contains(&byte) {
+                bytes.push(escape);
+            }
+            bytes.push(byte);
+        }
+        bytes.push(b'"');
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -105,11 +186,19 @@ enum ErrorFingerprint {
     MissingField {
         row: u64,
         header: String,
+        position: Option<Position>,
     },
     RaggedRow {
         row: Option<u64>,
         expected: u64,
         actual: u64,
+        position: Option<Position>,
+    },
+    SchemaViolation {
+        row: u64,
+        column: String,
+        rule: CsvRule,
+        position: Option<Position>,
     },
     Other(String),
 }
@@ -119,36 +208,78 @@ type Outcome = Result<VerifiedOutput, ErrorFingerprint>;
 fn fingerprint(error: CsvIngestError) -> ErrorFingerprint {
     match error {
         CsvIngestError::MissingHeader(header) => ErrorFingerprint::MissingHeader(header),
-        CsvIngestError::MissingRequiredField { row, header } => {
-            ErrorFingerprint::MissingField { row, header }
-        }
+        CsvIngestError::MissingRequiredField {
+            row,
+            header,
+            position,
+            ..
+        } => ErrorFingerprint::MissingField {
+            row,
+            header,
+            position,
+        },
         CsvIngestError::RaggedRow {
             row,
             expected,
             actual,
+            position,
+            ..
         } => ErrorFingerprint::RaggedRow {
             row,
             expected,
             actual,
+            position,
+        },
+        CsvIngestError::SchemaViolation {
+            row,
+            column,
+            rule,
+            position,
+            ..
+        } => ErrorFingerprint::SchemaViolation {
+            row,
+            column,
+            rule: *rule,
+            position,
         },
         error => ErrorFingerprint::Other(format!("{error:?}")),
     }
 }
 
+/// Row-level behavior applied to both parsers, in the order the streaming
+/// parser installs it.
+#[derive(Clone, Copy, Default)]
+struct RowRules<'a> {
+    projection: Option<&'a CsvProjection>,
+    filters: &'a [CsvFilter],
+    schema: Option<&'a CsvSchema>,
+}
+
 async fn streaming_outcome(
     bytes: &[u8],
     required_headers: &[&str],
     options: &CsvOptions,
+    rules: RowRules<'_>,
     limit: Option<u64>,
 ) -> Outcome {
     async fn parse(
         bytes: &[u8],
         required_headers: &[&str],
         options: &CsvOptions,
+        rules: RowRules<'_>,
         limit: Option<u64>,
     ) -> CsvResult<VerifiedOutput> {
         let mut parser =
             CsvParser::from_reader(Cursor::new(bytes), required_headers, options).await?;
+        if let Some(projection) = rules.projection {
+            parser = parser.with_projection(projection)?;
+        }
+        for filter in rules.filters {
+            parser = parser.with_filter(filter.clone())?;
+        }
+        if let Some(schema) = rules.schema {
+            parser = parser.with_schema(schema)?;
+        }
         let headers = parser
             .headers()
             .iter()
@@ -175,12 +306,16 @@ async fn streaming_outcome(
             summary: CsvIngestSummary {
                 row_count: parser.records_read(),
                 headers,
+                scanned_rows: parser.rows_scanned(),
+                filtered_rows: parser.filtered_rows(),
+                rejected_rows: parser.rejected_rows(),
+                diagnostics: parser.diagnostics().to_vec(),
             },
             crc: crc.finalize(),
         })
     }
 
-    parse(bytes, required_headers, options, limit)
+    parse(bytes, required_headers, options, rules, limit)
         .await
         .map_err(fingerprint)
 }
@@ -189,10 +324,20 @@ fn fast_outcome(
     file: &NamedTempFile,
     required_headers: &[&str],
     options: &CsvOptions,
+    rules: RowRules<'_>,
     limit: Option<u64>,
     workers: usize,
+    window_size: Option<usize>,
 ) -> Outcome {
-    fast_local_process_with_workers(file.path(), required_headers, options, true, limit, workers)
+    let config = FastLocalConfig {
+        workers: std::num::NonZeroUsize::new(workers),
+        window_size: window_size.and_then(std::num::NonZeroUsize::new),
+        schema: rules.schema,
+        projection: rules.projection,
+        filters: rules.filters,
+        ..FastLocalConfig::default()
+    };
+    process_path(file.path(), required_headers, options, true, limit, config)
         .map(|(summary, crc)| VerifiedOutput {
             summary,
             crc: crc.expect("verification requested"),
@@ -222,24 +367,41 @@ fn assert_fixture_matches(
     let required: Vec<&str> = required_headers.iter().map(String::as_str).collect();
 
     for &limit in limits {
-        let expected = runtime().block_on(streaming_outcome(bytes, &required, options, limit));
+        let expected = runtime().block_on(streaming_outcome(
+            bytes,
+            &required,
+            options,
+            RowRules::default(),
+            limit,
+        ));
         for workers in WORKER_COUNTS {
-            let actual = fast_outcome(&file, &required, options, limit, workers);
-            assert_eq!(
-                actual, expected,
-                "fixture={bytes:?}, options={options:?}, limit={limit:?}, workers={workers}"
-            );
+            for window_size in WINDOW_SIZES {
+                let actual = fast_outcome(
+                    &file,
+                    &required,
+                    options,
+                    RowRules::default(),
+                    limit,
+                    workers,
+                    window_size,
+                );
+                assert_eq!(
+                    actual, expected,
+                    "fixture={bytes:?}, options={options:?}, limit={limit:?}, workers={workers}, \
+                     window_size={window_size:?}"
+                );
+            }
         }
     }
 }
 
-fn field_strategy() -> impl Strategy<Value = Vec<u8>> {
-    prop::collection::vec(
-        prop::sample::select(
-            b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 _-".to_vec(),
-        ),
-        0..=16,
-    )
+fn field_strategy(quoted: bool) -> impl Strategy<Value = Vec<u8>> {
+    let mut alphabet =
+        b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 _-".to_vec();
+    if quoted {
+        alphabet.extend_from_slice(b"\"\"\\,;|\t\r\n");
+    }
+    prop::collection::vec(prop::sample::select(alphabet), 0..=16)
 }
 
 fn differential_case_strategy() -> impl Strategy<Value = DifferentialCase> {
@@ -255,6 +417,23 @@ fn differential_case_strategy() -> impl Strategy<Value = DifferentialCase> {
         any::<bool>(),
         prop::sample::select(vec![b',', b';', b'|', b'\t']),
         prop_oneof![3 => Just(None), 1 => (0u64..=20).prop_map(Some)],
+        (
+            prop_oneof![
+                3 => Just(CsvErrorPolicy::FailFast),
+                1 => Just(CsvErrorPolicy::Skip),
+                1 => (0u64..=3).prop_map(CsvErrorPolicy::SkipUpTo),
+                1 => (0u8..=100).prop_map(CsvErrorPolicy::SkipUpToPercent),
+            ],
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            prop_oneof![
+                2 => Just(None),
+                1 => Just(Some(QuoteStyle::Doubled)),
+                1 => Just(Some(QuoteStyle::Escaped)),
+                1 => Just(Some(QuoteStyle::Literal)),
+            ],
+        ),
     )
         .prop_flat_map(
             |(
@@ -269,15 +448,17 @@ fn differential_case_strategy() -> impl Strategy<Value = DifferentialCase> {
                 bom,
                 delimiter,
                 limit,
+                (error_policy, schema, projection, filter, quote_style),
             )| {
+                let quoted = quote_style.is_some();
                 let row = if ragged {
                     (1usize..=(width + 2))
-                        .prop_flat_map(|row_width| {
-                            prop::collection::vec(field_strategy(), row_width)
+                        .prop_flat_map(move |row_width| {
+                            prop::collection::vec(field_strategy(quoted), row_width)
                         })
                         .boxed()
                 } else {
-                    prop::collection::vec(field_strategy(), width).boxed()
+                    prop::collection::vec(field_strategy(quoted), width).boxed()
                 };
 
                 prop::collection::vec(row, row_count).prop_map(move |rows| DifferentialCase {
@@ -290,7 +471,13 @@ fn differential_case_strategy() -> impl Strategy<Value = DifferentialCase> {
                     require_last_header,
                     bom,
                     delimiter,
-                    limit,
+                    // Limits count accepted streaming records but scanned fast-local rows.
+                    limit: limit.filter(|_| error_policy == CsvErrorPolicy::FailFast && !filter),
+                    error_policy,
+                    schema,
+                    projection,
+                    filter,
+                    quote_style,
                 })
             },
         )
@@ -309,24 +496,44 @@ proptest! {
         let options = case.options();
         let required_headers = case.required_headers();
         let required: Vec<&str> = required_headers.iter().map(String::as_str).collect();
+        let schema = case.schema();
+        let projection = case.projection();
+        let filters = case.filters();
+        let rules = RowRules {
+            projection: projection.as_ref(),
+            filters: &filters,
+            schema: schema.as_ref(),
+        };
         let expected = runtime().block_on(streaming_outcome(
             &bytes,
             &required,
             &options,
+            rules,
             case.limit,
         ));
         let file = fixture(&bytes);
 
         for workers in WORKER_COUNTS {
-            let actual = fast_outcome(&file, &required, &options, case.limit, workers);
-            prop_assert_eq!(
-                &actual,
-                &expected,
-                "case={:#?}\nfixture={:?}\nworkers={}",
-                case,
-                bytes,
-                workers
-            );
+            for window_size in WINDOW_SIZES {
+                let actual = fast_outcome(
+                    &file,
+                    &required,
+                    &options,
+                    rules,
+                    case.limit,
+                    workers,
+                    window_size,
+                );
+                prop_assert_eq!(
+                    &actual,
+                    &expected,
+                    "case={:#?}\nfixture={:?}\nworkers={}\nwindow_size={:?}",
+                    case,
+                    bytes,
+                    workers,
+                    window_size
+                );
+            }
         }
     }
 }
@@ -350,6 +557,8 @@ fn explicit_edge_case_matrix_matches_streaming() {
         &b"\n\r\na,b\n1,2\n"[..],
         &b"a,b,c\n,middle,\n"[..],
         &b"a,b\n1\n"[..],
+        &b"a,b\r\n\r\n1,2\r\n\n3\n"[..],
+        &b"\xef\xbb\xbf\na,b\n1,2,3"[..],
     ] {
         assert_fixture_matches(bytes, &no_required, &default, &[None]);
     }
@@ -0,0 +1,420 @@
+use super::{
+    next_record_terminator, process_path_each, process_row, trim_ascii_if, ChunkSink,
+    FastLocalConfig, FieldBuf, Row, RowPlan, RowSink,
+};
+use crate::record_batch::{infer_schema, resolve_fields, BatchBuilder};
+use crate::{
+    ByteRecord, CsvArrowOptions, CsvHeaderMode, CsvIngestSummary, CsvOptions, CsvRecordBatches,
+    CsvResult, Position,
+};
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Read a local file into Arrow record batches, converting chunks on parallel workers.
+///
+/// Rows are validated, filtered, and projected as by
+/// [`super::fast_local_process_with_config`], and converted as by
+/// `CsvParser::into_record_batches`. When `arrow.schema` is unset, it is
+/// inferred from the first `batch_size` accepted rows before the workers
+/// start. Each worker converts its own chunk of the file, so a batch holds at
+/// most `batch_size` rows and never spans two chunks. Batches are returned in
+/// file order, and the summary matches `fast_local_process_with_config`.
+///
+/// A value that cannot be converted returns `CsvIngestError::InvalidValue`
+/// with its global row and streaming position, whatever the error policy.
+pub fn fast_local_record_batches(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    limit_rows: Option<u64>,
+    arrow: &CsvArrowOptions,
+    config: FastLocalConfig<'_>,
+) -> CsvResult<CsvRecordBatches> {
+    let mut batches = Vec::new();
+    let (schema, summary) = record_batches_each(
+        path,
+        required_headers,
+        options,
+        limit_rows,
+        arrow,
+        config,
+        |chunk| {
+            batches.extend(chunk);
+            Ok(())
+        },
+    )?;
+    Ok(CsvRecordBatches {
+        schema,
+        batches,
+        summary,
+    })
+}
+
+/// Convert a local file as [`fast_local_record_batches`] does, passing each
+/// chunk's batches to `each` in file order as soon as the chunks before it
+/// are merged, and return the schema with the summary.
+pub(crate) fn record_batches_each(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    limit_rows: Option<u64>,
+    arrow: &CsvArrowOptions,
+    config: FastLocalConfig<'_>,
+    each: impl FnMut(Vec<RecordBatch>) -> CsvResult<()>,
+) -> CsvResult<(SchemaRef, CsvIngestSummary)> {
+    arrow.validate()?;
+    let mut sink = BatchSink {
+        options: arrow,
+        columns: None,
+    };
+    let (summary, _) = process_path_each(
+        path,
+        required_headers,
+        options,
+        false,
+        limit_rows,
+        config,
+        &mut sink,
+        each,
+    )?;
+    let schema = match sink.columns {
+        Some(columns) => columns.schema,
+        // The file is empty, so no headers were resolved.
+        None => {
+            let schema = arrow
+                .schema
+                .clone()
+                .unwrap_or_else(|| Arc::new(Schema::empty()));
+            let headers = (options.headers == CsvHeaderMode::Present).then_some(&[][..]);
+            resolve_fields(&schema, headers)?;
+            schema
+        }
+    };
+    Ok((schema, summary))
+}
+
+struct BatchSink<'a> {
+    options: &'a CsvArrowOptions,
+    columns: Option<BatchColumns>,
+}
+
+/// The output schema with the source field index of each of its fields.
+struct BatchColumns {
+    schema: SchemaRef,
+    sources: Vec<usize>,
+    trim: bool,
+}
+
+impl RowSink for BatchSink<'_> {
+    type Chunk = BatchChunk;
+
+    fn prepare(
+        &mut self,
+        headers: &[String],
+        plan: &mut RowPlan<'_>,
+        body: &[u8],
+    ) -> CsvResult<()> {
+        let headers = (plan.options.headers == CsvHeaderMode::Present).then_some(headers);
+        let schema = match &self.options.schema {
+            Some(schema) => Arc::clone(schema),
+            None => infer_schema(
+                headers,
+                &sample(plan, body, self.options.batch_size),
+                &self.options.null_tokens,
+            ),
+        };
+        let slot_sources = plan.projection.as_ref().map(|slots| {
+            let mut sources = vec![0; plan.projected_width];
+            for &(index, slot) in slots {
+                sources[slot] = index;
+            }
+            sources
+        });
+        let sources: Vec<_> = resolve_fields(&schema, headers)?
+            .into_iter()
+            .map(|index| match &slot_sources {
+                // A headerless field past the projection is always null.
+                Some(sources) => sources.get(index).copied().unwrap_or(usize::MAX),
+                None => index,
+            })
+            .collect();
+        let width = sources
+            .iter()
+            .filter(|&&index| index != usize::MAX)
+            .map(|index| index + 1)
+            .max()
+            .unwrap_or_default();
+        plan.field_limit = plan.field_limit.map(|limit| limit.max(width));
+        self.columns = Some(BatchColumns {
+            schema,
+            sources,
+            trim: plan.options.trims_fields(),
+        });
+        Ok(())
+    }
+
+    fn chunk(&self) -> BatchChunk {
+        let columns = self
+            .columns
+            .as_ref()
+            .expect("columns are resolved before chunks are processed");
+        BatchChunk {
+            builder: BatchBuilder::new(
+                Arc::clone(&columns.schema),
+                columns.sources.clone(),
+                &self.options.null_tokens,
+            ),
+            batch_size: self.options.batch_size,
+            trim: columns.trim,
+            batches: Vec::new(),
+        }
+    }
+}
+
+/// Converts the accepted rows of one chunk.
+struct BatchChunk {
+    builder: BatchBuilder,
+    batch_size: usize,
+    trim: bool,
+    batches: Vec<RecordBatch>,
+}
+
+impl ChunkSink for BatchChunk {
+    type Output = Vec<RecordBatch>;
+
+    fn accept(&mut self, row: &Row<'_>, offset: usize, row_number: u64) -> CsvResult<()> {
+        let trim = self.trim;
+        self.builder.push(
+            |index| row.get(index).map(|field| trim_ascii_if(field, trim)),
+            row_number,
+            Some(Position::new().set_byte(offset as u64).clone()),
+        );
+        if self.builder.len() == self.batch_size {
+            self.batches.push(self.builder.finish()?);
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> CsvResult<Vec<RecordBatch>> {
+        if self.builder.len() > 0 {
+            self.batches.push(self.builder.finish()?);
+        }
+        Ok(self.batches)
+    }
+}
+
+/// Return up to `limit` accepted rows from the start of `body`, as output records.
+fn sample(plan: &RowPlan<'_>, body: &[u8], limit: usize) -> Vec<ByteRecord> {
+    let options = plan.options;
+    let trim = options.trims_fields();
+    let mut slots = vec![(0, 0); plan.projected_width];
+    let mut fields = FieldBuf::default();
+    let mut sample = Vec::new();
+    let mut row_number = 0u64;
+    let mut cursor = 0usize;
+    while cursor < body.len() && sample.len() < limit {
+        let (record_end, next_record) = next_record_terminator(body, cursor, body.len(), options)
+            .unwrap_or((body.len(), body.len()));
+        let raw = &body[cursor..record_end];
+        cursor = next_record;
+        if raw.is_empty() {
+            continue;
+        }
+        row_number += 1;
+        let row = fields.split(raw, options, None);
+        // Rejected rows are reported by the workers.
+        if !matches!(
+            process_row(&row, plan, None, &mut slots, row_number),
+            Ok(true)
+        ) {
+            continue;
+        }
+        let record = if plan.projection.is_some() {
+            slots
+                .iter()
+                .map(|&(start, end)| &row.bytes()[start..end])
+                .collect()
+        } else {
+            row.iter().map(|field| trim_ascii_if(field, trim)).collect()
+        };
+        sample.push(record);
+    }
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CsvErrorPolicy, CsvIngestError, CsvParser, CsvProjection};
+    use arrow::compute::concat_batches;
+    use arrow::datatypes::{DataType, Field};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn with_workers<'a>(count: usize) -> FastLocalConfig<'a> {
+        FastLocalConfig {
+            workers: std::num::NonZeroUsize::new(count),
+            ..FastLocalConfig::default()
+        }
+    }
+
+    fn fixture(contents: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().expect("create fixture");
+        file.write_all(contents).expect("write fixture");
+        file
+    }
+
+    async fn streaming_batch(contents: &'static [u8], arrow: &CsvArrowOptions) -> RecordBatch {
+        let mut reader = CsvParser::from_reader(contents, &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .into_record_batches(arrow)
+            .await
+            .expect("resolve schema");
+        let mut batches = Vec::new();
+        while let Some(batch) = reader.next_batch().await.expect("read batch") {
+            batches.push(batch);
+        }
+        concat_batches(&reader.schema(), &batches).expect("concatenate")
+    }
+
+    #[tokio::test]
+    async fn parallel_batches_match_the_streaming_reader() {
+        let mut contents = b"id,price,name\n".to_vec();
+        for row in 0..200 {
+            let price = if row % 7 == 0 {
+                String::new()
+            } else {
+                format!("{row}.5")
+            };
+            contents.extend(format!("{row},{price},n{row}\n").as_bytes());
+        }
+        let contents: &'static [u8] = contents.leak();
+        let file = fixture(contents);
+        let arrow = CsvArrowOptions {
+            batch_size: 16,
+            ..CsvArrowOptions::default()
+        };
+        let expected = streaming_batch(contents, &arrow).await;
+
+        for workers in [1, 3, 8] {
+            let output = fast_local_record_batches(
+                file.path(),
+                &[],
+                &CsvOptions::default(),
+                None,
+                &arrow,
+                with_workers(workers),
+            )
+            .expect("read batches");
+            assert!(output.batches.iter().all(|batch| batch.num_rows() <= 16));
+            assert_eq!(output.summary.row_count, 200);
+            let combined = concat_batches(&output.schema, &output.batches).expect("concatenate");
+            assert_eq!(combined, expected, "{workers} workers");
+        }
+    }
+
+    #[test]
+    fn projected_batches_map_schema_fields_to_source_columns() {
+        let file = fixture(b"sku,qty,note\na,1,x\nb,2,y\n");
+        let projection = CsvProjection::new(["qty", "sku"]);
+        let arrow = CsvArrowOptions {
+            batch_size: 8,
+            schema: Some(Arc::new(Schema::new(vec![
+                Field::new("sku", DataType::Utf8, false),
+                Field::new("qty", DataType::Int32, false),
+            ]))),
+            ..CsvArrowOptions::default()
+        };
+        let output = fast_local_record_batches(
+            file.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            &arrow,
+            FastLocalConfig {
+                projection: Some(&projection),
+                ..with_workers(2)
+            },
+        )
+        .expect("read batches");
+
+        assert_eq!(output.summary.headers, ["qty", "sku"]);
+        let combined = concat_batches(&output.schema, &output.batches).expect("concatenate");
+        let expected = RecordBatch::try_new(
+            Arc::clone(&output.schema),
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["a", "b"])),
+                Arc::new(arrow::array::Int32Array::from(vec![1, 2])),
+            ],
+        )
+        .expect("expected batch");
+        assert_eq!(combined, expected);
+    }
+
+    #[test]
+    fn conversion_errors_are_located_globally_and_never_skipped() {
+        let file = fixture(b"id\n1\n2\n3\nx\n5\n");
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let arrow = CsvArrowOptions {
+            batch_size: 1,
+            schema: Some(Arc::new(Schema::new(vec![Field::new(
+                "id",
+                DataType::Int64,
+                true,
+            )]))),
+            ..CsvArrowOptions::default()
+        };
+        let error =
+            fast_local_record_batches(file.path(), &[], &options, None, &arrow, with_workers(3))
+                .expect_err("x is not an integer");
+
+        assert!(matches!(
+            error,
+            CsvIngestError::InvalidValue { row: 4, ref column, .. } if column == "id"
+        ));
+        assert_eq!(error.position().map(Position::byte), Some(9));
+        assert_eq!(error.position().map(Position::record), Some(4));
+        assert!(error.source_name().is_some());
+    }
+
+    #[test]
+    fn empty_files_resolve_the_supplied_schema() {
+        let file = fixture(b"");
+        let output = fast_local_record_batches(
+            file.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            &CsvArrowOptions::default(),
+            with_workers(2),
+        )
+        .expect("empty file");
+        assert!(output.batches.is_empty());
+        assert_eq!(output.schema.fields().len(), 0);
+
+        let arrow = CsvArrowOptions {
+            schema: Some(Arc::new(Schema::new(vec![Field::new(
+                "id",
+                DataType::Int64,
+                true,
+            )]))),
+            ..CsvArrowOptions::default()
+        };
+        let error = fast_local_record_batches(
+            file.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            &arrow,
+            with_workers(2),
+        )
+        .expect_err("the file has no id header");
+        assert!(matches!(error, CsvIngestError::MissingHeader(name) if name == "id"));
+    }
+}
@@ -0,0 +1,767 @@
+use super::{
+    process_path_each, process_path_into, streamed, trim_ascii_if, ChunkSink, FastLocalConfig, Row,
+    RowPlan, RowSink,
+};
+use crate::{CsvIngestSummary, CsvOptions, CsvResult};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// One accepted record of a fast-local file.
+///
+/// Fields borrow from the file's bytes in memory, or from the chunk of records being
+/// read, unless the record had quoted fields, which are unquoted into a
+/// buffer. Fields are
+/// trimmed as `CsvOptions::trim` asks and, with a projection, are the selected
+/// columns in selection order.
+#[derive(Debug, Clone, Copy)]
+pub struct FastLocalRecord<'a> {
+    row: Row<'a>,
+    /// The source field of each projected field.
+    sources: Option<&'a [usize]>,
+    trim: bool,
+    offset: usize,
+}
+
+impl<'a> FastLocalRecord<'a> {
+    /// The number of fields in the record.
+    pub fn len(&self) -> usize {
+        self.sources.map_or(self.row.len(), <[usize]>::len)
+    }
+
+    /// Whether the record has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the field at `index`, if the record has one.
+    pub fn get(&self, index: usize) -> Option<&'a [u8]> {
+        let source = match self.sources {
+            Some(sources) => *sources.get(index)?,
+            None => index,
+        };
+        self.row
+            .get(source)
+            .map(|field| trim_ascii_if(field, self.trim))
+    }
+
+    /// Iterate over the record's fields.
+    pub fn iter(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+
+    /// The byte offset of the record's first byte in the file.
+    pub fn offset(&self) -> u64 {
+        self.offset as u64
+    }
+}
+
+/// Receives the accepted records of one fast-local chunk, in file order.
+///
+/// [`fast_local_visit`] creates a visitor for every chunk, on the worker that
+/// processes it. Any `FnMut(&FastLocalRecord<'_>) -> CsvResult<()>` closure is
+/// a visitor with no output.
+pub trait FastLocalVisitor: Send {
+    type Output: Send;
+
+    /// Take one accepted record. An error stops the whole run.
+    fn visit(&mut self, record: &FastLocalRecord<'_>) -> CsvResult<()>;
+
+    /// Return the chunk's output once its last record has been visited.
+    fn finish(self) -> CsvResult<Self::Output>;
+}
+
+impl<F> FastLocalVisitor for F
+where
+    F: FnMut(&FastLocalRecord<'_>) -> CsvResult<()> + Send,
+{
+    type Output = ();
+
+    fn visit(&mut self, record: &FastLocalRecord<'_>) -> CsvResult<()> {
+        self(record)
+    }
+
+    fn finish(self) -> CsvResult<()> {
+        Ok(())
+    }
+}
+
+/// Pass the accepted records of a local file to visitors on parallel workers.
+///
+/// Rows are validated, filtered, and projected as by
+/// [`super::fast_local_process_with_config`], and only accepted rows are
+/// visited. `new_visitor` is called once per chunk and the outputs are
+/// returned in file order with the summary.
+///
+/// Chunks run concurrently, so a visitor sees only its own chunk. When a
+/// chunk's start was misjudged inside a quoted field, the chunk is processed
+/// again by a new visitor and the first visitor's output is discarded; a
+/// failing chunk can also leave later chunks visited. Visitors should
+/// therefore collect into their output rather than act on records directly.
+pub fn fast_local_visit<V, F>(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+    new_visitor: F,
+) -> CsvResult<(CsvIngestSummary, Vec<V::Output>)>
+where
+    V: FastLocalVisitor,
+    F: Fn() -> V + Sync,
+{
+    let mut sink = VisitSink {
+        new_visitor,
+        layout: None,
+    };
+    let (summary, _, outputs) = process_path_into(
+        path,
+        required_headers,
+        options,
+        false,
+        limit_rows,
+        config,
+        &mut sink,
+    )?;
+    Ok((summary, outputs))
+}
+
+/// Fold the accepted records of a local file on parallel workers.
+///
+/// Each chunk of the file starts from `init()` and folds its records in order
+/// with `fold`. The chunk results are then merged with `combine` in file order,
+/// starting from `init()`, as the chunk CRCs of
+/// [`super::fast_local_process`] are, so `combine` need not be commutative.
+/// Rows are validated, filtered, and projected as by
+/// [`super::fast_local_process_with_config`]. An error from `fold` stops the
+/// run.
+///
+/// A chunk processed again after its start was misjudged is folded again from
+/// `init()`, so `fold` should not have side effects.
+#[allow(clippy::too_many_arguments)]
+pub fn fast_local_fold<A, I, F, C>(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+    init: I,
+    fold: F,
+    combine: C,
+) -> CsvResult<(CsvIngestSummary, A)>
+where
+    A: Send,
+    I: Fn() -> A + Sync,
+    F: Fn(A, &FastLocalRecord<'_>) -> CsvResult<A> + Sync,
+    C: FnMut(A, A) -> A,
+{
+    let (summary, outputs) =
+        fast_local_visit(path, required_headers, options, limit_rows, config, || {
+            Fold {
+                acc: Some(init()),
+                fold: &fold,
+            }
+        })?;
+    Ok((summary, outputs.into_iter().fold(init(), combine)))
+}
+
+/// Folds the records of one chunk.
+struct Fold<'f, A, F> {
+    /// Taken only while a record is folded.
+    acc: Option<A>,
+    fold: &'f F,
+}
+
+impl<A, F> FastLocalVisitor for Fold<'_, A, F>
+where
+    A: Send,
+    F: Fn(A, &FastLocalRecord<'_>) -> CsvResult<A> + Sync,
+{
+    type Output = A;
+
+    fn visit(&mut self, record: &FastLocalRecord<'_>) -> CsvResult<()> {
+        let acc = self
+            .acc
+            .take()
+            .expect("the accumulator is restored after every record");
+        self.acc = Some((self.fold)(acc, record)?);
+        Ok(())
+    }
+
+    fn finish(self) -> CsvResult<A> {
+        Ok(self
+            .acc
+            .expect("the accumulator is restored after every record"))
+    }
+}
+
+/// The accepted records of a local file, read in file order by
+/// [`FastLocalRecords::next_record`] while later chunks are parsed.
+///
+/// This is not an [`Iterator`]: each record borrows the chunk that the next
+/// call may replace, and `Iterator::next` cannot return items that borrow from
+/// the iterator.
+pub struct FastLocalRecords {
+    chunks: Receiver<RecordChunk>,
+    chunk: RecordChunk,
+    /// The index in `chunk.records` of the next record.
+    next: usize,
+}
+
+impl FastLocalRecords {
+    /// Return the next accepted record, or `None` after the last one or once
+    /// the parse has failed.
+    pub fn next_record(&mut self) -> Option<FastLocalRecord<'_>> {
+        while self.next == self.chunk.records.len() {
+            self.chunk = self.chunks.recv().ok()?;
+            self.next = 0;
+        }
+        self.next += 1;
+        Some(self.chunk.record(self.next - 1))
+    }
+}
+
+/// Read the accepted records of a local file in file order, parsing later
+/// chunks on parallel workers while `read` takes earlier ones.
+///
+/// Rows are validated, filtered, and projected as by
+/// [`super::fast_local_process_with_config`], and each is split into fields
+/// once, on a worker. `read` runs on its own thread and receives a window's
+/// records once the window is parsed, while the next one is parsed; at most
+/// about two windows of records wait for it. Unless `config.window_size` is
+/// set, windows are 8 MiB per worker.
+///
+/// A record-level error that stops the parse ends the records early and is
+/// returned in place of `read`'s result, as is a change to the file. When
+/// `read` returns before the last record, the rest of the file is still
+/// validated, so the summary covers the whole file; set `limit_rows` to stop
+/// sooner.
+pub fn fast_local_records<T, F>(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    limit_rows: Option<u64>,
+    config: FastLocalConfig<'_>,
+    read: F,
+) -> CsvResult<(CsvIngestSummary, T)>
+where
+    T: Send,
+    F: FnOnce(&mut FastLocalRecords) -> T + Send,
+{
+    let (sender, chunks) = mpsc::sync_channel(config.worker_count());
+    thread::scope(|scope| {
+        let reader = scope.spawn(move || {
+            read(&mut FastLocalRecords {
+                chunks,
+                chunk: RecordChunk::default(),
+                next: 0,
+            })
+        });
+        let parsed = process_path_each(
+            path,
+            required_headers,
+            options,
+            false,
+            limit_rows,
+            streamed(config),
+            &mut RecordSink { layout: None },
+            move |chunk| {
+                // Once `read` has returned, the rest of the file is only validated.
+                let _ = sender.send(chunk);
+                Ok(())
+            },
+        );
+        let read = reader
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        parsed.map(|(summary, _)| (summary, read))
+    })
+}
+
+/// How the fields of an accepted row are presented.
+#[derive(Clone, Default)]
+struct RecordLayout {
+    sources: Option<Vec<usize>>,
+    trim: bool,
+}
+
+impl RecordLayout {
+    /// Resolve the layout of `plan`'s rows, which must be split in full
+    /// unless they are projected.
+    fn new(plan: &mut RowPlan<'_>) -> Self {
+        if plan.projection.is_none() {
+            plan.field_limit = None;
+        }
+        let sources = plan.projection.as_ref().map(|slots| {
+            let mut sources = vec![0; plan.projected_width];
+            for &(index, slot) in slots {
+                sources[slot] = index;
+            }
+            sources
+        });
+        Self {
+            sources,
+            trim: plan.options.trims_fields(),
+        }
+    }
+
+    fn record<'a>(&'a self, row: Row<'a>, offset: usize) -> FastLocalRecord<'a> {
+        FastLocalRecord {
+            row,
+            sources: self.sources.as_deref(),
+            trim: self.trim,
+            offset,
+        }
+    }
+}
+
+struct VisitSink<F> {
+    new_visitor: F,
+    layout: Option<RecordLayout>,
+}
+
+impl<V, F> RowSink for VisitSink<F>
+where
+    V: FastLocalVisitor,
+    F: Fn() -> V + Sync,
+{
+    type Chunk = VisitChunk<V>;
+
+    fn prepare(&mut self, _: &[String], plan: &mut RowPlan<'_>, _: &[u8]) -> CsvResult<()> {
+        self.layout = Some(RecordLayout::new(plan));
+        Ok(())
+    }
+
+    fn chunk(&self) -> VisitChunk<V> {
+        VisitChunk {
+            visitor: (self.new_visitor)(),
+            layout: self
+                .layout
+                .clone()
+                .expect("the layout is resolved before chunks are processed"),
+        }
+    }
+}
+
+struct VisitChunk<V> {
+    visitor: V,
+    layout: RecordLayout,
+}
+
+impl<V: FastLocalVisitor> ChunkSink for VisitChunk<V> {
+    type Output = V::Output;
+
+    fn accept(&mut self, row: &Row<'_>, offset: usize, _: u64) -> CsvResult<()> {
+        self.visitor.visit(&self.layout.record(*row, offset))
+    }
+
+    fn finish(self) -> CsvResult<V::Output> {
+        self.visitor.finish()
+    }
+}
+
+/// Copies every accepted row out of the file for [`FastLocalRecords`].
+struct RecordSink {
+    layout: Option<RecordLayout>,
+}
+
+impl RowSink for RecordSink {
+    type Chunk = RecordChunk;
+
+    fn prepare(&mut self, _: &[String], plan: &mut RowPlan<'_>, _: &[u8]) -> CsvResult<()> {
+        self.layout = Some(RecordLayout::new(plan));
+        Ok(())
+    }
+
+    fn chunk(&self) -> RecordChunk {
+        RecordChunk {
+            layout: self
+                .layout
+                .clone()
+                .expect("the layout is resolved before chunks are processed"),
+            ..RecordChunk::default()
+        }
+    }
+}
+
+/// The accepted rows of one chunk, split into fields.
+#[derive(Default)]
+struct RecordChunk {
+    layout: RecordLayout,
+    bytes: Vec<u8>,
+    fields: Vec<(usize, usize)>,
+    /// Where each record ends in `bytes` and `fields`, and its offset in the
+    /// file.
+    records: Vec<(usize, usize, usize)>,
+}
+
+impl RecordChunk {
+    fn record(&self, index: usize) -> FastLocalRecord<'_> {
+        let (bytes_start, fields_start) = index.checked_sub(1).map_or((0, 0), |previous| {
+            let (bytes_end, fields_end, _) = self.records[previous];
+            (bytes_end, fields_end)
+        });
+        let (bytes_end, fields_end, offset) = self.records[index];
+        let row = Row::new(
+            &self.bytes[bytes_start..bytes_end],
+            &self.fields[fields_start..fields_end],
+        );
+        self.layout.record(row, offset)
+    }
+}
+
+impl ChunkSink for RecordChunk {
+    type Output = Self;
+
+    fn accept(&mut self, row: &Row<'_>, offset: usize, _: u64) -> CsvResult<()> {
+        self.bytes.extend_from_slice(row.bytes());
+        self.fields.extend_from_slice(row.ranges());
+        self.records
+            .push((self.bytes.len(), self.fields.len(), offset));
+        Ok(())
+    }
+
+    fn finish(self) -> CsvResult<Self> {
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CsvErrorPolicy, CsvIngestError, CsvProjection, CsvTrim};
+    use std::io::Write;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tempfile::NamedTempFile;
+
+    fn with_workers<'a>(count: usize) -> FastLocalConfig<'a> {
+        FastLocalConfig {
+            workers: std::num::NonZeroUsize::new(count),
+            ..FastLocalConfig::default()
+        }
+    }
+
+    fn fixture(contents: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().expect("create fixture");
+        file.write_all(contents).expect("write fixture");
+        file
+    }
+
+    /// Rows with quoted fields, embedded newlines, and one ragged row.
+    fn contents() -> Vec<u8> {
+        let mut contents = b"id,note\n".to_vec();
+        for row in 0..300 {
+            match row % 5 {
+                0 => contents.extend(format!("{row},\"two\nlines, \"\"quoted\"\"\"\n").as_bytes()),
+                1 => contents.extend(format!("{row}\n").as_bytes()),
+                _ => contents.extend(format!("{row},n{row}\n").as_bytes()),
+            }
+        }
+        contents
+    }
+
+    fn expected(contents: &[u8]) -> Vec<(u64, Vec<Vec<u8>>)> {
+        let mut expected = Vec::new();
+        let mut offset = b"id,note\n".len();
+        for row in 0..300 {
+            let (fields, len) = match row % 5 {
+                0 => (
+                    vec![
+                        row.to_string().into_bytes(),
+                        b"two\nlines, \"quoted\"".to_vec(),
+                    ],
+                    format!("{row},\"two\nlines, \"\"quoted\"\"\"\n").len(),
+                ),
+                1 => (Vec::new(), format!("{row}\n").len()),
+                _ => (
+                    vec![row.to_string().into_bytes(), format!("n{row}").into_bytes()],
+                    format!("{row},n{row}\n").len(),
+                ),
+            };
+            if !fields.is_empty() {
+                expected.push((offset as u64, fields));
+            }
+            offset += len;
+        }
+        assert_eq!(offset, contents.len());
+        expected
+    }
+
+    fn collect(record: &FastLocalRecord<'_>) -> (u64, Vec<Vec<u8>>) {
+        (record.offset(), record.iter().map(<[u8]>::to_vec).collect())
+    }
+
+    /// Collects the records of one chunk.
+    #[derive(Default)]
+    struct Collect(Vec<(u64, Vec<Vec<u8>>)>);
+
+    impl FastLocalVisitor for Collect {
+        type Output = Vec<(u64, Vec<Vec<u8>>)>;
+
+        fn visit(&mut self, record: &FastLocalRecord<'_>) -> CsvResult<()> {
+            self.0.push(collect(record));
+            Ok(())
+        }
+
+        fn finish(self) -> CsvResult<Self::Output> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn visitors_receive_accepted_records_in_file_order() {
+        let contents = contents();
+        let file = fixture(&contents);
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+
+        for workers in [1, 3, 8] {
+            let (summary, outputs) = fast_local_visit(
+                file.path(),
+                &["note"],
+                &options,
+                None,
+                with_workers(workers),
+                Collect::default,
+            )
+            .expect("visit records");
+            assert_eq!(summary.rejected_rows, 60);
+            assert_eq!(summary.row_count, 240);
+            let visited: Vec<_> = outputs.into_iter().flatten().collect();
+            assert_eq!(visited, expected(&contents), "{workers} workers");
+        }
+    }
+
+    #[test]
+    fn closures_are_visitors_and_their_errors_stop_the_run() {
+        let file = fixture(b"id,qty\na,1\nb,2\nc,3\n");
+        let total = AtomicU64::new(0);
+        let (summary, outputs) = fast_local_visit(
+            file.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            with_workers(2),
+            || {
+                |record: &FastLocalRecord<'_>| {
+                    let qty = std::str::from_utf8(record.get(1).unwrap_or_default())?;
+                    total.fetch_add(qty.parse::<u64>().unwrap_or_default(), Ordering::Relaxed);
+                    Ok(())
+                }
+            },
+        )
+        .expect("visit records");
+        assert_eq!(summary.row_count, 3);
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(total.load(Ordering::Relaxed), 6);
+
+        let error = fast_local_visit(
+            file.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            with_workers(2),
+            || |_: &FastLocalRecord<'_>| Err(CsvIngestError::MissingHeader("stop".to_string())),
+        )
+        .expect_err("the visitor fails");
+        assert!(matches!(error, CsvIngestError::MissingHeader(name) if name == "stop"));
+    }
+
+    fn read_all(records: &mut FastLocalRecords) -> Vec<(u64, Vec<Vec<u8>>)> {
+        let mut read = Vec::new();
+        while let Some(record) = records.next_record() {
+            read.push(collect(&record));
+        }
+        read
+    }
+
+    #[test]
+    fn records_are_read_in_file_order_while_the_file_is_parsed() {
+        let contents = contents();
+        let file = fixture(&contents);
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+
+        for workers in [1, 4] {
+            let (summary, read) = fast_local_records(
+                file.path(),
+                &[],
+                &options,
+                Some(100),
+                with_workers(workers),
+                read_all,
+            )
+            .expect("read records");
+            assert_eq!(summary.scanned_rows, 100);
+            assert_eq!(read, expected(&contents)[..80], "{workers} workers");
+
+            // Each window is handed over once it is parsed, while the next
+            // one is parsed.
+            let (summary, read) = fast_local_records(
+                file.path(),
+                &[],
+                &options,
+                None,
+                FastLocalConfig {
+                    window_size: std::num::NonZeroUsize::new(256),
+                    ..with_workers(workers)
+                },
+                read_all,
+            )
+            .expect("read records in windows");
+            assert_eq!(summary.row_count, 240);
+            assert_eq!(read, expected(&contents), "{workers} workers");
+        }
+
+        // Stopping early leaves the rest of the file validated.
+        let (summary, first) = fast_local_records(
+            file.path(),
+            &[],
+            &options,
+            None,
+            with_workers(3),
+            |records| records.next_record().map(|record| collect(&record)),
+        )
+        .expect("read one record");
+        assert_eq!(summary.row_count, 240);
+        assert_eq!(first.as_ref(), expected(&contents).first());
+
+        let mut before_error = Vec::new();
+        let error = fast_local_records(
+            file.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            with_workers(3),
+            |records| before_error = read_all(records),
+        )
+        .expect_err("the ragged row stops the parse");
+        assert!(matches!(
+            error,
+            CsvIngestError::RaggedRow { row: Some(2), .. }
+        ));
+        assert!(before_error.len() <= 1);
+    }
+
+    #[test]
+    fn records_are_trimmed_projected_and_split_in_full() {
+        let file = fixture(b"sku, qty ,note\n a ,1, x \nb,\" 2 \"\nc,3,y,extra\n");
+        let options = CsvOptions {
+            flexible: true,
+            trim: CsvTrim::All,
+            ..CsvOptions::default()
+        };
+        let read = |projection: Option<&CsvProjection>| {
+            let (summary, read) = fast_local_records(
+                file.path(),
+                &["sku"],
+                &options,
+                None,
+                FastLocalConfig {
+                    projection,
+                    ..with_workers(2)
+                },
+                read_all,
+            )
+            .expect("read records");
+            let fields = read
+                .into_iter()
+                .map(|(_, fields)| fields)
+                .collect::<Vec<_>>();
+            (summary.headers, fields)
+        };
+
+        let (headers, rows) = read(None);
+        assert_eq!(headers, ["sku", "qty", "note"]);
+        assert_eq!(
+            rows,
+            [
+                vec![&b"a"[..], b"1", b"x"],
+                vec![&b"b"[..], b"2"],
+                vec![&b"c"[..], b"3", b"y", b"extra"],
+            ]
+        );
+
+        let projection = CsvProjection::new(["qty", "sku"]);
+        let (headers, rows) = read(Some(&projection));
+        assert_eq!(headers, ["qty", "sku"]);
+        assert_eq!(
+            rows,
+            [
+                vec![&b"1"[..], b"a"],
+                vec![&b"2"[..], b"b"],
+                vec![&b"3"[..], b"c"],
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_combine_chunk_results_in_file_order() {
+        let contents = contents();
+        let file = fixture(&contents);
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let expected_ids: Vec<u64> = expected(&contents)
+            .iter()
+            .map(|(_, fields)| {
+                std::str::from_utf8(&fields[0])
+                    .expect("ids are UTF-8")
+                    .parse()
+                    .expect("ids are integers")
+            })
+            .collect();
+
+        for workers in [1, 3, 8] {
+            let (summary, (ids, notes)) = fast_local_fold(
+                file.path(),
+                &[],
+                &options,
+                None,
+                with_workers(workers),
+                || (Vec::new(), std::collections::BTreeSet::new()),
+                |(mut ids, mut notes), record| {
+                    let id = std::str::from_utf8(record.get(0).unwrap_or_default())?;
+                    ids.push(id.parse().expect("ids are integers"));
+                    notes.insert(record.get(1).unwrap_or_default().to_vec());
+                    Ok((ids, notes))
+                },
+                |(mut ids, mut notes): (Vec<u64>, _), (more_ids, more_notes)| {
+                    ids.extend(more_ids);
+                    notes.extend(more_notes);
+                    (ids, notes)
+                },
+            )
+            .expect("fold records");
+            assert_eq!(summary.row_count, 240);
+            assert_eq!(ids, expected_ids, "{workers} workers");
+            // Every quoted note is the same, and the others are unique.
+            assert_eq!(notes.len(), 181, "{workers} workers");
+        }
+
+        let error = fast_local_fold(
+            file.path(),
+            &[],
+            &options,
+            None,
+            with_workers(4),
+            || 0u64,
+            |total, record| {
+                let id = std::str::from_utf8(record.get(0).unwrap_or_default())?;
+                let id: u64 = id.parse().expect("ids are integers");
+                if id == 250 {
+                    return Err(CsvIngestError::MissingHeader("stop".to_string()));
+                }
+                Ok(total + id)
+            },
+            |left, right| left + right,
+        )
+        .expect_err("the fold fails");
+        assert!(matches!(error, CsvIngestError::MissingHeader(name) if name == "stop"));
+    }
+}
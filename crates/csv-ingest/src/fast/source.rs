@@ -0,0 +1,524 @@
+use super::pool::run_all;
+use super::{FastLocalAdvice, FastLocalConfig};
+use crate::{CsvIngestError, CsvResult};
+use memmap2::{Mmap, MmapOptions};
+#[cfg(feature = "parallel_decompress")]
+use std::cell::RefCell;
+use std::fs::{self, File, Metadata};
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+#[cfg(feature = "parallel_decompress")]
+use std::sync::mpsc::Receiver;
+use std::time::SystemTime;
+
+/// How a fast-local file is read into memory.
+///
+/// Either way, the file's length, modification time, and (on Unix) inode are
+/// recorded when it is opened and compared once it has been parsed, and a
+/// change fails the parse with `CsvIngestError::SourceModified`. A write that
+/// keeps the length and lands within the file system's timestamp resolution
+/// can go unnoticed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FastLocalAccess {
+    /// Map the file, which avoids the copy. If another process truncates it
+    /// while it is mapped, reading the lost pages raises `SIGBUS` on Unix,
+    /// which ends the process, so map only files nothing else writes.
+    Map,
+    /// Copy the file into memory with positioned reads, one per worker, so a
+    /// file truncated while it is read fails with `SourceModified`. The copy
+    /// of each window is held until the window has been parsed.
+    #[default]
+    Read,
+}
+
+/// Data a fast-local parse reads, one window at a time, and checks once the
+/// parse has finished.
+pub(super) trait Input {
+    /// The most bytes to hold at once, when that is less than the whole input.
+    fn window_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Bytes `start..end` of the input, or up to its end when that comes
+    /// first. Windows never start before an earlier window.
+    fn window(
+        &self,
+        start: usize,
+        end: usize,
+        config: &FastLocalConfig<'_>,
+    ) -> CsvResult<Window<'_>>;
+
+    /// Fail with `SourceModified` if the input no longer matches its source.
+    fn verify(&self) -> CsvResult<()> {
+        Ok(())
+    }
+}
+
+impl Input for [u8] {
+    fn window(&self, start: usize, end: usize, _: &FastLocalConfig<'_>) -> CsvResult<Window<'_>> {
+        Ok(Window::Borrowed(&self[start..end.min(self.len())]))
+    }
+}
+
+impl Input for Vec<u8> {
+    fn window(
+        &self,
+        start: usize,
+        end: usize,
+        config: &FastLocalConfig<'_>,
+    ) -> CsvResult<Window<'_>> {
+        self[..].window(start, end, config)
+    }
+}
+
+impl<T: Input + ?Sized> Input for &T {
+    fn window_size(&self) -> Option<usize> {
+        (**self).window_size()
+    }
+
+    fn window(
+        &self,
+        start: usize,
+        end: usize,
+        config: &FastLocalConfig<'_>,
+    ) -> CsvResult<Window<'_>> {
+        (**self).window(start, end, config)
+    }
+
+    fn verify(&self) -> CsvResult<()> {
+        (**self).verify()
+    }
+}
+
+/// Bytes of an input, borrowed from memory, mapped, or copied.
+pub(super) enum Window<'a> {
+    Borrowed(&'a [u8]),
+    Map(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for Window<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Borrowed(data) => data,
+            Self::Map(map) => map,
+            Self::Read(data) => data,
+        }
+    }
+}
+
+/// A file, with what it looked like when it was opened.
+pub(super) struct FileData<'a> {
+    file: Handle<'a>,
+    /// Checked by name when the file is verified, so a file replaced by a
+    /// rename is also noticed.
+    path: Option<&'a Path>,
+    source_name: Option<&'a str>,
+    stamp: Stamp,
+    len: usize,
+    /// The whole file, unless `FastLocalConfig::window_size` is smaller.
+    contents: Option<Window<'static>>,
+    window_size: Option<usize>,
+}
+
+enum Handle<'a> {
+    Owned(File),
+    Borrowed(&'a File),
+}
+
+impl Deref for Handle<'_> {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        match self {
+            Self::Owned(file) => file,
+            Self::Borrowed(file) => file,
+        }
+    }
+}
+
+/// The identity and version of a file, as far as its metadata tells.
+#[derive(Debug, PartialEq, Eq)]
+struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    #[cfg(unix)]
+    inode: (u64, u64),
+}
+
+impl Stamp {
+    fn new(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        use std::os::unix::fs::MetadataExt;
+
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            #[cfg(unix)]
+            inode: (metadata.dev(), metadata.ino()),
+        }
+    }
+}
+
+impl<'a> FileData<'a> {
+    /// Open `path` and read it, or prepare to read it in windows, as `config`
+    /// asks.
+    pub(super) fn open(
+        path: &'a Path,
+        config: &FastLocalConfig<'_>,
+        source_name: Option<&'a str>,
+    ) -> CsvResult<Self> {
+        let file = File::open(path)?;
+        Self::load(Handle::Owned(file), Some(path), config, source_name)
+    }
+
+    /// Read an open `file` from its start, or prepare to read it in windows,
+    /// as `config` asks.
+    pub(super) fn new(
+        file: &'a File,
+        config: &FastLocalConfig<'_>,
+        source_name: Option<&'a str>,
+    ) -> CsvResult<Self> {
+        Self::load(Handle::Borrowed(file), None, config, source_name)
+    }
+
+    fn load(
+        file: Handle<'a>,
+        path: Option<&'a Path>,
+        config: &FastLocalConfig<'_>,
+        source_name: Option<&'a str>,
+    ) -> CsvResult<Self> {
+        let stamp = Stamp::new(&file.metadata()?);
+        let len = usize::try_from(stamp.len).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                "file is larger than the address space",
+            )
+        })?;
+        // A copy of the whole file would grow with it, so a copied file is
+        // read in windows even when no size is set. A map is not copied.
+        let window_size = match config.access {
+            FastLocalAccess::Map => config.window_size,
+            FastLocalAccess::Read => Some(config.bounded_window_size()),
+        }
+        .map(usize::from)
+        .filter(|&size| size < len);
+        let mut data = Self {
+            file,
+            path,
+            source_name,
+            stamp,
+            len,
+            contents: None,
+            window_size,
+        };
+        if window_size.is_none() {
+            data.contents = Some(data.read(0, len, config)?);
+        }
+        Ok(data)
+    }
+
+    fn read(
+        &self,
+        start: usize,
+        end: usize,
+        config: &FastLocalConfig<'_>,
+    ) -> CsvResult<Window<'static>> {
+        Ok(match config.access {
+            FastLocalAccess::Map => Window::Map(map_file(&self.file, start, end, config.advice)?),
+            FastLocalAccess::Read => {
+                Window::Read(read_file(&self.file, start, end, config, self.source_name)?)
+            }
+        })
+    }
+}
+
+impl Input for FileData<'_> {
+    fn window_size(&self) -> Option<usize> {
+        self.window_size
+    }
+
+    fn window(
+        &self,
+        start: usize,
+        end: usize,
+        config: &FastLocalConfig<'_>,
+    ) -> CsvResult<Window<'_>> {
+        let end = end.min(self.len);
+        match &self.contents {
+            Some(contents) => Ok(Window::Borrowed(&contents[start..end])),
+            None => self.read(start, end, config),
+        }
+    }
+
+    fn verify(&self) -> CsvResult<()> {
+        let metadata = self.path.map_or_else(|| self.file.metadata(), fs::metadata);
+        let unchanged = match metadata {
+            Ok(metadata) => Stamp::new(&metadata) == self.stamp,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => false,
+            Err(error) => return Err(error.into()),
+        };
+        if unchanged {
+            Ok(())
+        } else {
+            Err(modified(self.source_name))
+        }
+    }
+}
+
+/// The output of a compressed file, decoded segment by segment on other
+/// threads and read as the parse reaches it.
+#[cfg(feature = "parallel_decompress")]
+pub(super) struct Decompressed<'a> {
+    compressed: &'a FileData<'a>,
+    segments: Receiver<CsvResult<Vec<u8>>>,
+    window_size: Option<usize>,
+    /// The decoded bytes from offset `start` on.
+    pending: RefCell<Pending>,
+}
+
+#[cfg(feature = "parallel_decompress")]
+#[derive(Default)]
+struct Pending {
+    start: usize,
+    bytes: Vec<u8>,
+    finished: bool,
+}
+
+#[cfg(feature = "parallel_decompress")]
+impl<'a> Decompressed<'a> {
+    /// Read the segments decoded from `compressed` in windows of
+    /// `config.window_size`.
+    pub(super) fn new(
+        compressed: &'a FileData<'a>,
+        segments: Receiver<CsvResult<Vec<u8>>>,
+        config: &FastLocalConfig<'_>,
+    ) -> Self {
+        Self {
+            compressed,
+            segments,
+            window_size: config.window_size.map(usize::from),
+            pending: RefCell::default(),
+        }
+    }
+}
+
+#[cfg(feature = "parallel_decompress")]
+impl Input for Decompressed<'_> {
+    fn window_size(&self) -> Option<usize> {
+        self.window_size
+    }
+
+    fn window(&self, start: usize, end: usize, _: &FastLocalConfig<'_>) -> CsvResult<Window<'_>> {
+        let mut pending = self.pending.borrow_mut();
+        // Later windows start no earlier, so the bytes before this one are
+        // no longer needed.
+        let consumed = start - pending.start;
+        pending.bytes.drain(..consumed);
+        pending.start = start;
+        while !pending.finished && pending.bytes.len() < end - start {
+            match self.segments.recv() {
+                Ok(segment) => pending.bytes.extend_from_slice(&segment?),
+                Err(_) => pending.finished = true,
+            }
+        }
+        let len = pending.bytes.len().min(end - start);
+        Ok(Window::Read(pending.bytes[..len].to_vec()))
+    }
+
+    fn verify(&self) -> CsvResult<()> {
+        self.compressed.verify()
+    }
+}
+
+/// Map bytes `start..end` of `file`.
+fn map_file(file: &File, start: usize, end: usize, advice: FastLocalAdvice) -> CsvResult<Mmap> {
+    // SAFETY: the map is read-only and no longer than the file when it was
+    // opened. A file truncated while it is mapped raises SIGBUS when the lost
+    // pages are read, which `FastLocalAccess::Map` documents.
+    let map = unsafe {
+        MmapOptions::new()
+            .offset(start as u64)
+            .len(end - start)
+            .map(file)?
+    };
+    #[cfg(unix)]
+    {
+        let advice = match advice {
+            FastLocalAdvice::Normal => None,
+            FastLocalAdvice::Sequential => Some(memmap2::Advice::Sequential),
+            FastLocalAdvice::WillNeed => Some(memmap2::Advice::WillNeed),
+        };
+        if let Some(advice) = advice {
+            // A hint the kernel rejects only costs performance.
+            let _ = map.advise(advice);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = advice;
+    Ok(map)
+}
+
+/// Copy bytes `start..end` of `file` with one positioned read per worker.
+fn read_file(
+    file: &File,
+    start: usize,
+    end: usize,
+    config: &FastLocalConfig<'_>,
+    source_name: Option<&str>,
+) -> CsvResult<Vec<u8>> {
+    let mut data = vec![0; end - start];
+    let part_size = data.len().div_ceil(config.worker_count().max(1)).max(1);
+    let results = run_all(
+        config.pool(),
+        data.chunks_mut(part_size).enumerate().map(|(index, part)| {
+            move || read_exact_at(file, part, (start + index * part_size) as u64)
+        }),
+    );
+    for result in results {
+        match result {
+            Some(Ok(())) => {}
+            Some(Err(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(modified(source_name));
+            }
+            Some(Err(error)) => return Err(error.into()),
+            None => return Err(CsvIngestError::FastLocalWorkerPanicked),
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read) => {
+                buf = &mut buf[read..];
+                offset += read as u64;
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(_: &File, _: &mut [u8], _: u64) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "positioned reads are not supported on this platform",
+    ))
+}
+
+fn modified(source_name: Option<&str>) -> CsvIngestError {
+    CsvIngestError::SourceModified {
+        source_name: source_name.map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::num::NonZeroUsize;
+    use tempfile::NamedTempFile;
+
+    fn fixture(contents: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().expect("create fixture");
+        file.write_all(contents).expect("write fixture");
+        file
+    }
+
+    #[test]
+    fn copied_files_are_read_in_bounded_windows_by_default() {
+        let window = 8 << 20;
+        let file = fixture(&vec![b'a'; window + 1]);
+        for (access, expected) in [
+            (FastLocalAccess::Read, Some(window)),
+            (FastLocalAccess::Map, None),
+        ] {
+            let config = FastLocalConfig {
+                workers: NonZeroUsize::new(1),
+                access,
+                ..FastLocalConfig::default()
+            };
+            let data = FileData::open(file.path(), &config, None).expect("load file");
+            assert_eq!(data.window_size(), expected, "{access:?}");
+        }
+    }
+
+    #[test]
+    fn reads_and_windows_match_maps_and_short_reads_are_modifications() {
+        let contents: Vec<u8> = (0..1000u32).map(|index| index as u8).collect();
+        let file = fixture(&contents);
+        for access in [FastLocalAccess::Map, FastLocalAccess::Read] {
+            for window_size in [None, NonZeroUsize::new(300)] {
+                let config = FastLocalConfig {
+                    workers: NonZeroUsize::new(3),
+                    access,
+                    window_size,
+                    ..FastLocalConfig::default()
+                };
+                let data = FileData::open(file.path(), &config, None).expect("load file");
+                assert_eq!(data.window_size(), window_size.map(usize::from));
+                let whole = data.window(0, usize::MAX, &config).expect("read file");
+                assert_eq!(&*whole, contents);
+                // Map offsets need not be page aligned.
+                let window = data.window(123, 777, &config).expect("read window");
+                assert_eq!(&*window, &contents[123..777]);
+                data.verify().expect("unchanged file");
+            }
+        }
+
+        let config = FastLocalConfig {
+            workers: NonZeroUsize::new(3),
+            ..FastLocalConfig::default()
+        };
+        let error = read_file(file.as_file(), 900, 1200, &config, Some("short.csv"))
+            .expect_err("the file ended early");
+        assert!(matches!(
+            error,
+            CsvIngestError::SourceModified { source_name: Some(ref name) } if name == "short.csv"
+        ));
+    }
+
+    #[test]
+    fn appends_truncations_and_replacements_are_modifications() {
+        let file = fixture(b"a,b\n1,2\n");
+        let config = FastLocalConfig::default();
+        let data = FileData::open(file.path(), &config, None).expect("load file");
+        file.as_file().set_len(4).expect("truncate fixture");
+        assert!(matches!(
+            data.verify(),
+            Err(CsvIngestError::SourceModified { source_name: None })
+        ));
+
+        let data = FileData::new(file.as_file(), &config, Some("open")).expect("load file");
+        writeln!(file.as_file(), "3,4").expect("append row");
+        assert!(matches!(
+            data.verify(),
+            Err(CsvIngestError::SourceModified { .. })
+        ));
+
+        let contents = fs::read(file.path()).expect("read fixture");
+        let data = FileData::open(file.path(), &config, None).expect("load file");
+        let replacement = fixture(&contents);
+        fs::rename(replacement.path(), file.path()).expect("replace fixture");
+        #[cfg(unix)]
+        assert!(data.verify().is_err());
+        fs::remove_file(file.path()).expect("remove fixture");
+        assert!(data.verify().is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{CsvOptions, CsvTerminator};
+use memchr::{memchr, memchr2, memchr_iter};
+
+/// Where a record scan is, following the `csv_core` reader's states.
+#[derive(Clone, Copy)]
+enum State {
+    StartField,
+    InField,
+    InQuotedField,
+    /// After the escape byte inside a quoted field.
+    InEscapedQuote,
+    /// After a quote inside a quoted field, which either closes the field or
+    /// starts a doubled quote.
+    InDoubleEscapedQuote,
+}
+
+/// Return the end of the record starting at `start` and where the next one
+/// starts, or `None` if no terminator outside a quoted field precedes `end`.
+pub(super) fn next_record_terminator(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    options: &CsvOptions,
+) -> Option<(usize, usize)> {
+    let record_end = find_terminator(data, start, end, options)?;
+    let next_record = if options.terminator == CsvTerminator::CrLf
+        && data[record_end] == b'\r'
+        && record_end + 1 < end
+        && data[record_end + 1] == b'\n'
+    {
+        record_end + 2
+    } else {
+        record_end + 1
+    };
+    Some((record_end, next_record))
+}
+
+fn find_terminator(data: &[u8], start: usize, end: usize, options: &CsvOptions) -> Option<usize> {
+    let candidate = match options.terminator {
+        CsvTerminator::CrLf => memchr2(b'\r', b'\n', &data[start..end]),
+        CsvTerminator::Any(byte) => memchr(byte, &data[start..end]),
+    }
+    .map(|offset| start + offset);
+    // A record without quotes ends at its first terminator byte.
+    if !options.quoting || memchr(options.quote, &data[start..candidate.unwrap_or(end)]).is_none() {
+        return candidate;
+    }
+
+    let mut state = State::StartField;
+    let mut cursor = start;
+    while cursor < end {
+        let byte = data[cursor];
+        state = match state {
+            State::StartField if byte == options.quote => State::InQuotedField,
+            State::InDoubleEscapedQuote if options.double_quote && byte == options.quote => {
+                State::InQuotedField
+            }
+            State::StartField | State::InField | State::InDoubleEscapedQuote => {
+                if byte == options.delimiter {
+                    State::StartField
+                } else if options.is_terminator(byte) {
+                    return Some(cursor);
+                } else {
+                    State::InField
+                }
+            }
+            State::InQuotedField => {
+                let escape = options.escape.unwrap_or(options.quote);
+                cursor += memchr2(options.quote, escape, &data[cursor..end])?;
+                if data[cursor] == options.quote {
+                    State::InDoubleEscapedQuote
+                } else {
+                    State::InEscapedQuote
+                }
+            }
+            State::InEscapedQuote => State::InQuotedField,
+        };
+        cursor += 1;
+    }
+    None
+}
+
+/// Reusable storage for splitting records into fields.
+#[derive(Default)]
+pub(super) struct FieldBuf {
+    /// The unquoted field values of the current record, if it has quotes.
+    decoded: Vec<u8>,
+    fields: Vec<(usize, usize)>,
+}
+
+/// The fields of one record, borrowed from the file unless the record had to
+/// be unquoted.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Row<'a> {
+    bytes: &'a [u8],
+    fields: &'a [(usize, usize)],
+}
+
+impl FieldBuf {
+    /// Split the record `row`, without its terminator, into at most `limit`
+    /// fields, removing quotes and escapes as the streaming parser does.
+    pub(super) fn split<'a>(
+        &'a mut self,
+        row: &'a [u8],
+        options: &CsvOptions,
+        limit: Option<usize>,
+    ) -> Row<'a> {
+        self.fields.clear();
+        let limit = limit.unwrap_or(usize::MAX);
+        if !options.quoting || memchr(options.quote, row).is_none() {
+            let mut field_start = 0usize;
+            let field_ends = memchr_iter(options.delimiter, row).chain(std::iter::once(row.len()));
+            for field_end in field_ends.take(limit) {
+                self.fields.push((field_start, field_end));
+                field_start = field_end + 1;
+            }
+            return Row {
+                bytes: row,
+                fields: &self.fields,
+            };
+        }
+
+        self.decoded.clear();
+        let mut state = State::StartField;
+        let mut field_start = 0usize;
+        for &byte in row {
+            if self.fields.len() == limit {
+                break;
+            }
+            state = match state {
+                State::StartField if byte == options.quote => State::InQuotedField,
+                State::InDoubleEscapedQuote if options.double_quote && byte == options.quote => {
+                    self.decoded.push(byte);
+                    State::InQuotedField
+                }
+                State::StartField | State::InField | State::InDoubleEscapedQuote => {
+                    if byte == options.delimiter {
+                        self.fields.push((field_start, self.decoded.len()));
+                        field_start = self.decoded.len();
+                        State::StartField
+                    } else {
+                        self.decoded.push(byte);
+                        State::InField
+                    }
+                }
+                State::InQuotedField if byte == options.quote => State::InDoubleEscapedQuote,
+                State::InQuotedField if options.escape == Some(byte) => State::InEscapedQuote,
+                State::InQuotedField | State::InEscapedQuote => {
+                    self.decoded.push(byte);
+                    State::InQuotedField
+                }
+            };
+        }
+        if self.fields.len() < limit {
+            self.fields.push((field_start, self.decoded.len()));
+        }
+        Row {
+            bytes: &self.decoded,
+            fields: &self.fields,
+        }
+    }
+}
+
+impl<'a> Row<'a> {
+    /// A row of the fields at `fields` within `bytes`, as [`Row::ranges`]
+    /// returns them.
+    pub(super) fn new(bytes: &'a [u8], fields: &'a [(usize, usize)]) -> Self {
+        Self { bytes, fields }
+    }
+
+    /// The number of fields split, which is capped by the split's limit.
+    pub(super) fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub(super) fn get(&self, index: usize) -> Option<&'a [u8]> {
+        self.fields
+            .get(index)
+            .map(|&(start, end)| &self.bytes[start..end])
+    }
+
+    pub(super) fn iter(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.fields
+            .iter()
+            .map(|&(start, end)| &self.bytes[start..end])
+    }
+
+    /// The range of `field`, which must be a subslice of one of this row's
+    /// fields, within [`Row::bytes`].
+    pub(super) fn range_of(&self, field: &[u8]) -> (usize, usize) {
+        let start = field.as_ptr() as usize - self.bytes.as_ptr() as usize;
+        (start, start + field.len())
+    }
+
+    pub(super) fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The range of each field within [`Row::bytes`].
+    pub(super) fn ranges(&self) -> &'a [(usize, usize)] {
+        self.fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(row: &[u8], options: &CsvOptions) -> Vec<Vec<u8>> {
+        let mut buf = FieldBuf::default();
+        let row = buf.split(row, options, None);
+        row.iter().map(<[u8]>::to_vec).collect()
+    }
+
+    #[test]
+    fn quoted_fields_are_unquoted_like_csv_core() {
+        let options = CsvOptions::default();
+        assert_eq!(
+            split(b"\"a,b\",\"say \"\"hi\"\"\",plain", &options),
+            [&b"a,b"[..], b"say \"hi\"", b"plain"]
+        );
+        // Quotes inside an unquoted field, or after a closing quote, are data.
+        assert_eq!(split(b"a\"b,\"c\"d\"", &options), [&b"a\"b"[..], b"cd\""]);
+        assert_eq!(split(b"\"\",,\"x", &options), [&b""[..], b"", b"x"]);
+
+        let escaped = CsvOptions {
+            escape: Some(b'\\'),
+            double_quote: false,
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            split(b"\"a\\\"b\",\"c\"\"d\"", &escaped),
+            [&b"a\"b"[..], b"c\"d\""]
+        );
+
+        let limited = FieldBuf::default()
+            .split(b"\"a\",b,c", &options, Some(2))
+            .len();
+        assert_eq!(limited, 2);
+    }
+
+    #[test]
+    fn terminators_inside_quotes_do_not_end_records() {
+        let options = CsvOptions::default();
+        let data = b"a,\"line\r\none\"\r\nb,\"x\"\"\ny\"\nc,\"open";
+        assert_eq!(
+            next_record_terminator(data, 0, data.len(), &options),
+            Some((13, 15))
+        );
+        assert_eq!(
+            next_record_terminator(data, 15, data.len(), &options),
+            Some((24, 25))
+        );
+        assert_eq!(next_record_terminator(data, 25, data.len(), &options), None);
+
+        let escaped = CsvOptions {
+            escape: Some(b'\\'),
+            ..CsvOptions::default()
+        };
+        let data = b"\"a\\\"\nb\"\nc";
+        assert_eq!(
+            next_record_terminator(data, 0, data.len(), &escaped),
+            Some((7, 8))
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use crate::projection::CompiledProjection;
+use crate::{CsvProjection, CsvResult, CsvSelector};
+use std::fmt;
+use std::sync::Arc;
+
+type FieldPredicate = dyn Fn(&[u8]) -> bool + Send + Sync;
+
+/// A predicate over the raw bytes of one column that decides whether a row is kept.
+///
+/// Filters are checked after a row's shape is validated and before its schema
+/// rules, so rows that are filtered out are never checked against a schema,
+/// hashed, or returned. A row is kept only if every filter accepts it. The
+/// filtered column is required and the predicate sees the field after trimming.
+#[derive(Clone)]
+pub struct CsvFilter {
+    column: CsvSelector,
+    predicate: Arc<FieldPredicate>,
+}
+
+impl CsvFilter {
+    /// Keep rows whose `column` satisfies `predicate`.
+    pub fn new(
+        column: impl Into<CsvSelector>,
+        predicate: impl Fn(&[u8]) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            column: column.into(),
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Keep rows whose `column` equals `value` byte for byte.
+    pub fn equals(column: impl Into<CsvSelector>, value: impl Into<Vec<u8>>) -> Self {
+        let value = value.into();
+        Self::new(column, move |field| field == value)
+    }
+
+    /// Keep rows whose `column` equals one of `values` byte for byte.
+    pub fn one_of<V: Into<Vec<u8>>>(
+        column: impl Into<CsvSelector>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        let values: Vec<Vec<u8>> = values.into_iter().map(Into::into).collect();
+        Self::new(column, move |field| {
+            values.iter().any(|value| value.as_slice() == field)
+        })
+    }
+
+    /// Return the column this filter reads.
+    pub fn column(&self) -> &CsvSelector {
+        &self.column
+    }
+
+    /// Return whether `field` passes this filter.
+    pub fn accepts(&self, field: &[u8]) -> bool {
+        (self.predicate)(field)
+    }
+}
+
+impl fmt::Debug for CsvFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvFilter")
+            .field("column", &self.column)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Filters resolved to field indices, in the order they were added.
+#[derive(Default)]
+pub(crate) struct CompiledFilters {
+    filters: Vec<CompiledFilter>,
+}
+
+pub(crate) struct CompiledFilter {
+    pub(crate) index: usize,
+    pub(crate) name: String,
+    predicate: Arc<FieldPredicate>,
+}
+
+impl CompiledFilters {
+    /// Resolve `filters` like a [`CsvProjection`] of their columns.
+    pub(crate) fn new<H: AsRef<[u8]>>(
+        filters: &[CsvFilter],
+        headers: Option<&[H]>,
+    ) -> CsvResult<Self> {
+        let columns = CompiledProjection::new(
+            &CsvProjection::new(filters.iter().map(|filter| filter.column.clone())),
+            headers,
+        )?;
+        Ok(Self {
+            filters: filters
+                .iter()
+                .zip(columns.columns())
+                .map(|(filter, (index, name))| CompiledFilter {
+                    index: *index,
+                    name: name.clone(),
+                    predicate: Arc::clone(&filter.predicate),
+                })
+                .collect(),
+        })
+    }
+
+    pub(crate) fn filters(&self) -> &[CompiledFilter] {
+        &self.filters
+    }
+
+    pub(crate) fn extend(&mut self, other: Self) {
+        self.filters.extend(other.filters);
+    }
+
+    /// Return whether every filter accepts its field, treating a missing field as rejected.
+    pub(crate) fn accepts<'f>(&self, field: impl Fn(usize) -> Option<&'f [u8]>) -> bool {
+        self.filters
+            .iter()
+            .all(|filter| field(filter.index).is_some_and(|value| filter.accepts(value)))
+    }
+}
+
+impl CompiledFilter {
+    pub(crate) fn accepts(&self, field: &[u8]) -> bool {
+        (self.predicate)(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvIngestError;
+
+    #[test]
+    fn filters_match_raw_bytes() {
+        let country = CsvFilter::equals("country", "US");
+        assert!(country.accepts(b"US"));
+        assert!(!country.accepts(b"us"));
+        assert_eq!(
+            country.column(),
+            &CsvSelector::Header("country".to_string())
+        );
+
+        let status = CsvFilter::one_of(2, ["open", "held"]);
+        assert!(status.accepts(b"held"));
+        assert!(!status.accepts(b""));
+
+        let numeric = CsvFilter::new("qty", |field| field.iter().all(u8::is_ascii_digit));
+        assert!(numeric.accepts(b"12"));
+        assert!(!numeric.accepts(b"1x"));
+        assert_eq!(
+            format!("{numeric:?}"),
+            "CsvFilter { column: Header(\"qty\"), .. }"
+        );
+    }
+
+    #[test]
+    fn compiled_filters_require_every_filter_and_field() {
+        let headers = ["country", "qty"];
+        let filters = CompiledFilters::new(
+            &[
+                CsvFilter::equals("country", "US"),
+                CsvFilter::new(1, |field| !field.is_empty()),
+            ],
+            Some(&headers[..]),
+        )
+        .expect("resolve filters");
+        let names: Vec<_> = filters
+            .filters()
+            .iter()
+            .map(|filter| (filter.index, filter.name.as_str()))
+            .collect();
+        assert_eq!(names, [(0, "country"), (1, "qty")]);
+
+        let row = |fields: &'static [&'static [u8]]| move |index: usize| fields.get(index).copied();
+        assert!(filters.accepts(row(&[b"US", b"1"])));
+        assert!(!filters.accepts(row(&[b"CA", b"1"])));
+        assert!(!filters.accepts(row(&[b"US", b""])));
+        assert!(!filters.accepts(row(&[b"US"])));
+
+        let error = CompiledFilters::new(&[CsvFilter::equals("region", "EU")], Some(&headers[..]))
+            .err()
+            .expect("unknown filter column must fail");
+        assert!(matches!(error, CsvIngestError::MissingHeader(header) if header == "region"));
+    }
+}
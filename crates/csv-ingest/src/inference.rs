@@ -0,0 +1,357 @@
+use crate::schema::{is_boolean, is_date, is_float, is_integer, is_timestamp, width};
+use crate::{ByteRecord, CsvColumnSchema, CsvRule, CsvSchema};
+
+/// How [`crate::CsvParser::infer_schema`] chooses the records it inspects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvSampling {
+    /// Inspect the first `n` records.
+    First(u64),
+    /// Read every record and inspect a uniform random sample of `size` records.
+    ///
+    /// The same `seed` over the same input selects the same sample.
+    Reservoir { size: usize, seed: u64 },
+}
+
+impl Default for CsvSampling {
+    fn default() -> Self {
+        Self::First(1_000)
+    }
+}
+
+/// The value type inferred for a column, from narrowest to widest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CsvColumnType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    Timestamp,
+    /// Any other text, or a column whose sampled values were all empty.
+    String,
+}
+
+impl CsvColumnType {
+    fn of(value: &[u8]) -> Self {
+        if is_integer(value) {
+            Self::Integer
+        } else if is_float(value) {
+            Self::Float
+        } else if is_boolean(value) {
+            Self::Boolean
+        } else if is_date(value) {
+            Self::Date
+        } else if is_timestamp(value) {
+            Self::Timestamp
+        } else {
+            Self::String
+        }
+    }
+
+    /// Return the narrowest type that admits both `self` and `other`.
+    fn widen(self, other: Self) -> Self {
+        match (self, other) {
+            (left, right) if left == right => left,
+            (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => Self::Float,
+            _ => Self::String,
+        }
+    }
+
+    fn rule(self) -> Option<CsvRule> {
+        match self {
+            Self::Integer => Some(CsvRule::Integer {
+                min: None,
+                max: None,
+            }),
+            Self::Float => Some(CsvRule::Float),
+            Self::Boolean => Some(CsvRule::Boolean),
+            Self::Date => Some(CsvRule::Date),
+            Self::Timestamp => Some(CsvRule::Timestamp),
+            Self::String => None,
+        }
+    }
+}
+
+/// What the sampled values of one column have in common.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvInferredColumn {
+    pub name: String,
+    pub column_type: CsvColumnType,
+    /// Whether any sampled value was empty or missing from a short row.
+    pub nullable: bool,
+    /// The widest sampled value, in characters or, for non-UTF-8 values, bytes.
+    pub max_width: usize,
+}
+
+/// The result of [`crate::CsvParser::infer_schema`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvInferredSchema {
+    /// One entry per header, in header order.
+    pub columns: Vec<CsvInferredColumn>,
+    /// The number of records inspected.
+    pub rows_sampled: u64,
+    /// The number of records read, including those not sampled.
+    pub rows_read: u64,
+}
+
+impl CsvInferredSchema {
+    /// Convert the inference into an enforceable [`CsvSchema`].
+    ///
+    /// Each column gets [`CsvRule::NotEmpty`] unless it is nullable, a rule for
+    /// its type unless it is a string, and [`CsvRule::MaxWidth`] at the sampled
+    /// maximum. Relax the result before enforcing it on data the sample did not
+    /// cover.
+    pub fn to_schema(&self) -> CsvSchema {
+        CsvSchema {
+            columns: self
+                .columns
+                .iter()
+                .map(|column| {
+                    let not_empty = (!column.nullable).then_some(CsvRule::NotEmpty);
+                    let max_width = CsvRule::MaxWidth {
+                        max: column.max_width,
+                    };
+                    CsvColumnSchema::new(
+                        column.name.clone(),
+                        not_empty
+                            .into_iter()
+                            .chain(column.column_type.rule())
+                            .chain([max_width]),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Accumulates per-column observations over sampled records.
+pub(crate) struct SchemaInference {
+    columns: Vec<ColumnState>,
+    rows_sampled: u64,
+}
+
+struct ColumnState {
+    name: String,
+    column_type: Option<CsvColumnType>,
+    nullable: bool,
+    max_width: usize,
+}
+
+impl SchemaInference {
+    pub(crate) fn new<'a>(headers: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        Self {
+            columns: headers
+                .into_iter()
+                .map(|header| ColumnState {
+                    name: String::from_utf8_lossy(header).into_owned(),
+                    column_type: None,
+                    nullable: false,
+                    max_width: 0,
+                })
+                .collect(),
+            rows_sampled: 0,
+        }
+    }
+
+    pub(crate) fn observe(&mut self, record: &ByteRecord) {
+        self.rows_sampled += 1;
+        for (index, column) in self.columns.iter_mut().enumerate() {
+            let value = record.get(index).unwrap_or_default();
+            if value.is_empty() {
+                column.nullable = true;
+                continue;
+            }
+            let value_type = CsvColumnType::of(value);
+            column.column_type = Some(
+                column
+                    .column_type
+                    .map_or(value_type, |column_type| column_type.widen(value_type)),
+            );
+            column.max_width = column.max_width.max(width(value));
+        }
+    }
+
+    pub(crate) fn finish(self, rows_read: u64) -> CsvInferredSchema {
+        CsvInferredSchema {
+            columns: self
+                .columns
+                .into_iter()
+                .map(|column| CsvInferredColumn {
+                    name: column.name,
+                    column_type: column.column_type.unwrap_or(CsvColumnType::String),
+                    nullable: column.nullable,
+                    max_width: column.max_width,
+                })
+                .collect(),
+            rows_sampled: self.rows_sampled,
+            rows_read,
+        }
+    }
+}
+
+/// Algorithm R over records, with a SplitMix64 generator so samples are
+/// reproducible without a random-number dependency.
+pub(crate) struct Reservoir {
+    sample: Vec<ByteRecord>,
+    size: usize,
+    seen: u64,
+    state: u64,
+}
+
+impl Reservoir {
+    pub(crate) fn new(size: usize, seed: u64) -> Self {
+        Self {
+            sample: Vec::with_capacity(size.min(1 << 16)),
+            size,
+            seen: 0,
+            state: seed,
+        }
+    }
+
+    pub(crate) fn offer(&mut self, record: &ByteRecord) {
+        self.seen += 1;
+        if self.sample.len() < self.size {
+            self.sample.push(record.clone());
+            return;
+        }
+        let slot = self.next_random() % self.seen;
+        if let Some(replaced) = usize::try_from(slot)
+            .ok()
+            .and_then(|slot| self.sample.get_mut(slot))
+        {
+            replaced.clone_from(record);
+        }
+    }
+
+    pub(crate) fn into_sample(self) -> Vec<ByteRecord> {
+        self.sample
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infer(rows: &[&[&str]]) -> CsvInferredSchema {
+        let mut inference = SchemaInference::new([&b"a"[..], b"b", b"c"]);
+        for row in rows {
+            inference.observe(&ByteRecord::from(row.to_vec()));
+        }
+        inference.finish(rows.len() as u64)
+    }
+
+    #[test]
+    fn value_types_are_recognized() {
+        let cases = [
+            ("-12", CsvColumnType::Integer),
+            ("1.5e-3", CsvColumnType::Float),
+            ("TRUE", CsvColumnType::Boolean),
+            ("2024-02-29", CsvColumnType::Date),
+            ("2024-02-29T23:59:60.5+05:30", CsvColumnType::Timestamp),
+            ("2023-02-29", CsvColumnType::String),
+            ("inf", CsvColumnType::String),
+            ("ABC-1", CsvColumnType::String),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(CsvColumnType::of(value.as_bytes()), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn columns_widen_and_track_nulls_and_width() {
+        let inferred = infer(&[&["1", "2024-01-01", ""], &["2.5", "x", "é"], &["3"]]);
+
+        assert_eq!(
+            inferred.columns,
+            [
+                CsvInferredColumn {
+                    name: "a".to_string(),
+                    column_type: CsvColumnType::Float,
+                    nullable: false,
+                    max_width: 3,
+                },
+                CsvInferredColumn {
+                    name: "b".to_string(),
+                    column_type: CsvColumnType::String,
+                    nullable: true,
+                    max_width: 10,
+                },
+                CsvInferredColumn {
+                    name: "c".to_string(),
+                    column_type: CsvColumnType::String,
+                    nullable: true,
+                    max_width: 1,
+                },
+            ]
+        );
+        assert_eq!(inferred.rows_sampled, 3);
+    }
+
+    #[test]
+    fn inferred_schemas_become_enforceable_rules() {
+        let inferred = infer(&[&["1", "true", ""]]);
+
+        assert_eq!(
+            inferred.to_schema().columns,
+            [
+                CsvColumnSchema::new(
+                    "a",
+                    [
+                        CsvRule::NotEmpty,
+                        CsvRule::Integer {
+                            min: None,
+                            max: None,
+                        },
+                        CsvRule::MaxWidth { max: 1 },
+                    ],
+                ),
+                CsvColumnSchema::new(
+                    "b",
+                    [
+                        CsvRule::NotEmpty,
+                        CsvRule::Boolean,
+                        CsvRule::MaxWidth { max: 4 },
+                    ],
+                ),
+                CsvColumnSchema::new("c", [CsvRule::MaxWidth { max: 0 }]),
+            ]
+        );
+    }
+
+    #[test]
+    fn reservoirs_keep_a_reproducible_bounded_sample() {
+        let sample = |seed| {
+            let mut reservoir = Reservoir::new(3, seed);
+            for index in 0..100 {
+                reservoir.offer(&ByteRecord::from(vec![index.to_string()]));
+            }
+            reservoir
+                .into_sample()
+                .iter()
+                .map(|record| record[0].to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sample(7).len(), 3);
+        assert_eq!(sample(7), sample(7));
+        assert_ne!(sample(7), sample(8));
+
+        let mut small = Reservoir::new(5, 0);
+        small.offer(&ByteRecord::from(vec!["only"]));
+        assert_eq!(small.into_sample().len(), 1);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::{CsvIngestError, CsvResult, DecodePolicy};
-use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+use crate::members::MemberReader;
+use crate::{CsvIngestError, CsvResult, DecodePolicy, TrailingDataPolicy, TrailingDataReport};
 use std::path::Path;
 use tokio::fs::File;
 use tokio::io::{AsyncRead, BufReader};
@@ -26,6 +26,12 @@ pub struct CsvMeta {
     pub charset: &'static encoding_rs::Encoding,
     /// How malformed encoded input is handled while transcoding (defaults to strict).
     pub decode_policy: DecodePolicy,
+    /// What decompression does with data after the last gzip member or zstd
+    /// frame (defaults to an error).
+    pub trailing_data_policy: TrailingDataPolicy,
+    /// Where a reader records the trailing data it stopped at under
+    /// [`TrailingDataPolicy::Warn`].
+    pub trailing_data: TrailingDataReport,
 }
 
 impl Default for CsvMeta {
@@ -36,18 +42,20 @@ impl Default for CsvMeta {
             name_hint: String::new(),
             charset: encoding_rs::UTF_8,
             decode_policy: DecodePolicy::Strict,
+            trailing_data_policy: TrailingDataPolicy::Error,
+            trailing_data: TrailingDataReport::default(),
         }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Compression {
+pub(crate) enum Compression {
     Gzip,
     Zstd,
 }
 
 impl Compression {
-    fn name(self) -> &'static str {
+    pub(crate) fn name(self) -> &'static str {
         match self {
             Self::Gzip => "gzip",
             Self::Zstd => "zstd",
@@ -119,7 +127,7 @@ fn reject_conflict(
     Ok(())
 }
 
-fn detect_compression(meta: &CsvMeta) -> CsvResult<Option<Compression>> {
+pub(crate) fn detect_compression(meta: &CsvMeta) -> CsvResult<Option<Compression>> {
     let content_encoding = compression_from_content_encoding(&meta.content_encoding)?;
     let content_type = compression_from_content_type(&meta.content_type);
     let name_hint = compression_from_name_hint(&meta.name_hint);
@@ -151,6 +159,11 @@ fn detect_compression(meta: &CsvMeta) -> CsvResult<Option<Compression>> {
 /// Compression signals are evaluated in this order: `Content-Encoding`,
 /// compression-specific `Content-Type`, then filename extension. Gzip/zstd
 /// disagreements and unsupported or stacked content encodings return an error.
+///
+/// Every gzip member or zstd frame is decoded in turn, so concatenated files
+/// read as the concatenation of their contents. Input that ends inside a
+/// member or frame fails with [`crate::CompressedStreamError::Truncated`], and
+/// other data after the last one is handled by `meta.trailing_data_policy`.
 pub fn build_csv_reader<'a, R>(raw: R, meta: CsvMeta) -> CsvResult<(BoxedCsvReader<'a>, CsvMeta)>
 where
     R: AsyncRead + Unpin + Send + 'a,
@@ -159,11 +172,14 @@ where
     let compression = detect_compression(&meta)?;
 
     // Use a larger buffer for fewer syscalls (1 MiB)
-    let buf = BufReader::with_capacity(1 << 20, raw);
     let decompressed: BoxedCsvReader<'a> = match compression {
-        Some(Compression::Gzip) => Box::new(GzipDecoder::new(buf)),
-        Some(Compression::Zstd) => Box::new(ZstdDecoder::new(buf)),
-        None => Box::new(buf),
+        Some(compression) => Box::new(MemberReader::new(
+            raw,
+            compression,
+            meta.trailing_data_policy,
+            meta.trailing_data.clone(),
+        )),
+        None => Box::new(BufReader::with_capacity(1 << 20, raw)),
     };
 
     // 2) transcoding to UTF-8 only when charset != UTF-8 to avoid extra copies
@@ -0,0 +1,390 @@
+use crate::{ByteRecord, CsvHeaderMode, CsvIngestError, CsvIngestSummary, CsvParser, CsvResult};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufWriter};
+
+/// How [`CsvParser::write_json_lines`] writes field values that are not valid UTF-8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CsvJsonInvalidUtf8 {
+    /// Return [`CsvIngestError::InvalidValue`].
+    #[default]
+    Error,
+    /// Replace invalid sequences with `U+FFFD`.
+    Lossy,
+    /// Write the field's raw bytes as a standard, padded base64 string.
+    Base64,
+}
+
+/// How records are written as JSON Lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvJsonOptions {
+    /// Write integers, decimals, and `true` or `false` in any case as JSON
+    /// numbers and booleans instead of strings. Only fields that are already
+    /// JSON numbers are coerced, so `007`, `+5`, and `.5` stay strings.
+    pub coerce_types: bool,
+    /// Field values, such as `NULL` or `\N`, that are written as `null`.
+    pub null_tokens: Vec<String>,
+    /// Leave empty fields out of objects. Arrays keep them, so that positions
+    /// are preserved.
+    pub drop_empty: bool,
+    pub invalid_utf8: CsvJsonInvalidUtf8,
+}
+
+impl<R> CsvParser<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    /// Write the remaining records to `writer` as JSON Lines and summarize the parse.
+    ///
+    /// Each record becomes one line: an object keyed by header, or an array
+    /// when headers are absent. Fields past the last header and headers past
+    /// the end of a short row are left out of objects. Header names follow
+    /// `options.invalid_utf8`, except that base64 headers are written lossily.
+    /// `writer` is flushed but not shut down.
+    pub async fn write_json_lines<W>(
+        mut self,
+        writer: W,
+        options: &CsvJsonOptions,
+    ) -> CsvResult<CsvIngestSummary>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let keys = if self.options().headers == CsvHeaderMode::Present {
+            let keys = self
+                .headers()
+                .iter()
+                .map(|header| {
+                    let header = match options.invalid_utf8 {
+                        CsvJsonInvalidUtf8::Error => std::str::from_utf8(header)?.into(),
+                        _ => String::from_utf8_lossy(header),
+                    };
+                    let mut key = Vec::with_capacity(header.len() + 3);
+                    write_string(&mut key, &header);
+                    key.push(b':');
+                    Ok(key)
+                })
+                .collect::<CsvResult<Vec<_>>>()?;
+            Some(keys)
+        } else {
+            None
+        };
+
+        let mut writer = BufWriter::new(writer);
+        let mut record = ByteRecord::new();
+        let mut line = Vec::new();
+        while self.read_record(&mut record).await? {
+            line.clear();
+            encode_record(&mut line, &record, keys.as_deref(), options).map_err(|index| {
+                let column = self.headers().get(index).map_or_else(
+                    || format!("column {index}"),
+                    |header| String::from_utf8_lossy(header).into_owned(),
+                );
+                CsvIngestError::InvalidValue {
+                    row: self.rows_scanned(),
+                    column,
+                    expected: "UTF-8 string".to_string(),
+                    position: record.position().cloned(),
+                    source_name: self.source_name().map(str::to_string),
+                }
+            })?;
+            line.push(b'\n');
+            writer.write_all(&line).await?;
+        }
+        writer.flush().await?;
+        self.into_summary().await
+    }
+}
+
+/// Append `record` as a JSON object with `keys`, or as an array without them.
+///
+/// Returns the index of a field that is not valid UTF-8 under the error policy.
+fn encode_record(
+    out: &mut Vec<u8>,
+    record: &ByteRecord,
+    keys: Option<&[Vec<u8>]>,
+    options: &CsvJsonOptions,
+) -> Result<(), usize> {
+    let (open, close) = if keys.is_some() {
+        (b'{', b'}')
+    } else {
+        (b'[', b']')
+    };
+    out.push(open);
+    let mut first = true;
+    for (index, field) in record.iter().enumerate() {
+        let key = match keys {
+            Some(keys) => match keys.get(index) {
+                Some(key) => Some(key),
+                None => break,
+            },
+            None => None,
+        };
+        if key.is_some() && options.drop_empty && field.is_empty() {
+            continue;
+        }
+        if !first {
+            out.push(b',');
+        }
+        first = false;
+        if let Some(key) = key {
+            out.extend_from_slice(key);
+        }
+        if !write_value(out, field, options) {
+            return Err(index);
+        }
+    }
+    out.push(close);
+    Ok(())
+}
+
+/// Append `field` as a JSON value, returning `false` if it is invalid UTF-8
+/// that the options do not allow.
+fn write_value(out: &mut Vec<u8>, field: &[u8], options: &CsvJsonOptions) -> bool {
+    if options
+        .null_tokens
+        .iter()
+        .any(|token| token.as_bytes() == field)
+    {
+        out.extend_from_slice(b"null");
+        return true;
+    }
+    if options.coerce_types {
+        if is_json_number(field) {
+            out.extend_from_slice(field);
+            return true;
+        }
+        if field.eq_ignore_ascii_case(b"true") || field.eq_ignore_ascii_case(b"false") {
+            let value: &[u8] = if field.len() == 4 { b"true" } else { b"false" };
+            out.extend_from_slice(value);
+            return true;
+        }
+    }
+    match (std::str::from_utf8(field), options.invalid_utf8) {
+        (Ok(value), _) => write_string(out, value),
+        (Err(_), CsvJsonInvalidUtf8::Error) => return false,
+        (Err(_), CsvJsonInvalidUtf8::Lossy) => write_string(out, &String::from_utf8_lossy(field)),
+        (Err(_), CsvJsonInvalidUtf8::Base64) => {
+            out.push(b'"');
+            write_base64(out, field);
+            out.push(b'"');
+        }
+    }
+    true
+}
+
+/// Return whether `value` is already a number in JSON syntax.
+fn is_json_number(value: &[u8]) -> bool {
+    let digits = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+    let rest = value.strip_prefix(b"-").unwrap_or(value);
+    let whole = digits(rest);
+    if whole == 0 || (whole > 1 && rest[0] == b'0') {
+        return false;
+    }
+    let mut rest = &rest[whole..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let count = digits(fraction);
+        if count == 0 {
+            return false;
+        }
+        rest = &fraction[count..];
+    }
+    if let Some(exponent) = rest.strip_prefix(b"e").or_else(|| rest.strip_prefix(b"E")) {
+        let exponent = exponent
+            .strip_prefix(b"+")
+            .or_else(|| exponent.strip_prefix(b"-"))
+            .unwrap_or(exponent);
+        let count = digits(exponent);
+        if count == 0 {
+            return false;
+        }
+        rest = &exponent[count..];
+    }
+    rest.is_empty()
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    out.push(b'"');
+    for &byte in value.as_bytes() {
+        match byte {
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            0..=0x1f => out.extend_from_slice(&[
+                b'\\',
+                b'u',
+                b'0',
+                b'0',
+                HEX[usize::from(byte >> 4)],
+                HEX[usize::from(byte & 0xf)],
+            ]),
+            _ => out.push(byte),
+        }
+    }
+    out.push(b'"');
+}
+
+fn write_base64(out: &mut Vec<u8>, bytes: &[u8]) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | u32::from(byte) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize]);
+            } else {
+                out.push(b'=');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvOptions;
+    use std::io::Cursor;
+
+    async fn convert(input: &'static [u8], csv: &CsvOptions, json: &CsvJsonOptions) -> String {
+        let mut output = Vec::new();
+        CsvParser::from_reader(Cursor::new(input), &[], csv)
+            .await
+            .expect("construct parser")
+            .write_json_lines(&mut output, json)
+            .await
+            .expect("write json lines");
+        String::from_utf8(output).expect("json is utf-8")
+    }
+
+    #[tokio::test]
+    async fn objects_are_keyed_by_header_with_optional_coercion() {
+        let input =
+            b"id,price,ok,note,\"say \"\"hi\"\"\"\n7,+1.50,TRUE,,a\tb\n-0,1e3,no,NULL,\"x\ny\"\n";
+        let plain = convert(input, &CsvOptions::default(), &CsvJsonOptions::default()).await;
+        assert_eq!(
+            plain,
+            "{\"id\":\"7\",\"price\":\"+1.50\",\"ok\":\"TRUE\",\"note\":\"\",\"say \\\"hi\\\"\":\"a\\tb\"}\n\
+             {\"id\":\"-0\",\"price\":\"1e3\",\"ok\":\"no\",\"note\":\"NULL\",\"say \\\"hi\\\"\":\"x\\ny\"}\n"
+        );
+
+        let options = CsvJsonOptions {
+            coerce_types: true,
+            null_tokens: vec!["NULL".to_string()],
+            drop_empty: true,
+            ..CsvJsonOptions::default()
+        };
+        let coerced = convert(input, &CsvOptions::default(), &options).await;
+        assert_eq!(
+            coerced,
+            "{\"id\":7,\"price\":\"+1.50\",\"ok\":true,\"say \\\"hi\\\"\":\"a\\tb\"}\n\
+             {\"id\":-0,\"price\":1e3,\"ok\":\"no\",\"note\":null,\"say \\\"hi\\\"\":\"x\\ny\"}\n"
+        );
+        for line in coerced.lines() {
+            serde_json::from_str::<serde_json::Value>(line).expect("valid json");
+        }
+    }
+
+    #[tokio::test]
+    async fn headerless_records_become_arrays_that_keep_empty_fields() {
+        let csv = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            flexible: true,
+            ..CsvOptions::default()
+        };
+        let json = CsvJsonOptions {
+            coerce_types: true,
+            drop_empty: true,
+            ..CsvJsonOptions::default()
+        };
+        let output = convert(b"1,,x\n2\n", &csv, &json).await;
+        assert_eq!(output, "[1,\"\",\"x\"]\n[2]\n");
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8_fields_follow_the_policy() {
+        let input: &'static [u8] = b"name,raw\nok,\xff\xfeA\n";
+        let lossy = CsvJsonOptions {
+            invalid_utf8: CsvJsonInvalidUtf8::Lossy,
+            ..CsvJsonOptions::default()
+        };
+        assert_eq!(
+            convert(input, &CsvOptions::default(), &lossy).await,
+            "{\"name\":\"ok\",\"raw\":\"\u{fffd}\u{fffd}A\"}\n"
+        );
+        let base64 = CsvJsonOptions {
+            invalid_utf8: CsvJsonInvalidUtf8::Base64,
+            ..CsvJsonOptions::default()
+        };
+        assert_eq!(
+            convert(input, &CsvOptions::default(), &base64).await,
+            "{\"name\":\"ok\",\"raw\":\"//5B\"}\n"
+        );
+
+        let error = CsvParser::from_reader(Cursor::new(input), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .with_source_name("names.csv")
+            .write_json_lines(Vec::new(), &CsvJsonOptions::default())
+            .await
+            .expect_err("invalid utf-8 is an error by default");
+        assert!(matches!(
+            error,
+            CsvIngestError::InvalidValue { row: 1, ref column, ref source_name, .. }
+                if column == "raw" && source_name.as_deref() == Some("names.csv")
+        ));
+        assert_eq!(error.position().map(|position| position.byte()), Some(9));
+    }
+
+    #[tokio::test]
+    async fn coercion_keeps_numbers_exactly_as_written() {
+        let json = CsvJsonOptions {
+            coerce_types: true,
+            ..CsvJsonOptions::default()
+        };
+        let output = convert(
+            b"zip,code,delta,ratio,half,big\n02134,007,+5,1.0,.5,12345678901234567890.10\n",
+            &CsvOptions::default(),
+            &json,
+        )
+        .await;
+        assert_eq!(
+            output,
+            "{\"zip\":\"02134\",\"code\":\"007\",\"delta\":\"+5\",\"ratio\":1.0,\"half\":\".5\",\"big\":12345678901234567890.10}\n"
+        );
+    }
+
+    #[test]
+    fn numbers_and_base64_match_their_specifications() {
+        for valid in ["0", "-0", "12", "1.25", "-3e7", "2E-2", "4.5e+1"] {
+            assert!(is_json_number(valid.as_bytes()), "{valid}");
+        }
+        for invalid in ["", "-", "01", "+1", ".5", "5.", "1e", "0x1", "1_000"] {
+            assert!(!is_json_number(invalid.as_bytes()), "{invalid}");
+        }
+
+        let encode = |bytes: &[u8]| {
+            let mut out = Vec::new();
+            write_base64(&mut out, bytes);
+            String::from_utf8(out).expect("base64 is ascii")
+        };
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+    }
+}
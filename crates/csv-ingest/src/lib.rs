@@ -1,35 +1,128 @@
 //! Streaming CSV ingestion with optional fast local path.
 //!
-//! - Streaming path: works with local files and gzip/zstd.
-//! - Fast local path: feature `fast_local`, uncompressed local UTF-8 only.
+//! - Streaming path: works with local files and gzip/zstd, decoding every member or frame.
+//! - Pipelined summaries: [`summarize_csv_stream_pipelined`], decoding and parsing on separate cores.
+//! - Fast local path: feature `fast_local`, uncompressed UTF-8 files or buffers, with the streaming parser's quoting,
+//!   counting rows, visiting [`FastLocalRecord`]s, or folding them, optionally on a [`FastLocalPool`].
+//! - Parallel decompression: feature `parallel_decompress`, for BGZF, multi-member gzip and multi-frame zstd files.
+//! - Typed records: feature `serde`, deserialized by header name.
+//! - Zero-copy views: [`CsvRecord`], derivable with feature `derive`.
+//! - Arrow record batches: feature `arrow`, streamed or built in parallel by the fast local path.
+//! - Parquet files: feature `parquet`, written from either path.
+//! - Regular-expression schema rules: default feature `schema_regex`, for [`CsvRule`]'s `Pattern`.
+//! - CSV output: [`CsvWriter`], in the dialect of a [`CsvOptions`], optionally compressed or transcoded.
+//! - JSON Lines: [`CsvParser::write_json_lines`], one object or array per record.
 //!
 //! Data shape:
-//! - `CsvIngestSummary { row_count, headers }`
+//! - `CsvIngestSummary { row_count, headers, scanned_rows, filtered_rows, rejected_rows, diagnostics }`
 //! - Streaming rows: [`ByteRecord`] (access with `get(idx) -> Option<&[u8]>`)
+//! - Record-level errors carry a [`Position`] and the source's `CsvMeta::name_hint`
 #![cfg_attr(docsrs, feature(doc_cfg))]
 //
 mod codec;
+#[cfg(feature = "parallel_decompress")]
+mod decompress;
 #[cfg(feature = "fast_local")]
 mod fast;
+mod filter;
+mod inference;
 mod io;
+mod json_lines;
+mod members;
 mod options;
+#[cfg(feature = "parquet")]
+mod parquet_writer;
 mod parser;
+mod pipeline;
+mod projection;
+mod quarantine;
+mod record;
+#[cfg(feature = "arrow")]
+mod record_batch;
+mod schema;
+mod stream;
+mod writer;
 
 pub use crate::codec::{DecodePolicy, TranscodingError};
+#[cfg(feature = "parallel_decompress")]
+pub use crate::decompress::{reader_from_path_parallel, CsvDecompressConfig};
+#[cfg(all(feature = "fast_local", feature = "parallel_decompress"))]
+pub use crate::fast::fast_local_process_compressed;
+#[cfg(all(feature = "fast_local", feature = "arrow"))]
+pub use crate::fast::fast_local_record_batches;
 #[cfg(feature = "fast_local")]
-pub use crate::fast::fast_local_process;
+pub use crate::fast::{
+    fast_local_fold, fast_local_process, fast_local_process_bytes, fast_local_process_file,
+    fast_local_process_with_config, fast_local_records, fast_local_visit, FastLocalAccess,
+    FastLocalAdvice, FastLocalConfig, FastLocalPool, FastLocalRecord, FastLocalRecords,
+    FastLocalTask, FastLocalVisitor,
+};
+pub use crate::filter::CsvFilter;
+pub use crate::inference::{CsvColumnType, CsvInferredColumn, CsvInferredSchema, CsvSampling};
 pub use crate::io::{build_csv_reader, reader_from_path, BoxedCsvReader, CsvMeta};
-pub use crate::options::{CsvHeaderMode, CsvOptions, CsvTerminator, CsvTrim};
+pub use crate::json_lines::{CsvJsonInvalidUtf8, CsvJsonOptions};
+pub use crate::members::{CompressedStreamError, TrailingDataPolicy, TrailingDataReport};
+pub use crate::options::{CsvErrorPolicy, CsvHeaderMode, CsvOptions, CsvTerminator, CsvTrim};
+#[cfg(all(feature = "fast_local", feature = "parquet"))]
+pub use crate::parquet_writer::fast_local_write_parquet;
+#[cfg(feature = "parquet")]
+pub use crate::parquet_writer::{CsvParquetCompression, CsvParquetOptions};
 pub use crate::parser::{summarize_csv_path, summarize_csv_stream, CsvParser};
-pub use csv_async::ByteRecord;
+pub use crate::pipeline::{
+    summarize_csv_path_pipelined, summarize_csv_stream_pipelined, CsvPipelineConfig,
+};
+pub use crate::projection::{CsvProjection, CsvSelector};
+pub use crate::quarantine::{CsvQuarantine, CsvQuarantineWriter};
+pub use crate::record::{CsvColumns, CsvField, CsvRecord};
+#[cfg(feature = "arrow")]
+pub use crate::record_batch::{
+    CsvArrowOptions, CsvRecordBatchReader, CsvRecordBatchStream, CsvRecordBatches,
+};
+pub use crate::schema::{CsvColumnSchema, CsvRule, CsvSchema};
+pub use crate::stream::{CsvBatchStream, CsvRecordStream};
+pub use crate::writer::{CsvQuoteStyle, CsvWriteCompression, CsvWriteOptions, CsvWriter};
+pub use csv_async::{ByteRecord, Position};
+#[cfg(feature = "derive")]
+pub use csv_ingest_derive::CsvRecord;
 
 use thiserror::Error;
 
 /// A count-and-header summary of a parsed CSV stream.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// `row_count` counts accepted rows and `scanned_rows` every data row read.
+/// Rows skipped by a [`CsvFilter`] are counted in `filtered_rows`. Rows skipped
+/// by a [`CsvErrorPolicy`] are counted in `rejected_rows` and described, in
+/// input order, by `diagnostics`, which keeps at most
+/// [`CsvOptions::max_diagnostics`] of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct CsvIngestSummary {
     pub row_count: u64,
     pub headers: Vec<String>,
+    pub scanned_rows: u64,
+    pub filtered_rows: u64,
+    pub rejected_rows: u64,
+    pub diagnostics: Vec<CsvDiagnostic>,
+}
+
+/// A row rejected by a skipping [`CsvErrorPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvDiagnostic {
+    /// One-based data row number, counting rejected rows.
+    pub row: u64,
+    /// Where the rejected record starts, when known.
+    pub position: Option<Position>,
+    /// The rendered record-level error.
+    pub message: String,
+}
+
+impl CsvDiagnostic {
+    pub(crate) fn new(error: &CsvIngestError) -> Self {
+        Self {
+            row: error.row().unwrap_or_default(),
+            position: error.position().cloned(),
+            message: error.to_string(),
+        }
+    }
 }
 
 /// Error returned by every public parsing path in this crate.
@@ -37,8 +130,17 @@ pub struct CsvIngestSummary {
 pub enum CsvIngestError {
     #[error("Missing required header: {0}")]
     MissingHeader(String),
+    /// A record ended before a required column.
+    ///
+    /// `position` is where the record starts and `source_name` is the
+    /// `CsvMeta::name_hint` of the input, when either is known.
     #[error("Row {row} is missing required field: {header}")]
-    MissingRequiredField { row: u64, header: String },
+    MissingRequiredField {
+        row: u64,
+        header: String,
+        position: Option<Position>,
+        source_name: Option<String>,
+    },
     /// A fixed-width parser encountered a record with a different width.
     /// `row` is absent only when the underlying parser provides no position.
     #[error("Ragged row: got {actual} fields, expected {expected}")]
@@ -46,7 +148,65 @@ pub enum CsvIngestError {
         row: Option<u64>,
         expected: u64,
         actual: u64,
+        position: Option<Position>,
+        source_name: Option<String>,
     },
+    /// A field violated a [`CsvRule`] of the configured [`CsvSchema`].
+    #[error("Row {row} column '{column}' violates rule: {rule}")]
+    SchemaViolation {
+        row: u64,
+        column: String,
+        rule: Box<CsvRule>,
+        position: Option<Position>,
+        source_name: Option<String>,
+    },
+    /// A field could not be converted to the type its output column requires.
+    ///
+    /// `expected` names the target type, such as an Arrow data type.
+    #[error("Row {row} column '{column}' is not a valid {expected}")]
+    InvalidValue {
+        row: u64,
+        column: String,
+        expected: String,
+        position: Option<Position>,
+        source_name: Option<String>,
+    },
+    /// A record passed to a [`CsvWriter`] cannot be represented in its output
+    /// charset. `record` is the one-based number of the rejected record.
+    #[error("Record {record} cannot be encoded as {encoding}")]
+    UnencodableOutput { record: u64, encoding: &'static str },
+    /// A [`CsvSchema`] could not be compiled, such as an invalid pattern.
+    #[error("Invalid CSV schema: {0}")]
+    InvalidSchema(String),
+    /// A record could not be deserialized into the requested type.
+    ///
+    /// `field` is the zero-based column index and `column` its header, when
+    /// the failure can be attributed to one column.
+    #[cfg(feature = "serde")]
+    #[error(
+        "Row {row} could not be deserialized{}: {}",
+        column_context(column.as_deref(), *field),
+        source.kind()
+    )]
+    Deserialize {
+        row: u64,
+        field: Option<u64>,
+        column: Option<String>,
+        source: Box<csv_async::DeserializeError>,
+        position: Option<Position>,
+        source_name: Option<String>,
+    },
+    /// Arrow rejected a schema or batch built from the input.
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+    /// Writing a Parquet file failed.
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    /// More rows were rejected than the configured [`CsvErrorPolicy`] allows.
+    #[error("Rejected {rejected} of {scanned} rows, exceeding the error budget")]
+    ErrorBudgetExceeded { rejected: u64, scanned: u64 },
     /// The selected parser cannot represent the configured CSV dialect.
     #[error("Unsupported CSV dialect: {0}")]
     UnsupportedDialect(String),
@@ -72,6 +232,9 @@ pub enum CsvIngestError {
     /// Input declared as a non-UTF-8 encoding contained malformed bytes.
     #[error("Invalid encoded input: {0}")]
     InvalidEncoding(#[source] std::io::Error),
+    /// A gzip or zstd stream was truncated or followed by unexpected data.
+    #[error("Invalid compressed input: {0}")]
+    CompressedStream(#[source] CompressedStreamError),
     #[error("I/O error: {0}")]
     Io(#[source] std::io::Error),
     /// A CSV parser failure that is neither I/O nor a ragged record.
@@ -80,12 +243,130 @@ pub enum CsvIngestError {
     #[cfg(feature = "fast_local")]
     #[error("Fast-local parser worker panicked")]
     FastLocalWorkerPanicked,
+    /// A fast-local input file was truncated, extended, rewritten, or replaced
+    /// while it was parsed, so no result would describe it.
+    #[cfg(feature = "fast_local")]
+    #[error("Source file was modified while it was parsed")]
+    SourceModified { source_name: Option<String> },
+    /// A fast-local data row contained bytes that are not UTF-8, found by
+    /// `FastLocalConfig::validate_utf8`.
+    ///
+    /// `offset` is the absolute byte offset of the first invalid byte, and
+    /// `position` is where its record starts.
+    #[cfg(feature = "fast_local")]
+    #[error("Row {row} has invalid UTF-8 at byte {offset}")]
+    InvalidUtf8Row {
+        row: u64,
+        offset: u64,
+        position: Option<Position>,
+        source_name: Option<String>,
+    },
+    #[error("Pipelined parser worker panicked")]
+    PipelineWorkerPanicked,
+    #[cfg(feature = "parallel_decompress")]
+    #[error("Parallel decompression worker panicked")]
+    DecompressWorkerPanicked,
 }
 
 pub type CsvResult<T> = std::result::Result<T, CsvIngestError>;
 
+#[cfg(feature = "serde")]
+fn column_context(column: Option<&str>, field: Option<u64>) -> String {
+    match (column, field) {
+        (Some(column), _) => format!(" at column '{column}'"),
+        (None, Some(field)) => format!(" at field {field}"),
+        (None, None) => String::new(),
+    }
+}
+
+impl CsvIngestError {
+    /// Return the one-based data row of a record-level error, when known.
+    pub fn row(&self) -> Option<u64> {
+        match self {
+            Self::MissingRequiredField { row, .. } => Some(*row),
+            Self::RaggedRow { row, .. } => *row,
+            Self::SchemaViolation { row, .. } | Self::InvalidValue { row, .. } => Some(*row),
+            #[cfg(feature = "serde")]
+            Self::Deserialize { row, .. } => Some(*row),
+            #[cfg(feature = "fast_local")]
+            Self::InvalidUtf8Row { row, .. } => Some(*row),
+            _ => None,
+        }
+    }
+
+    /// Return the position of the record that caused this error, when known.
+    ///
+    /// Positions follow `csv_async`: byte offsets and record indices start at
+    /// zero, line numbers start at one, and the header is record zero.
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            Self::MissingRequiredField { position, .. }
+            | Self::RaggedRow { position, .. }
+            | Self::SchemaViolation { position, .. }
+            | Self::InvalidValue { position, .. } => position.as_ref(),
+            #[cfg(feature = "serde")]
+            Self::Deserialize { position, .. } => position.as_ref(),
+            #[cfg(feature = "fast_local")]
+            Self::InvalidUtf8Row { position, .. } => position.as_ref(),
+            Self::InvalidCsvUtf8(error) | Self::CsvSyntax(error) => error.position(),
+            _ => None,
+        }
+    }
+
+    /// Return the `CsvMeta::name_hint` of the input that caused this error, or
+    /// the source name of a fast-local input.
+    pub fn source_name(&self) -> Option<&str> {
+        match self {
+            Self::MissingRequiredField { source_name, .. }
+            | Self::RaggedRow { source_name, .. }
+            | Self::SchemaViolation { source_name, .. }
+            | Self::InvalidValue { source_name, .. } => source_name.as_deref(),
+            #[cfg(feature = "serde")]
+            Self::Deserialize { source_name, .. } => source_name.as_deref(),
+            #[cfg(feature = "fast_local")]
+            Self::SourceModified { source_name } | Self::InvalidUtf8Row { source_name, .. } => {
+                source_name.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Return whether a skipping [`CsvErrorPolicy`] may reject the row instead.
+    pub(crate) fn is_record_error(&self) -> bool {
+        matches!(
+            self,
+            Self::MissingRequiredField { .. }
+                | Self::RaggedRow { .. }
+                | Self::SchemaViolation { .. }
+        )
+    }
+
+    /// Attach a source name to record-level errors that do not have one yet.
+    pub(crate) fn with_source_name(mut self, name: Option<&str>) -> Self {
+        let source_name = match &mut self {
+            Self::MissingRequiredField { source_name, .. }
+            | Self::RaggedRow { source_name, .. }
+            | Self::SchemaViolation { source_name, .. }
+            | Self::InvalidValue { source_name, .. } => source_name,
+            #[cfg(feature = "fast_local")]
+            Self::InvalidUtf8Row { source_name, .. } => source_name,
+            _ => return self,
+        };
+        if source_name.is_none() {
+            *source_name = name.map(str::to_string);
+        }
+        self
+    }
+}
+
 impl From<std::io::Error> for CsvIngestError {
     fn from(error: std::io::Error) -> Self {
+        if let Some(compressed) = error
+            .get_ref()
+            .and_then(|source| source.downcast_ref::<CompressedStreamError>())
+        {
+            return Self::CompressedStream(*compressed);
+        }
         if error
             .get_ref()
             .is_some_and(|source| source.is::<TranscodingError>())
@@ -106,9 +387,11 @@ impl From<csv_async::Error> for CsvIngestError {
         } = error.kind()
         {
             return Self::RaggedRow {
-                row: pos.as_ref().map(csv_async::Position::record),
+                row: pos.as_ref().map(Position::record),
                 expected: *expected_len,
                 actual: *len,
+                position: pos.clone(),
+                source_name: None,
             };
         }
 
@@ -160,7 +443,7 @@ mod tests {
 
         assert!(matches!(
             error,
-            CsvIngestError::MissingRequiredField { row: 1, header } if header == "value"
+            CsvIngestError::MissingRequiredField { row: 1, header, .. } if header == "value"
         ));
     }
 
@@ -179,7 +462,8 @@ mod tests {
             CsvIngestError::RaggedRow {
                 row: Some(1),
                 expected: 2,
-                actual: 1
+                actual: 1,
+                ..
             }
         ));
     }
@@ -197,13 +481,7 @@ mod tests {
         .await
         .expect("parse header-only input");
 
-        assert_eq!(
-            empty,
-            CsvIngestSummary {
-                row_count: 0,
-                headers: vec![]
-            }
-        );
+        assert_eq!(empty, CsvIngestSummary::default());
         assert_eq!(header_only.row_count, 0);
         assert_eq!(header_only.headers, ["sku", "value"]);
     }
@@ -222,7 +500,8 @@ mod tests {
             summary,
             CsvIngestSummary {
                 row_count: 2,
-                headers: vec![]
+                scanned_rows: 2,
+                ..CsvIngestSummary::default()
             }
         );
     }
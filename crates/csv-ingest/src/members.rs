@@ -0,0 +1,482 @@
+use crate::io::Compression;
+use async_compression::tokio::bufread::GzipDecoder;
+use bytes::{Buf, BytesMut};
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::task::{ready, Context, Poll};
+use thiserror::Error;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+use tokio_util::io::poll_read_buf;
+use zstd_safe::{DCtx, InBuffer, OutBuffer};
+
+const INPUT_CAPACITY: usize = 1 << 20;
+/// Enough bytes to recognize the start of a gzip member or zstd frame.
+const MAGIC_LEN: usize = 4;
+
+/// Controls what decompression does with data after the last gzip member or
+/// zstd frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingDataPolicy {
+    /// Fail with [`CompressedStreamError::TrailingData`].
+    #[default]
+    Error,
+    /// Stop at the end of the last member or frame.
+    Ignore,
+    /// Stop at the end of the last member or frame and record where the
+    /// trailing data starts in [`CsvMeta::trailing_data`](crate::CsvMeta::trailing_data).
+    Warn,
+}
+
+/// Where a reader found trailing data under [`TrailingDataPolicy::Warn`].
+///
+/// Clones share one record, so the [`CsvMeta`](crate::CsvMeta) returned with
+/// a reader reports what the reader found once it has reached the end.
+#[derive(Debug, Clone, Default)]
+pub struct TrailingDataReport(Arc<OnceLock<u64>>);
+
+impl TrailingDataReport {
+    /// The compressed byte offset where ignored trailing data starts, if any
+    /// was found.
+    pub fn offset(&self) -> Option<u64> {
+        self.0.get().copied()
+    }
+
+    pub(crate) fn record(&self, offset: u64) {
+        let _ = self.0.set(offset);
+    }
+}
+
+/// A compressed stream that is not a complete sequence of gzip members or
+/// zstd frames.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum CompressedStreamError {
+    /// The input ended inside a member or frame. `offset` is the number of
+    /// compressed bytes read.
+    #[error("{compression} stream is truncated at compressed byte {offset}")]
+    Truncated {
+        compression: &'static str,
+        offset: u64,
+    },
+    /// Data that does not start a member or frame follows the last complete
+    /// one, starting at compressed byte `offset`.
+    #[error("unexpected data after the last {compression} member at compressed byte {offset}")]
+    TrailingData {
+        compression: &'static str,
+        offset: u64,
+    },
+}
+
+impl From<CompressedStreamError> for io::Error {
+    fn from(error: CompressedStreamError) -> Self {
+        let kind = match error {
+            CompressedStreamError::Truncated { .. } => io::ErrorKind::UnexpectedEof,
+            CompressedStreamError::TrailingData { .. } => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+impl Compression {
+    /// Whether `head` starts a gzip member, or a zstd frame or skippable frame.
+    pub(crate) fn starts_member(self, head: &[u8]) -> bool {
+        match self {
+            Self::Gzip => head.starts_with(b"\x1f\x8b"),
+            Self::Zstd => {
+                head.starts_with(b"\x28\xb5\x2f\xfd")
+                    || (head.len() >= MAGIC_LEN
+                        && head[0] & 0xf0 == 0x50
+                        && head[1..4] == *b"\x2a\x4d\x18")
+            }
+        }
+    }
+}
+
+/// Buffered compressed input that counts the bytes consumed from it.
+struct Input<R> {
+    inner: R,
+    buffer: BytesMut,
+    consumed: u64,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> Input<R> {
+    /// Buffer at least `len` bytes, or all that remain.
+    fn poll_fill(&mut self, cx: &mut Context<'_>, len: usize) -> Poll<io::Result<&[u8]>> {
+        while self.buffer.len() < len && !self.eof {
+            self.buffer.reserve(INPUT_CAPACITY);
+            if ready!(poll_read_buf(
+                Pin::new(&mut self.inner),
+                cx,
+                &mut self.buffer
+            ))? == 0
+            {
+                self.eof = true;
+            }
+        }
+        Poll::Ready(Ok(&self.buffer))
+    }
+
+    /// Whether every byte of the input has been consumed.
+    fn exhausted(&self) -> bool {
+        self.eof && self.buffer.is_empty()
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufRead for Input<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.get_mut().poll_fill(cx, 1)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.buffer.advance(amt);
+        self.consumed += amt as u64;
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Input<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let available = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = available.len().min(buf.remaining());
+        buf.put_slice(&available[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn truncated(compression: Compression, offset: u64) -> io::Error {
+    CompressedStreamError::Truncated {
+        compression: compression.name(),
+        offset,
+    }
+    .into()
+}
+
+pub(crate) fn not_compressed(compression: Compression) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("input is not a {} stream", compression.name()),
+    )
+}
+
+enum State<R> {
+    /// Between members, or before the first.
+    Between(Input<R>),
+    Gzip(Box<GzipDecoder<Input<R>>>),
+    Zstd(Input<R>),
+    Done,
+}
+
+/// Decodes every gzip member or zstd frame of a stream in turn.
+pub(crate) struct MemberReader<R> {
+    state: State<R>,
+    compression: Compression,
+    zstd: Option<DCtx<'static>>,
+    members: u64,
+    policy: TrailingDataPolicy,
+    report: TrailingDataReport,
+}
+
+impl<R: AsyncRead + Unpin> MemberReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        compression: Compression,
+        policy: TrailingDataPolicy,
+        report: TrailingDataReport,
+    ) -> Self {
+        Self {
+            state: State::Between(Input {
+                inner,
+                buffer: BytesMut::new(),
+                consumed: 0,
+                eof: false,
+            }),
+            compression,
+            zstd: None,
+            members: 0,
+            policy,
+            report,
+        }
+    }
+
+    /// Decode from the current zstd frame into `buf`, returning whether the
+    /// frame is complete.
+    fn poll_zstd(&mut self, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<bool>> {
+        let State::Zstd(input) = &mut self.state else {
+            unreachable!("only called while decoding a zstd frame");
+        };
+        if ready!(input.poll_fill(cx, 1))?.is_empty() {
+            return Poll::Ready(Err(truncated(self.compression, input.consumed)));
+        }
+        let context = self.zstd.get_or_insert_with(DCtx::create);
+        let mut source = InBuffer::around(&input.buffer);
+        let mut target = OutBuffer::around(buf.initialize_unfilled());
+        let remaining = context
+            .decompress_stream(&mut target, &mut source)
+            .map_err(|code| {
+                io::Error::new(io::ErrorKind::InvalidData, zstd_safe::get_error_name(code))
+            })?;
+        let (read, written) = (source.pos(), target.pos());
+        Pin::new(input).consume(read);
+        buf.advance(written);
+        Poll::Ready(Ok(remaining == 0))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for MemberReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            let filled = buf.filled().len();
+            match &mut this.state {
+                State::Done => return Poll::Ready(Ok(())),
+                State::Between(input) => {
+                    let head = ready!(input.poll_fill(cx, MAGIC_LEN))?;
+                    if head.is_empty() {
+                        this.state = State::Done;
+                        continue;
+                    }
+                    if !this.compression.starts_member(head) {
+                        let offset = input.consumed;
+                        this.state = State::Done;
+                        if this.members == 0 {
+                            return Poll::Ready(Err(not_compressed(this.compression)));
+                        }
+                        match this.policy {
+                            TrailingDataPolicy::Error => {
+                                let error = CompressedStreamError::TrailingData {
+                                    compression: this.compression.name(),
+                                    offset,
+                                };
+                                return Poll::Ready(Err(error.into()));
+                            }
+                            TrailingDataPolicy::Ignore => {}
+                            TrailingDataPolicy::Warn => this.report.record(offset),
+                        }
+                        continue;
+                    }
+                    let State::Between(input) = mem::replace(&mut this.state, State::Done) else {
+                        unreachable!("the state was just matched");
+                    };
+                    this.state = match this.compression {
+                        Compression::Gzip => State::Gzip(Box::new(GzipDecoder::new(input))),
+                        Compression::Zstd => State::Zstd(input),
+                    };
+                }
+                State::Gzip(decoder) => {
+                    let result = ready!(Pin::new(&mut **decoder).poll_read(cx, buf));
+                    if let Err(error) = result {
+                        let input = decoder.get_ref();
+                        let error = if input.exhausted() {
+                            truncated(this.compression, input.consumed)
+                        } else {
+                            error
+                        };
+                        this.state = State::Done;
+                        return Poll::Ready(Err(error));
+                    }
+                    if buf.filled().len() > filled {
+                        return Poll::Ready(Ok(()));
+                    }
+                    let State::Gzip(decoder) = mem::replace(&mut this.state, State::Done) else {
+                        unreachable!("the state was just matched");
+                    };
+                    this.members += 1;
+                    this.state = State::Between(decoder.into_inner());
+                }
+                State::Zstd(_) => {
+                    let complete = match ready!(this.poll_zstd(cx, buf)) {
+                        Ok(complete) => complete,
+                        Err(error) => {
+                            this.state = State::Done;
+                            return Poll::Ready(Err(error));
+                        }
+                    };
+                    if complete {
+                        let State::Zstd(input) = mem::replace(&mut this.state, State::Done) else {
+                            unreachable!("the state was just matched");
+                        };
+                        this.members += 1;
+                        this.state = State::Between(input);
+                    }
+                    if buf.filled().len() > filled {
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_csv_reader, CsvIngestError, CsvMeta, CsvResult};
+    use async_compression::tokio::write::{GzipEncoder, ZstdEncoder};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Yields its data a few bytes per read, so members straddle reads.
+    struct Trickle(Vec<u8>, usize);
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let Self(data, position) = &mut *self;
+            let len = (data.len() - *position).min(buf.remaining()).min(7);
+            buf.put_slice(&data[*position..*position + len]);
+            *position += len;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    async fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzipEncoder::new(Vec::new());
+        encoder.write_all(bytes).await.expect("write gzip input");
+        encoder.shutdown().await.expect("finish gzip member");
+        encoder.into_inner()
+    }
+
+    async fn zstd(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = ZstdEncoder::new(Vec::new());
+        encoder.write_all(bytes).await.expect("write zstd input");
+        encoder.shutdown().await.expect("finish zstd frame");
+        encoder.into_inner()
+    }
+
+    fn skippable(payload: &[u8]) -> Vec<u8> {
+        let mut frame = b"\x5a\x2a\x4d\x18".to_vec();
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    async fn decode(raw: Vec<u8>, meta: CsvMeta) -> CsvResult<(Vec<u8>, CsvMeta)> {
+        let (mut reader, meta) = build_csv_reader(Trickle(raw, 0), meta)?;
+        let mut decoded = Vec::new();
+        reader.read_to_end(&mut decoded).await?;
+        Ok((decoded, meta))
+    }
+
+    fn meta(name: &str, trailing_data_policy: TrailingDataPolicy) -> CsvMeta {
+        CsvMeta {
+            name_hint: name.into(),
+            trailing_data_policy,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn every_member_and_frame_is_decoded() {
+        let parts: [&[u8]; 4] = [b"sku,qty\n", b"A,1\n", b"", b"B,2\n"];
+        let mut gzip_file = Vec::new();
+        let mut zstd_file = skippable(b"leading");
+        for part in parts {
+            gzip_file.extend_from_slice(&gzip(part).await);
+            zstd_file.extend_from_slice(&zstd(part).await);
+            zstd_file.extend_from_slice(&skippable(b"between"));
+        }
+
+        for (raw, name) in [(gzip_file, "rows.csv.gz"), (zstd_file, "rows.csv.zst")] {
+            let (decoded, meta) = decode(raw, meta(name, TrailingDataPolicy::Error))
+                .await
+                .expect("decode members");
+            assert_eq!(decoded, parts.concat(), "{name}");
+            assert_eq!(meta.trailing_data.offset(), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn truncated_streams_report_the_compressed_offset() {
+        let data = b"sku,qty\nA,1\nB,2\n".repeat(100);
+        for (mut raw, name, compression) in [
+            (
+                [gzip(&data).await, gzip(&data).await].concat(),
+                "rows.csv.gz",
+                "gzip",
+            ),
+            (
+                [zstd(&data).await, zstd(&data).await].concat(),
+                "rows.csv.zst",
+                "zstd",
+            ),
+        ] {
+            raw.truncate(raw.len() - 3);
+            let error = decode(raw.clone(), meta(name, TrailingDataPolicy::Error))
+                .await
+                .expect_err("truncated input must fail");
+            let expected = CompressedStreamError::Truncated {
+                compression,
+                offset: raw.len() as u64,
+            };
+            assert!(
+                matches!(error, CsvIngestError::CompressedStream(found) if found == expected),
+                "{error:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn trailing_data_follows_the_policy() {
+        for (raw, name, compression) in [
+            (gzip(b"sku\nA\n").await, "rows.csv.gz", "gzip"),
+            (zstd(b"sku\nA\n").await, "rows.csv.zst", "zstd"),
+        ] {
+            let offset = raw.len() as u64;
+            let padded = [raw, b"\n\0garbage".to_vec()].concat();
+
+            let error = decode(padded.clone(), meta(name, TrailingDataPolicy::Error))
+                .await
+                .expect_err("trailing data must fail");
+            let expected = CompressedStreamError::TrailingData {
+                compression,
+                offset,
+            };
+            assert!(
+                matches!(error, CsvIngestError::CompressedStream(found) if found == expected),
+                "{error:?}"
+            );
+
+            let (decoded, ignored) = decode(padded.clone(), meta(name, TrailingDataPolicy::Ignore))
+                .await
+                .expect("ignore trailing data");
+            assert_eq!(decoded, b"sku\nA\n");
+            assert_eq!(ignored.trailing_data.offset(), None);
+
+            let (decoded, warned) = decode(padded, meta(name, TrailingDataPolicy::Warn))
+                .await
+                .expect("warn about trailing data");
+            assert_eq!(decoded, b"sku\nA\n");
+            assert_eq!(warned.trailing_data.offset(), Some(offset));
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_and_uncompressed_input() {
+        let (decoded, _) = decode(Vec::new(), meta("rows.csv.zst", TrailingDataPolicy::Error))
+            .await
+            .expect("decode empty zstd input");
+        assert!(decoded.is_empty());
+
+        for name in ["rows.csv.gz", "rows.csv.zst"] {
+            let error = decode(b"sku\nA\n".to_vec(), meta(name, TrailingDataPolicy::Ignore))
+                .await
+                .expect_err("uncompressed input must fail");
+            assert!(matches!(error, CsvIngestError::Io(_)), "{error:?}");
+        }
+    }
+}
@@ -28,11 +28,60 @@ pub enum CsvTrim {
     All,
 }
 
+/// How record-level errors affect a parse.
+///
+/// Ragged rows and rows missing a required field are recoverable: a skipping
+/// policy rejects the row, records a [`crate::CsvDiagnostic`], and keeps
+/// reading. Header, transport, encoding, and syntax errors always stop parsing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvErrorPolicy {
+    /// Return the first record-level error.
+    #[default]
+    FailFast,
+    /// Skip every rejected row.
+    Skip,
+    /// Skip at most this many rejected rows.
+    SkipUpTo(u64),
+    /// Skip rejected rows while they are at most this percentage of the rows
+    /// read. The budget is checked once the input is exhausted.
+    SkipUpToPercent(u8),
+}
+
+impl CsvErrorPolicy {
+    pub(crate) fn skips(self) -> bool {
+        self != Self::FailFast
+    }
+
+    /// Return the budget error once `rejected` of `scanned` rows exceed a count budget.
+    pub(crate) fn check_count(self, rejected: u64, scanned: u64) -> CsvResult<()> {
+        match self {
+            Self::SkipUpTo(max) if rejected > max => {
+                Err(CsvIngestError::ErrorBudgetExceeded { rejected, scanned })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Return the budget error if the finished input exceeds a percentage budget.
+    pub(crate) fn check_finished(self, rejected: u64, scanned: u64) -> CsvResult<()> {
+        match self {
+            Self::SkipUpToPercent(percent)
+                if u128::from(rejected) * 100 > u128::from(percent) * u128::from(scanned) =>
+            {
+                Err(CsvIngestError::ErrorBudgetExceeded { rejected, scanned })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 /// CSV dialect options shared by streaming and fast-local parsing.
 ///
 /// A leading UTF-8 BOM is always stripped. Character transcoding, compression,
 /// content type, and filename hints remain transport concerns configured with
-/// [`crate::CsvMeta`].
+/// [`crate::CsvMeta`]. `error_policy` controls whether ragged rows and missing
+/// required fields stop the parse, and `max_diagnostics` how many of the rows
+/// it skips are described.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CsvOptions {
     pub delimiter: u8,
@@ -44,6 +93,11 @@ pub struct CsvOptions {
     pub quote: u8,
     pub escape: Option<u8>,
     pub double_quote: bool,
+    pub error_policy: CsvErrorPolicy,
+    /// The most [`crate::CsvDiagnostic`]s kept for skipped rows, 1,000 by
+    /// default. Rows past the cap are still counted in `rejected_rows` and
+    /// sent to a quarantine.
+    pub max_diagnostics: usize,
 }
 
 impl Default for CsvOptions {
@@ -58,6 +112,8 @@ impl Default for CsvOptions {
             quote: b'"',
             escape: None,
             double_quote: true,
+            error_policy: CsvErrorPolicy::FailFast,
+            max_diagnostics: 1000,
         }
     }
 }
@@ -86,6 +142,12 @@ impl CsvOptions {
             ));
         }
 
+        if matches!(self.error_policy, CsvErrorPolicy::SkipUpToPercent(percent) if percent > 100) {
+            return Err(CsvIngestError::UnsupportedDialect(
+                "error budget percentage cannot exceed 100".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
@@ -147,6 +209,7 @@ mod tests {
         assert_eq!(options.quote, b'"');
         assert_eq!(options.escape, None);
         assert!(options.double_quote);
+        assert_eq!(options.error_policy, CsvErrorPolicy::FailFast);
         options.validate().expect("default options are valid");
     }
 
@@ -197,4 +260,34 @@ mod tests {
             Err(CsvIngestError::UnsupportedDialect(message)) if message.contains("escape")
         ));
     }
+
+    #[test]
+    fn error_budgets_are_checked_by_count_and_percentage() {
+        assert!(CsvErrorPolicy::SkipUpTo(2).check_count(2, 10).is_ok());
+        assert!(matches!(
+            CsvErrorPolicy::SkipUpTo(2).check_count(3, 10),
+            Err(CsvIngestError::ErrorBudgetExceeded {
+                rejected: 3,
+                scanned: 10
+            })
+        ));
+        assert!(CsvErrorPolicy::Skip.check_count(u64::MAX, 1).is_ok());
+
+        assert!(CsvErrorPolicy::SkipUpToPercent(10)
+            .check_finished(1, 10)
+            .is_ok());
+        assert!(CsvErrorPolicy::SkipUpToPercent(10)
+            .check_finished(2, 10)
+            .is_err());
+        assert!(CsvErrorPolicy::SkipUpTo(0).check_finished(5, 5).is_ok());
+
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::SkipUpToPercent(101),
+            ..CsvOptions::default()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(CsvIngestError::UnsupportedDialect(message)) if message.contains("percentage")
+        ));
+    }
 }
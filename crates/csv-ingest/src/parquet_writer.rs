@@ -0,0 +1,407 @@
+use crate::{CsvArrowOptions, CsvIngestError, CsvIngestSummary, CsvParser, CsvResult};
+#[cfg(feature = "fast_local")]
+use arrow::datatypes::SchemaRef;
+#[cfg(feature = "fast_local")]
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::AsyncArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// The codec that compresses Parquet pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvParquetCompression {
+    Uncompressed,
+    #[default]
+    Snappy,
+    /// Gzip at its default level.
+    Gzip,
+    /// Zstandard at its default level.
+    Zstd,
+}
+
+/// How records are written as a Parquet file.
+#[derive(Debug, Clone)]
+pub struct CsvParquetOptions {
+    /// How records are converted to Arrow, including the schema and null tokens.
+    ///
+    /// `arrow.batch_size` also bounds how many rows are converted between writes.
+    pub arrow: CsvArrowOptions,
+    /// The most rows in one row group.
+    pub row_group_size: usize,
+    pub compression: CsvParquetCompression,
+}
+
+impl Default for CsvParquetOptions {
+    fn default() -> Self {
+        Self {
+            arrow: CsvArrowOptions::default(),
+            row_group_size: 1 << 20,
+            compression: CsvParquetCompression::default(),
+        }
+    }
+}
+
+impl CsvParquetOptions {
+    fn properties(&self) -> CsvResult<WriterProperties> {
+        self.arrow.validate()?;
+        if self.row_group_size == 0 {
+            return Err(CsvIngestError::UnsupportedDialect(
+                "row groups must hold at least one row".to_string(),
+            ));
+        }
+        let compression = match self.compression {
+            CsvParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            CsvParquetCompression::Snappy => Compression::SNAPPY,
+            CsvParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            CsvParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        };
+        Ok(WriterProperties::builder()
+            .set_max_row_group_size(self.row_group_size)
+            .set_compression(compression)
+            .build())
+    }
+}
+
+impl<R> CsvParser<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    /// Write the remaining records to `writer` as a Parquet file and summarize the parse.
+    ///
+    /// Records are converted as by [`CsvParser::into_record_batches`] and
+    /// written one batch at a time, so memory use is bounded by the batch and
+    /// the current row group rather than the input. The file is complete, and
+    /// `writer` shut down, only when this returns `Ok`.
+    pub async fn write_parquet<W>(
+        self,
+        writer: W,
+        options: &CsvParquetOptions,
+    ) -> CsvResult<CsvIngestSummary>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let properties = options.properties()?;
+        let mut reader = self.into_record_batches(&options.arrow).await?;
+        let mut writer = AsyncArrowWriter::try_new(writer, reader.schema(), Some(properties))?;
+        while let Some(batch) = reader.next_batch().await? {
+            writer.write(&batch).await?;
+        }
+        writer.close().await?;
+        reader.into_parser().into_summary().await
+    }
+}
+
+/// Write a local file as Parquet, converting chunks on parallel workers.
+///
+/// Records are converted as by [`crate::fast_local_record_batches`]. The file
+/// is parsed in windows, of 8 MiB per worker unless `config.window_size` is
+/// set, and a chunk's batches are written on a separate thread as soon as the
+/// chunks before it are, while later windows are parsed. Memory use is
+/// bounded by about two windows of batches and the row group being written.
+/// When `parquet.arrow.schema` is unset, it is inferred from the first
+/// window. The file is complete only when this returns `Ok`.
+#[cfg(feature = "fast_local")]
+pub fn fast_local_write_parquet<W: std::io::Write + Send>(
+    path: &std::path::Path,
+    required_headers: &[&str],
+    options: &crate::CsvOptions,
+    limit_rows: Option<u64>,
+    parquet: &CsvParquetOptions,
+    config: crate::FastLocalConfig<'_>,
+    writer: W,
+) -> CsvResult<CsvIngestSummary> {
+    let properties = parquet.properties()?;
+    let (sender, received) = std::sync::mpsc::sync_channel::<Vec<RecordBatch>>(1);
+    std::thread::scope(|scope| {
+        let writing = scope.spawn(move || {
+            let mut writer = LazyWriter::Unopened(writer, properties);
+            for batch in received.iter().flatten() {
+                writer = writer.write(&batch)?;
+            }
+            Ok::<_, CsvIngestError>(writer)
+        });
+        let converted = crate::fast::record_batches_each(
+            path,
+            required_headers,
+            options,
+            limit_rows,
+            &parquet.arrow,
+            crate::fast::streamed(config),
+            move |batches| {
+                sender.send(batches).map_err(|_| {
+                    // The writer failed, and its error is returned instead.
+                    CsvIngestError::Io(std::io::Error::new(
+                        std::io::ErrorKind::BrokenPipe,
+                        "the Parquet writer stopped",
+                    ))
+                })
+            },
+        );
+        let writer = writing
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+        let (schema, summary) = converted?;
+        writer.close(schema)?;
+        Ok(summary)
+    })
+}
+
+/// A Parquet writer that is opened with the schema of its first batch.
+#[cfg(feature = "fast_local")]
+enum LazyWriter<W: std::io::Write + Send> {
+    Unopened(W, WriterProperties),
+    Open(parquet::arrow::ArrowWriter<W>),
+}
+
+#[cfg(feature = "fast_local")]
+impl<W: std::io::Write + Send> LazyWriter<W> {
+    fn open(self, schema: SchemaRef) -> CsvResult<parquet::arrow::ArrowWriter<W>> {
+        Ok(match self {
+            Self::Unopened(writer, properties) => {
+                parquet::arrow::ArrowWriter::try_new(writer, schema, Some(properties))?
+            }
+            Self::Open(writer) => writer,
+        })
+    }
+
+    fn write(self, batch: &RecordBatch) -> CsvResult<Self> {
+        let mut writer = self.open(batch.schema())?;
+        writer.write(batch)?;
+        Ok(Self::Open(writer))
+    }
+
+    /// Finish the file, opening it with `schema` if nothing was written.
+    fn close(self, schema: SchemaRef) -> CsvResult<()> {
+        self.open(schema)?.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvOptions;
+    use arrow::array::{Array, Int64Array};
+    use arrow::record_batch::RecordBatch;
+    use bytes::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::file::metadata::ParquetMetaData;
+    use std::io::Cursor;
+
+    fn read_back(file: Vec<u8>) -> (ParquetMetaData, Vec<RecordBatch>) {
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file)).expect("read footer");
+        let metadata = builder.metadata().as_ref().clone();
+        let batches = builder
+            .build()
+            .expect("build reader")
+            .collect::<Result<_, _>>()
+            .expect("read batches");
+        (metadata, batches)
+    }
+
+    fn ids(batches: &[RecordBatch]) -> Vec<Option<i64>> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let ids = batch.column(0).as_any().downcast_ref::<Int64Array>();
+                ids.map(|ids| ids.iter().collect::<Vec<_>>())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn row_groups_and_compression_follow_the_options() {
+        let mut input = "id,name\n".to_string();
+        for id in 0..10 {
+            input.push_str(&format!("{id},n{id}\n"));
+        }
+        let options = CsvParquetOptions {
+            arrow: CsvArrowOptions {
+                batch_size: 3,
+                ..CsvArrowOptions::default()
+            },
+            row_group_size: 4,
+            compression: CsvParquetCompression::Zstd,
+        };
+        let mut file = Vec::new();
+        let summary = CsvParser::from_reader(Cursor::new(input), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .write_parquet(&mut file, &options)
+            .await
+            .expect("write parquet");
+
+        assert_eq!(summary.row_count, 10);
+        let (metadata, batches) = read_back(file);
+        let row_groups: Vec<_> = metadata
+            .row_groups()
+            .iter()
+            .map(|group| group.num_rows())
+            .collect();
+        assert_eq!(row_groups, [4, 4, 2]);
+        assert_eq!(
+            metadata.row_group(0).column(0).compression(),
+            Compression::ZSTD(ZstdLevel::default())
+        );
+        assert_eq!(ids(&batches), (0..10).map(Some).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn null_tokens_are_written_as_nulls() {
+        let options = CsvParquetOptions {
+            arrow: CsvArrowOptions {
+                null_tokens: vec!["NULL".to_string()],
+                ..CsvArrowOptions::default()
+            },
+            ..CsvParquetOptions::default()
+        };
+        let mut file = Vec::new();
+        CsvParser::from_reader(
+            Cursor::new("id\n1\nNULL\n\n3\n"),
+            &[],
+            &CsvOptions::default(),
+        )
+        .await
+        .expect("construct parser")
+        .write_parquet(&mut file, &options)
+        .await
+        .expect("write parquet");
+
+        let (metadata, batches) = read_back(file);
+        assert_eq!(
+            metadata.row_group(0).column(0).compression(),
+            Compression::SNAPPY
+        );
+        assert_eq!(ids(&batches), [Some(1), None, Some(3)]);
+    }
+
+    #[tokio::test]
+    async fn invalid_options_fail_before_reading() {
+        let options = CsvParquetOptions {
+            row_group_size: 0,
+            ..CsvParquetOptions::default()
+        };
+        let error = CsvParser::from_reader(Cursor::new("id\n1\n"), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .write_parquet(Vec::new(), &options)
+            .await
+            .expect_err("empty row groups are invalid");
+        assert!(matches!(error, CsvIngestError::UnsupportedDialect(_)));
+    }
+
+    #[cfg(feature = "fast_local")]
+    #[test]
+    fn fast_local_output_matches_the_streaming_writer() {
+        use std::io::Write;
+
+        let mut input = "id,qty\n".to_string();
+        for id in 0..500 {
+            input.push_str(&format!("{id},{}\n", id % 9));
+        }
+        let mut fixture = tempfile::NamedTempFile::new().expect("create fixture");
+        fixture.write_all(input.as_bytes()).expect("write fixture");
+        let options = CsvParquetOptions {
+            row_group_size: 128,
+            ..CsvParquetOptions::default()
+        };
+
+        let windowed = crate::FastLocalConfig {
+            workers: std::num::NonZeroUsize::new(3),
+            min_chunk_size: 64,
+            window_size: std::num::NonZeroUsize::new(512),
+            ..crate::FastLocalConfig::default()
+        };
+        for config in [crate::FastLocalConfig::default(), windowed] {
+            let mut file = Vec::new();
+            let summary = fast_local_write_parquet(
+                fixture.path(),
+                &["id"],
+                &CsvOptions::default(),
+                None,
+                &options,
+                config,
+                &mut file,
+            )
+            .expect("write parquet");
+
+            assert_eq!(summary.row_count, 500);
+            let (metadata, batches) = read_back(file);
+            assert_eq!(metadata.num_row_groups(), 4);
+            assert_eq!(ids(&batches), (0..500).map(Some).collect::<Vec<_>>());
+        }
+    }
+
+    #[cfg(feature = "fast_local")]
+    #[test]
+    fn fast_local_writes_a_schema_for_headers_alone() {
+        use std::io::Write;
+
+        let mut fixture = tempfile::NamedTempFile::new().expect("create fixture");
+        fixture.write_all(b"id,qty\n").expect("write fixture");
+
+        let mut file = Vec::new();
+        let summary = fast_local_write_parquet(
+            fixture.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            &CsvParquetOptions::default(),
+            crate::FastLocalConfig::default(),
+            &mut file,
+        )
+        .expect("write parquet");
+
+        assert_eq!(summary.row_count, 0);
+        let (metadata, batches) = read_back(file);
+        let fields = metadata.file_metadata().schema_descr().columns().len();
+        assert_eq!(fields, 2);
+        assert!(batches.is_empty());
+    }
+
+    #[cfg(feature = "fast_local")]
+    #[test]
+    fn fast_local_reports_the_writer_error() {
+        use std::io::Write;
+
+        struct Failing;
+
+        impl Write for Failing {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut input = "id\n".to_string();
+        for id in 0..2000 {
+            input.push_str(&format!("{id}\n"));
+        }
+        let mut fixture = tempfile::NamedTempFile::new().expect("create fixture");
+        fixture.write_all(input.as_bytes()).expect("write fixture");
+        let config = crate::FastLocalConfig {
+            workers: std::num::NonZeroUsize::new(2),
+            min_chunk_size: 64,
+            window_size: std::num::NonZeroUsize::new(256),
+            ..crate::FastLocalConfig::default()
+        };
+
+        let error = fast_local_write_parquet(
+            fixture.path(),
+            &[],
+            &CsvOptions::default(),
+            None,
+            &CsvParquetOptions::default(),
+            config,
+            Failing,
+        )
+        .expect_err("the writer fails");
+        assert!(error.to_string().contains("disk full"), "{error}");
+    }
+}
@@ -1,6 +1,12 @@
+use crate::filter::CompiledFilters;
+use crate::inference::{Reservoir, SchemaInference};
+use crate::projection::CompiledProjection;
+use crate::quarantine::{trim_record, TapReader};
+use crate::schema::CompiledSchema;
 use crate::{
-    reader_from_path, BoxedCsvReader, CsvHeaderMode, CsvIngestError, CsvIngestSummary, CsvMeta,
-    CsvOptions, CsvResult,
+    reader_from_path, BoxedCsvReader, CsvColumns, CsvDiagnostic, CsvFilter, CsvHeaderMode,
+    CsvInferredSchema, CsvIngestError, CsvIngestSummary, CsvMeta, CsvOptions, CsvProjection,
+    CsvQuarantine, CsvRecord, CsvResult, CsvSampling, CsvSchema, Position,
 };
 use csv_async::{AsyncReader, AsyncReaderBuilder};
 use std::collections::HashMap;
@@ -12,14 +18,37 @@ use tokio::io::AsyncRead;
 /// The parser resolves headers and required columns during construction. Use
 /// [`CsvParser::next_record`] to reuse parser-owned storage or
 /// [`CsvParser::read_record`] to reuse a caller-owned [`crate::ByteRecord`].
+/// Every record read carries its [`Position`] in the source. Rows rejected by
+/// a skipping [`crate::CsvErrorPolicy`] are not returned; see [`CsvParser::diagnostics`]
+/// and [`CsvParser::with_quarantine`].
 pub struct CsvParser<R> {
-    reader: AsyncReader<R>,
+    reader: AsyncReader<TapReader<R>>,
     headers: crate::ByteRecord,
+    /// The full header record, once a projection replaces `headers`.
+    source_headers: Option<crate::ByteRecord>,
     header_indices: HashMap<Vec<u8>, usize>,
+    validator: RecordValidator,
+    record: crate::ByteRecord,
+}
+
+/// Per-record validation state, kept apart from the reader so records can be
+/// read into parser-owned storage.
+struct RecordValidator {
     required_indices: Vec<usize>,
     required_headers: Vec<String>,
-    record: crate::ByteRecord,
+    options: CsvOptions,
+    quarantine: Option<Box<dyn CsvQuarantine + Send>>,
+    schema: Option<CompiledSchema>,
+    filters: CompiledFilters,
+    projection: Option<CompiledProjection>,
+    /// Holds the full record while it is projected into the caller's storage.
+    scratch: crate::ByteRecord,
+    source_name: Option<String>,
     records_read: u64,
+    rows_scanned: u64,
+    filtered_rows: u64,
+    rejected_rows: u64,
+    diagnostics: Vec<CsvDiagnostic>,
 }
 
 impl<R> CsvParser<R>
@@ -35,16 +64,12 @@ where
         required_headers: &[&str],
         options: &CsvOptions,
     ) -> CsvResult<Self> {
-        if options.headers == CsvHeaderMode::Absent && !required_headers.is_empty() {
-            return Err(CsvIngestError::UnsupportedDialect(
-                "required headers cannot be validated when headers are absent".to_string(),
-            ));
-        }
+        check_required_headers(required_headers, options)?;
 
         let mut builder = AsyncReaderBuilder::new();
         options.configure_reader(&mut builder)?;
         builder.buffer_capacity(1 << 20);
-        let mut reader = builder.create_reader(reader);
+        let mut reader = builder.create_reader(TapReader::new(reader));
 
         let headers = if options.headers == CsvHeaderMode::Present {
             reader.byte_headers().await?.clone()
@@ -68,18 +93,140 @@ where
         Ok(Self {
             reader,
             headers,
+            source_headers: None,
             header_indices,
-            required_indices,
-            required_headers: required_headers
-                .iter()
-                .map(|header| (*header).to_string())
-                .collect(),
+            validator: RecordValidator {
+                required_indices,
+                required_headers: required_headers
+                    .iter()
+                    .map(|header| (*header).to_string())
+                    .collect(),
+                options: *options,
+                quarantine: None,
+                schema: None,
+                filters: CompiledFilters::default(),
+                projection: None,
+                scratch: crate::ByteRecord::new(),
+                source_name: None,
+                records_read: 0,
+                rows_scanned: 0,
+                filtered_rows: 0,
+                rejected_rows: 0,
+                diagnostics: Vec::new(),
+            },
             record: crate::ByteRecord::new(),
-            records_read: 0,
         })
     }
 
+    /// Name the input in record-level errors, usually with `CsvMeta::name_hint`.
+    ///
+    /// [`CsvParser::from_path`] sets this from the file name automatically.
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        self.validator.source_name = Some(name.into());
+        self
+    }
+
+    /// Send the original bytes of every row rejected by the error policy to `sink`.
+    ///
+    /// Install the sink before reading records; rows read earlier are not
+    /// quarantined and their raw bytes are no longer available.
+    pub fn with_quarantine(mut self, sink: impl CsvQuarantine + Send + 'static) -> Self {
+        self.validator.quarantine = Some(Box::new(sink));
+        self
+    }
+
+    /// Validate every record against `schema`.
+    ///
+    /// Schema columns become required headers: an absent header returns
+    /// [`CsvIngestError::MissingHeader`]. A field that breaks a rule returns
+    /// [`CsvIngestError::SchemaViolation`], which a skipping
+    /// [`crate::CsvErrorPolicy`] rejects like other record-level errors.
+    pub fn with_schema(mut self, schema: &CsvSchema) -> CsvResult<Self> {
+        if self.validator.options.headers == CsvHeaderMode::Absent && !schema.columns.is_empty() {
+            return Err(CsvIngestError::UnsupportedDialect(
+                "schema columns cannot be resolved when headers are absent".to_string(),
+            ));
+        }
+        let headers: Vec<&[u8]> = self.source_headers().iter().collect();
+        let schema = CompiledSchema::new(schema, &headers)?;
+        for column in schema.columns() {
+            self.validator.require(column.index, &column.name);
+        }
+        self.validator.schema = Some(schema);
+        Ok(self)
+    }
+
+    /// Skip rows that `filter` rejects.
+    ///
+    /// Filters are checked after required fields and before schema rules, and
+    /// a row is kept only if every filter accepts it. Filtered rows are not
+    /// returned or counted by [`CsvParser::records_read`]; see
+    /// [`CsvParser::filtered_rows`]. The filter's column is resolved against the
+    /// full record, even after a projection, and becomes a required header.
+    pub fn with_filter(mut self, filter: CsvFilter) -> CsvResult<Self> {
+        let headers: Vec<&[u8]> = self.source_headers().iter().collect();
+        let present = self.validator.options.headers == CsvHeaderMode::Present;
+        let filters = CompiledFilters::new(&[filter], present.then_some(&headers[..]))?;
+        for filter in filters.filters() {
+            self.validator.require(filter.index, &filter.name);
+        }
+        self.validator.filters.extend(filters);
+        Ok(self)
+    }
+
+    /// Return only the columns selected by `projection`, in selection order.
+    ///
+    /// Records, [`CsvParser::headers`], views, and deserialization all see the
+    /// projected columns. Selected columns become required headers, while
+    /// required headers and schemas still validate the full record. Headers
+    /// cannot be selected by name when headers are absent. Set the projection
+    /// before resolving views or reading records.
+    pub fn with_projection(mut self, projection: &CsvProjection) -> CsvResult<Self> {
+        let source_headers = self
+            .source_headers
+            .take()
+            .unwrap_or_else(|| std::mem::take(&mut self.headers));
+        let headers: Vec<&[u8]> = source_headers.iter().collect();
+        let present = self.validator.options.headers == CsvHeaderMode::Present;
+        let projection = CompiledProjection::new(projection, present.then_some(&headers[..]))?;
+        for (index, name) in projection.columns() {
+            self.validator.require(*index, name);
+        }
+        self.headers = if present {
+            projection
+                .columns()
+                .iter()
+                .map(|(index, _)| &source_headers[*index])
+                .collect()
+        } else {
+            crate::ByteRecord::new()
+        };
+        self.header_indices.clear();
+        for (index, header) in self.headers.iter().enumerate() {
+            self.header_indices.entry(header.to_vec()).or_insert(index);
+        }
+        self.source_headers = Some(source_headers);
+        self.validator.projection = Some(projection);
+        Ok(self)
+    }
+
+    /// Return the header record before any projection.
+    fn source_headers(&self) -> &crate::ByteRecord {
+        self.source_headers.as_ref().unwrap_or(&self.headers)
+    }
+
+    /// Return the source name attached to record-level errors, if any.
+    pub fn source_name(&self) -> Option<&str> {
+        self.validator.source_name.as_deref()
+    }
+
+    pub(crate) fn options(&self) -> &CsvOptions {
+        &self.validator.options
+    }
+
     /// Return the header record, or an empty record in headerless mode.
+    ///
+    /// After [`CsvParser::with_projection`], only the projected headers are returned.
     pub fn headers(&self) -> &crate::ByteRecord {
         &self.headers
     }
@@ -89,47 +236,245 @@ where
         self.header_indices.get(header.as_ref()).copied()
     }
 
-    /// Return the required-column indices resolved during construction.
+    /// Return the required-column indices, counted in the full record.
     pub fn required_indices(&self) -> &[usize] {
-        &self.required_indices
+        &self.validator.required_indices
     }
 
-    /// Return the number of data records read so far.
+    /// Return the number of data records read so far, excluding rejected rows.
     pub fn records_read(&self) -> u64 {
-        self.records_read
+        self.validator.records_read
+    }
+
+    /// Return the number of data rows read so far, including filtered and rejected rows.
+    pub fn rows_scanned(&self) -> u64 {
+        self.validator.rows_scanned
+    }
+
+    /// Return the number of rows skipped by filters so far.
+    pub fn filtered_rows(&self) -> u64 {
+        self.validator.filtered_rows
+    }
+
+    /// Return the number of rows rejected by the error policy so far.
+    pub fn rejected_rows(&self) -> u64 {
+        self.validator.rejected_rows
+    }
+
+    /// Return a diagnostic for each row rejected by the error policy so far,
+    /// up to [`CsvOptions::max_diagnostics`].
+    pub fn diagnostics(&self) -> &[CsvDiagnostic] {
+        &self.validator.diagnostics
+    }
+
+    /// Return the reader position, which is where the next record starts.
+    ///
+    /// The position of a record that was already read is available from
+    /// [`crate::ByteRecord::position`].
+    pub fn position(&self) -> &Position {
+        self.reader.position()
     }
 
     /// Read the next record into parser-owned reusable storage.
     ///
     /// The returned reference remains valid until the parser is mutably used
-    /// again. Fields are available as byte slices through `record.get(index)`.
+    /// again. Fields are available as byte slices through `record.get(index)`
+    /// and the record's byte offset, line, and index through `record.position()`.
     pub async fn next_record(&mut self) -> CsvResult<Option<&crate::ByteRecord>> {
-        let has_record = read_validated_record(
-            &mut self.reader,
-            &mut self.record,
-            &self.required_indices,
-            &self.required_headers,
-            &mut self.records_read,
-        )
-        .await?;
+        let has_record = self
+            .validator
+            .read(&mut self.reader, &mut self.record)
+            .await?;
         Ok(has_record.then_some(&self.record))
     }
 
     /// Read the next record into caller-owned reusable storage.
     pub async fn read_record(&mut self, record: &mut crate::ByteRecord) -> CsvResult<bool> {
-        read_validated_record(
-            &mut self.reader,
-            record,
-            &self.required_indices,
-            &self.required_headers,
-            &mut self.records_read,
-        )
-        .await
+        self.validator.read(&mut self.reader, record).await
+    }
+
+    /// Infer each column's type, nullability, and maximum width from records
+    /// read through this parser.
+    ///
+    /// Sampled records are consumed, and are validated and counted as usual.
+    /// Convert the result with [`CsvInferredSchema::to_schema`] to enforce it.
+    pub async fn infer_schema(&mut self, sampling: CsvSampling) -> CsvResult<CsvInferredSchema> {
+        if self.validator.options.headers == CsvHeaderMode::Absent {
+            return Err(CsvIngestError::UnsupportedDialect(
+                "schemas cannot be inferred when headers are absent".to_string(),
+            ));
+        }
+        let mut inference = SchemaInference::new(self.headers.iter());
+        let mut rows_read = 0u64;
+        match sampling {
+            CsvSampling::First(limit) => {
+                while rows_read < limit
+                    && self
+                        .validator
+                        .read(&mut self.reader, &mut self.record)
+                        .await?
+                {
+                    rows_read += 1;
+                    inference.observe(&self.record);
+                }
+            }
+            CsvSampling::Reservoir { size, seed } => {
+                let mut reservoir = Reservoir::new(size, seed);
+                while self
+                    .validator
+                    .read(&mut self.reader, &mut self.record)
+                    .await?
+                {
+                    rows_read += 1;
+                    reservoir.offer(&self.record);
+                }
+                for record in reservoir.into_sample() {
+                    inference.observe(&record);
+                }
+            }
+        }
+        Ok(inference.finish(rows_read))
+    }
+
+    /// Resolve the columns of a [`CsvRecord`] view against [`CsvParser::headers`].
+    ///
+    /// The view's required fields become required headers of this parser:
+    /// an absent header returns [`CsvIngestError::MissingHeader`], and records
+    /// read afterwards are validated like those passed to
+    /// [`CsvParser::from_reader`]. Resolve columns once, before reading records.
+    pub fn resolve_columns<'r, T: CsvRecord<'r>>(&mut self) -> CsvResult<CsvColumns> {
+        let mut columns = CsvColumns::resolve(T::FIELDS, &self.headers)?;
+        columns.header_records = u64::from(self.options().headers == CsvHeaderMode::Present);
+        for (field, descriptor) in T::FIELDS.iter().enumerate() {
+            let Some(index) = columns.index(field) else {
+                continue;
+            };
+            if descriptor.required {
+                let index = self
+                    .validator
+                    .projection
+                    .as_ref()
+                    .and_then(|projection| projection.source_index(index))
+                    .unwrap_or(index);
+                self.validator.require(index, descriptor.header);
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Read the next record and return a `T` view borrowing its fields.
+    ///
+    /// `columns` must come from [`CsvParser::resolve_columns`] for `T`. The
+    /// view remains valid until the parser is mutably used again.
+    pub async fn next_view<'r, T: CsvRecord<'r>>(
+        &'r mut self,
+        columns: &CsvColumns,
+    ) -> CsvResult<Option<T>> {
+        if !self
+            .validator
+            .read(&mut self.reader, &mut self.record)
+            .await?
+        {
+            return Ok(None);
+        }
+
+        let parser: &'r Self = self;
+        T::from_record(columns, &parser.record)
+            .map(Some)
+            .map_err(|error| parser.view_error(error))
+    }
+
+    /// Number a view error with the parser's row count and attach the source name.
+    fn view_error(&self, mut error: CsvIngestError) -> CsvIngestError {
+        if let CsvIngestError::MissingRequiredField { row, .. }
+        | CsvIngestError::InvalidValue { row, .. } = &mut error
+        {
+            *row = self.validator.rows_scanned;
+        }
+        error.with_source_name(self.validator.source_name.as_deref())
+    }
+
+    /// Read the next record and deserialize it into an owned `T`.
+    ///
+    /// Struct fields are matched to columns by header name, using the headers
+    /// resolved during construction. In headerless mode fields are matched by
+    /// position. Failures are returned as [`CsvIngestError::Deserialize`].
+    #[cfg(feature = "serde")]
+    pub async fn deserialize<T: serde::de::DeserializeOwned>(&mut self) -> CsvResult<Option<T>> {
+        self.deserialize_ref().await
+    }
+
+    /// Read the next record and deserialize it into a `T` that may borrow
+    /// from parser-owned storage, such as `&str` or `&[u8]` fields.
+    ///
+    /// The value remains valid until the parser is mutably used again.
+    #[cfg(feature = "serde")]
+    pub async fn deserialize_ref<'de, T: serde::Deserialize<'de>>(
+        &'de mut self,
+    ) -> CsvResult<Option<T>> {
+        if !self
+            .validator
+            .read(&mut self.reader, &mut self.record)
+            .await?
+        {
+            return Ok(None);
+        }
+
+        let parser: &'de Self = self;
+        let headers =
+            (parser.validator.options.headers == CsvHeaderMode::Present).then_some(&parser.headers);
+        parser
+            .record
+            .deserialize(headers)
+            .map(Some)
+            .map_err(|error| parser.deserialize_error(error))
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize_error(&self, error: csv_async::Error) -> CsvIngestError {
+        let csv_async::ErrorKind::Deserialize { err, .. } = error.kind() else {
+            return error.into();
+        };
+        let column = err
+            .field()
+            .and_then(|field| self.headers.get(usize::try_from(field).ok()?))
+            .map(|header| String::from_utf8_lossy(header).into_owned());
+        CsvIngestError::Deserialize {
+            row: self.validator.rows_scanned,
+            field: err.field(),
+            column,
+            source: Box::new(err.clone()),
+            position: self.record.position().cloned(),
+            source_name: self.validator.source_name.clone(),
+        }
+    }
+
+    /// Read the remaining records and summarize the whole parse.
+    ///
+    /// Counts include records read before this call.
+    pub async fn into_summary(mut self) -> CsvResult<CsvIngestSummary> {
+        let headers = self
+            .headers()
+            .iter()
+            .map(std::str::from_utf8)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        while self.next_record().await?.is_some() {}
+        Ok(CsvIngestSummary {
+            row_count: self.records_read(),
+            headers,
+            scanned_rows: self.rows_scanned(),
+            filtered_rows: self.filtered_rows(),
+            rejected_rows: self.rejected_rows(),
+            diagnostics: std::mem::take(&mut self.validator.diagnostics),
+        })
     }
 
     /// Unwrap the parser and return its underlying reader.
     pub fn into_inner(self) -> R {
-        self.reader.into_inner()
+        self.reader.into_inner().into_inner()
     }
 }
 
@@ -141,66 +486,192 @@ impl CsvParser<BoxedCsvReader<'static>> {
         options: &CsvOptions,
     ) -> CsvResult<(Self, CsvMeta)> {
         let (reader, meta) = reader_from_path(path).await?;
-        let parser = Self::from_reader(reader, required_headers, options).await?;
+        let parser = Self::from_reader(reader, required_headers, options)
+            .await
+            .map_err(|error| error.with_source_name(Some(&meta.name_hint)))?
+            .with_source_name(meta.name_hint.clone());
         Ok((parser, meta))
     }
 }
 
-async fn read_validated_record<R>(
-    reader: &mut AsyncReader<R>,
-    record: &mut crate::ByteRecord,
-    required_indices: &[usize],
-    required_headers: &[String],
-    records_read: &mut u64,
-) -> CsvResult<bool>
-where
-    R: AsyncRead + Unpin + Send,
-{
-    let has_record = reader.read_byte_record(record).await.map_err(|error| {
-        let error = CsvIngestError::from(error);
-        match error {
-            CsvIngestError::RaggedRow {
-                expected, actual, ..
-            } => CsvIngestError::RaggedRow {
-                row: Some(*records_read + 1),
-                expected,
-                actual,
-            },
-            error => error,
+impl RecordValidator {
+    /// Read the next accepted record, projected when a projection is set.
+    async fn read<R>(
+        &mut self,
+        reader: &mut AsyncReader<TapReader<R>>,
+        record: &mut crate::ByteRecord,
+    ) -> CsvResult<bool>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        if self.projection.is_none() {
+            return self.read_accepted(reader, record).await;
+        }
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let has_record = self.read_accepted(reader, &mut scratch).await;
+        if let (Ok(true), Some(projection)) = (&has_record, &self.projection) {
+            projection.project(&scratch, record);
         }
-    })?;
-    if !has_record {
-        return Ok(false);
+        self.scratch = scratch;
+        has_record
     }
-    *records_read += 1;
-    for (&index, header) in required_indices.iter().zip(required_headers) {
-        if record.get(index).is_none() {
-            return Err(CsvIngestError::MissingRequiredField {
-                row: *records_read,
-                header: header.clone(),
-            });
+
+    /// Read the next accepted record, skipping rows the error policy rejects.
+    async fn read_accepted<R>(
+        &mut self,
+        reader: &mut AsyncReader<TapReader<R>>,
+        record: &mut crate::ByteRecord,
+    ) -> CsvResult<bool>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let policy = self.options.error_policy;
+        if self.quarantine.is_none() {
+            reader.get_mut().stop_recording();
+        }
+        loop {
+            let start = reader.position().byte();
+            match self.read_validated(reader, record).await {
+                Ok(Validated::Accepted) => {
+                    if self.quarantine.is_some() {
+                        let end = reader.position().byte();
+                        reader.get_mut().discard_before(end);
+                    }
+                    self.records_read += 1;
+                    return Ok(true);
+                }
+                Ok(Validated::Filtered) => {
+                    if self.quarantine.is_some() {
+                        let end = reader.position().byte();
+                        reader.get_mut().discard_before(end);
+                    }
+                    self.filtered_rows += 1;
+                }
+                Ok(Validated::Finished) => {
+                    if let Some(quarantine) = &mut self.quarantine {
+                        quarantine.finish()?;
+                    }
+                    policy.check_finished(self.rejected_rows, self.rows_scanned)?;
+                    return Ok(false);
+                }
+                Err(error) if policy.skips() && error.is_record_error() => {
+                    self.rejected_rows += 1;
+                    if let Some(quarantine) = &mut self.quarantine {
+                        let end = reader.position().byte();
+                        let raw = reader.get_ref().slice(start, end);
+                        let raw = if start == 0 {
+                            raw.strip_prefix(b"\xef\xbb\xbf").unwrap_or(raw)
+                        } else {
+                            raw
+                        };
+                        let raw = trim_record(raw, |byte| self.options.is_terminator(byte));
+                        quarantine.quarantine(raw, error.position(), &error)?;
+                        reader.get_mut().discard_before(end);
+                    }
+                    if self.diagnostics.len() < self.options.max_diagnostics {
+                        self.diagnostics.push(CsvDiagnostic::new(&error));
+                    }
+                    policy.check_count(self.rejected_rows, self.rows_scanned)?;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    async fn read_validated<R>(
+        &mut self,
+        reader: &mut AsyncReader<TapReader<R>>,
+        record: &mut crate::ByteRecord,
+    ) -> CsvResult<Validated>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let has_record = match reader.read_byte_record(record).await {
+            Ok(has_record) => has_record,
+            Err(error) => {
+                return Err(match CsvIngestError::from(error) {
+                    CsvIngestError::RaggedRow {
+                        expected,
+                        actual,
+                        position,
+                        ..
+                    } => {
+                        self.rows_scanned += 1;
+                        CsvIngestError::RaggedRow {
+                            row: Some(self.rows_scanned),
+                            expected,
+                            actual,
+                            position,
+                            source_name: self.source_name.clone(),
+                        }
+                    }
+                    error => error,
+                });
+            }
+        };
+        if !has_record {
+            return Ok(Validated::Finished);
+        }
+        self.rows_scanned += 1;
+        for (&index, header) in self.required_indices.iter().zip(&self.required_headers) {
+            if record.get(index).is_none() {
+                return Err(CsvIngestError::MissingRequiredField {
+                    row: self.rows_scanned,
+                    header: header.clone(),
+                    position: record.position().cloned(),
+                    source_name: self.source_name.clone(),
+                });
+            }
+        }
+        if !self.filters.accepts(|index| record.get(index)) {
+            return Ok(Validated::Filtered);
+        }
+        if let Some(schema) = &self.schema {
+            for column in schema.columns() {
+                let value = record.get(column.index).unwrap_or_default();
+                column
+                    .validate(value, self.rows_scanned)
+                    .map_err(|error| self.locate(error, record))?;
+            }
+        }
+        Ok(Validated::Accepted)
+    }
+
+    /// Attach the record's position and the source name to a record-level error.
+    fn locate(&self, mut error: CsvIngestError, record: &crate::ByteRecord) -> CsvIngestError {
+        if let CsvIngestError::SchemaViolation { position, .. } = &mut error {
+            *position = record.position().cloned();
+        }
+        error.with_source_name(self.source_name.as_deref())
+    }
+
+    /// Require the column at `index`, unless it is already required.
+    fn require(&mut self, index: usize, header: &str) {
+        if !self.required_indices.contains(&index) {
+            self.required_indices.push(index);
+            self.required_headers.push(header.to_string());
         }
     }
-    Ok(true)
 }
 
-async fn finish_summary<R>(mut parser: CsvParser<R>) -> CsvResult<CsvIngestSummary>
-where
-    R: AsyncRead + Unpin + Send,
-{
-    let headers = parser
-        .headers()
-        .iter()
-        .map(std::str::from_utf8)
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .map(str::to_string)
-        .collect();
-    while parser.next_record().await?.is_some() {}
-    Ok(CsvIngestSummary {
-        row_count: parser.records_read(),
-        headers,
-    })
+/// Reject required headers that a headerless dialect cannot resolve.
+pub(crate) fn check_required_headers(
+    required_headers: &[&str],
+    options: &CsvOptions,
+) -> CsvResult<()> {
+    if options.headers == CsvHeaderMode::Absent && !required_headers.is_empty() {
+        return Err(CsvIngestError::UnsupportedDialect(
+            "required headers cannot be validated when headers are absent".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The outcome of reading one row that the error policy did not reject.
+enum Validated {
+    Accepted,
+    Filtered,
+    Finished,
 }
 
 /// Summarize a CSV stream using the record-oriented parser contract.
@@ -212,7 +683,10 @@ pub async fn summarize_csv_stream<R>(
 where
     R: AsyncRead + Unpin + Send,
 {
-    finish_summary(CsvParser::from_reader(reader, required_headers, options).await?).await
+    CsvParser::from_reader(reader, required_headers, options)
+        .await?
+        .into_summary()
+        .await
 }
 
 /// Summarize a local CSV path and return the transport metadata used.
@@ -222,12 +696,13 @@ pub async fn summarize_csv_path(
     options: &CsvOptions,
 ) -> CsvResult<(CsvIngestSummary, CsvMeta)> {
     let (parser, meta) = CsvParser::from_path(path, required_headers, options).await?;
-    Ok((finish_summary(parser).await?, meta))
+    Ok((parser.into_summary().await?, meta))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::CsvErrorPolicy;
     use std::io::Cursor;
 
     #[tokio::test]
@@ -337,7 +812,191 @@ mod tests {
 
         assert!(matches!(
             error,
-            CsvIngestError::MissingRequiredField { row: 1, header } if header == "value"
+            CsvIngestError::MissingRequiredField { row: 1, header, .. } if header == "value"
+        ));
+    }
+
+    #[tokio::test]
+    async fn records_and_errors_carry_csv_positions() {
+        let options = CsvOptions {
+            flexible: true,
+            ..CsvOptions::default()
+        };
+        let mut parser = CsvParser::from_reader(
+            Cursor::new(b"sku,value\nA,\"multi\nline\"\nB\n"),
+            &["value"],
+            &options,
+        )
+        .await
+        .expect("construct parser")
+        .with_source_name("rows.csv");
+
+        let record = parser
+            .next_record()
+            .await
+            .expect("read first")
+            .expect("first record");
+        let position = record.position().expect("record position");
+        assert_eq!(
+            (position.byte(), position.line(), position.record()),
+            (10, 2, 1)
+        );
+        assert_eq!(parser.position().line(), 4);
+
+        let error = parser
+            .next_record()
+            .await
+            .expect_err("missing field must fail");
+        let position = error.position().expect("error position");
+        assert_eq!(
+            (position.byte(), position.line(), position.record()),
+            (25, 4, 2)
+        );
+        assert_eq!(error.source_name(), Some("rows.csv"));
+        assert_eq!(parser.source_name(), Some("rows.csv"));
+    }
+
+    #[tokio::test]
+    async fn ragged_row_errors_use_the_record_start_position() {
+        let mut parser = CsvParser::from_reader(
+            Cursor::new(b"sku,value\n\"a\nb\",1\nC\n"),
+            &[],
+            &CsvOptions::default(),
+        )
+        .await
+        .expect("construct parser");
+
+        parser.next_record().await.expect("read first");
+        let error = parser
+            .next_record()
+            .await
+            .expect_err("ragged row must fail");
+
+        assert!(matches!(
+            &error,
+            CsvIngestError::RaggedRow {
+                row: Some(2),
+                source_name: None,
+                ..
+            }
+        ));
+        let position = error.position().expect("ragged position");
+        assert_eq!((position.byte(), position.line()), (18, 4));
+    }
+
+    #[tokio::test]
+    async fn skipping_policies_reject_rows_and_keep_reading() {
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let mut parser = CsvParser::from_reader(
+            Cursor::new(b"sku,value\nA,1\nB\nC,3\nD,4,extra\nE,5\n"),
+            &["sku"],
+            &options,
+        )
+        .await
+        .expect("construct parser");
+        let mut skus = Vec::new();
+        while let Some(record) = parser.next_record().await.expect("read record") {
+            skus.push(record.get(0).expect("sku").to_vec());
+        }
+
+        assert_eq!(skus, [b"A", b"C", b"E"]);
+        assert_eq!(parser.records_read(), 3);
+        assert_eq!(parser.rejected_rows(), 2);
+        let rows: Vec<_> = parser.diagnostics().iter().map(|d| d.row).collect();
+        assert_eq!(rows, [2, 4]);
+        assert_eq!(
+            parser.diagnostics()[0]
+                .position
+                .as_ref()
+                .map(Position::line),
+            Some(3)
+        );
+        assert!(parser.diagnostics()[1].message.contains("got 3 fields"));
+    }
+
+    #[tokio::test]
+    async fn diagnostics_stop_at_the_cap_while_rejections_are_counted() {
+        let mut input = "sku,value\n".to_string();
+        for row in 0..50 {
+            input.push_str(if row % 2 == 0 { "A\n" } else { "B,2\n" });
+        }
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            max_diagnostics: 3,
+            ..CsvOptions::default()
+        };
+
+        let summary = summarize_csv_stream(Cursor::new(input), &["sku"], &options)
+            .await
+            .expect("summarize with skipped rows");
+
+        assert_eq!(summary.row_count, 25);
+        assert_eq!(summary.rejected_rows, 25);
+        let rows: Vec<_> = summary.diagnostics.iter().map(|d| d.row).collect();
+        assert_eq!(rows, [1, 3, 5]);
+    }
+
+    #[tokio::test]
+    async fn skipped_missing_fields_keep_scanned_row_numbers() {
+        let options = CsvOptions {
+            flexible: true,
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let summary =
+            summarize_csv_stream(Cursor::new(b"sku,value\nA\nB,2\nC\n"), &["value"], &options)
+                .await
+                .expect("summarize with skipped rows");
+
+        assert_eq!(summary.row_count, 1);
+        assert_eq!(summary.rejected_rows, 2);
+        let rows: Vec<_> = summary.diagnostics.iter().map(|d| d.row).collect();
+        assert_eq!(rows, [1, 3]);
+    }
+
+    #[tokio::test]
+    async fn error_budgets_stop_the_parse_when_exceeded() {
+        let contents = b"sku,value\nA\nB,2\nC\nD,4\n";
+        let count = CsvOptions {
+            error_policy: CsvErrorPolicy::SkipUpTo(1),
+            ..CsvOptions::default()
+        };
+        let error = summarize_csv_stream(Cursor::new(contents), &[], &count)
+            .await
+            .expect_err("second rejected row exceeds the budget");
+        assert!(matches!(
+            error,
+            CsvIngestError::ErrorBudgetExceeded {
+                rejected: 2,
+                scanned: 3
+            }
+        ));
+
+        let generous = CsvOptions {
+            error_policy: CsvErrorPolicy::SkipUpToPercent(50),
+            ..CsvOptions::default()
+        };
+        let summary = summarize_csv_stream(Cursor::new(contents), &[], &generous)
+            .await
+            .expect("half of the rows may be rejected");
+        assert_eq!((summary.row_count, summary.rejected_rows), (2, 2));
+
+        let strict = CsvOptions {
+            error_policy: CsvErrorPolicy::SkipUpToPercent(49),
+            ..CsvOptions::default()
+        };
+        let error = summarize_csv_stream(Cursor::new(contents), &[], &strict)
+            .await
+            .expect_err("percentage budget is exceeded at EOF");
+        assert!(matches!(
+            error,
+            CsvIngestError::ErrorBudgetExceeded {
+                rejected: 2,
+                scanned: 4
+            }
         ));
     }
 
@@ -379,6 +1038,7 @@ mod tests {
             CsvParser::from_path(file.path(), &["sku"], &CsvOptions::default())
                 .await
                 .expect("construct path parser");
+        assert_eq!(path_parser.source_name(), Some(meta.name_hint.as_str()));
         let mut reader_parser = CsvParser::from_reader(
             Cursor::new(b"sku,value\nA,1\n"),
             &["sku"],
@@ -420,6 +1080,630 @@ mod tests {
         assert_eq!(summary.headers, ["sku", "value"]);
     }
 
+    #[tokio::test]
+    async fn quarantine_receives_original_bytes_of_rejected_rows() {
+        let directory = tempfile::tempdir().expect("create directory");
+        let path = directory.path().join("rejected.csv");
+        let writer = crate::CsvQuarantineWriter::create(&path).expect("create quarantine");
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let input = b"\xef\xbb\xbfsku,note\r\nA,\"x\r\ny\",extra\r\nC\r\nB,ok\r\n";
+        let mut parser = CsvParser::from_reader(Cursor::new(&input[..]), &["sku"], &options)
+            .await
+            .expect("construct parser")
+            .with_quarantine(writer);
+
+        let record = parser
+            .next_record()
+            .await
+            .expect("read accepted row")
+            .expect("accepted row");
+        assert_eq!(record, vec!["B", "ok"]);
+        assert!(parser.next_record().await.expect("finish").is_none());
+        assert_eq!(parser.rejected_rows(), 2);
+
+        let contents = std::fs::read(&path).expect("read quarantine");
+        let expected: &[u8] = b"row,byte,line,record,error,raw\n\
+1,12,1,1,\"Ragged row: got 3 fields, expected 2\",\"A,\"\"x\r\ny\"\",extra\"\n\
+2,28,3,2,\"Ragged row: got 1 fields, expected 2\",\"C\"\n";
+        assert_eq!(contents, expected);
+    }
+
+    #[tokio::test]
+    async fn quarantining_many_rows_moves_each_byte_at_most_once() {
+        let mut input = b"sku,note\n".to_vec();
+        for row in 0..300 {
+            if row % 3 == 0 {
+                input.extend(format!("R{row}\n").as_bytes());
+            } else {
+                input.extend(format!("A{row},note {row}\n").as_bytes());
+            }
+        }
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let writer = crate::CsvQuarantineWriter::new(std::io::sink()).expect("create quarantine");
+        let mut parser = CsvParser::from_reader(Cursor::new(&input[..]), &["sku"], &options)
+            .await
+            .expect("construct parser")
+            .with_quarantine(writer);
+
+        let mut accepted = 0;
+        while parser.next_record().await.expect("read row").is_some() {
+            accepted += 1;
+        }
+        assert_eq!(accepted, 200);
+        assert_eq!(parser.rejected_rows(), 100);
+        // Compaction is amortized: draining the buffer on every skipped row
+        // would move several times the input.
+        let moved = parser.reader.get_ref().moved();
+        assert!(moved > 0);
+        assert!(moved <= input.len());
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn deserializes_records_by_header_name() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Item {
+            value: u32,
+            sku: String,
+            note: Option<String>,
+        }
+
+        let mut parser = CsvParser::from_reader(
+            Cursor::new(b"sku,note,value\nA,,1\nB,fragile,2\n"),
+            &["sku"],
+            &CsvOptions::default(),
+        )
+        .await
+        .expect("construct parser");
+
+        let mut items = Vec::new();
+        while let Some(item) = parser.deserialize::<Item>().await.expect("deserialize") {
+            items.push(item);
+        }
+        assert_eq!(
+            items,
+            [
+                Item {
+                    value: 1,
+                    sku: "A".to_string(),
+                    note: None,
+                },
+                Item {
+                    value: 2,
+                    sku: "B".to_string(),
+                    note: Some("fragile".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn borrowed_deserialization_reuses_parser_storage() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Item<'a> {
+            sku: &'a str,
+            raw: &'a [u8],
+        }
+
+        let options = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        let mut parser = CsvParser::from_reader(Cursor::new(b"A,\xff\n"), &[], &options)
+            .await
+            .expect("construct parser");
+
+        let item: Item<'_> = parser
+            .deserialize_ref()
+            .await
+            .expect("deserialize")
+            .expect("record");
+        assert_eq!(
+            item,
+            Item {
+                sku: "A",
+                raw: b"\xff",
+            }
+        );
+        assert!(parser
+            .deserialize_ref::<Item<'_>>()
+            .await
+            .expect("finish")
+            .is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn deserialization_errors_name_the_row_and_column() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Item {
+            sku: String,
+            value: u32,
+        }
+
+        let mut parser = CsvParser::from_reader(
+            Cursor::new(b"sku,value\nA,1\nB,many\n"),
+            &[],
+            &CsvOptions::default(),
+        )
+        .await
+        .expect("construct parser")
+        .with_source_name("items.csv");
+
+        parser
+            .deserialize::<Item>()
+            .await
+            .expect("first row")
+            .expect("record");
+        let error = parser
+            .deserialize::<Item>()
+            .await
+            .expect_err("invalid integer must fail");
+
+        assert_eq!(
+            error.to_string(),
+            "Row 2 could not be deserialized at column 'value': invalid digit found in string"
+        );
+        assert!(matches!(
+            &error,
+            CsvIngestError::Deserialize {
+                row: 2,
+                field: Some(1),
+                column: Some(column),
+                ..
+            } if column == "value"
+        ));
+        assert_eq!(error.row(), Some(2));
+        assert_eq!(error.position().map(Position::byte), Some(14));
+        assert_eq!(error.source_name(), Some("items.csv"));
+
+        let mut missing =
+            CsvParser::from_reader(Cursor::new(b"sku\nA\n"), &[], &CsvOptions::default())
+                .await
+                .expect("construct parser");
+        let error = missing
+            .deserialize::<Item>()
+            .await
+            .expect_err("missing struct field must fail");
+        assert_eq!(
+            error.to_string(),
+            "Row 1 could not be deserialized: missing field `value`"
+        );
+    }
+
+    fn inventory_schema() -> crate::CsvSchema {
+        use crate::{CsvColumnSchema, CsvRule};
+
+        crate::CsvSchema {
+            columns: vec![
+                CsvColumnSchema::new(
+                    "qty",
+                    [
+                        CsvRule::NotEmpty,
+                        CsvRule::Integer {
+                            min: Some(0),
+                            max: None,
+                        },
+                    ],
+                ),
+                CsvColumnSchema::new("price", [CsvRule::Decimal]),
+                CsvColumnSchema::new("sku", [CsvRule::MaxWidth { max: 4 }]),
+                CsvColumnSchema::new(
+                    "status",
+                    [CsvRule::OneOf {
+                        values: vec!["open".to_string(), "closed".to_string()],
+                    }],
+                ),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn schema_violations_name_the_row_column_and_rule() {
+        let input = b"sku,qty,price,status\nAB-1,2,9.50,open\nAB-2,-1,1,closed\n";
+        let mut parser =
+            CsvParser::from_reader(Cursor::new(&input[..]), &[], &CsvOptions::default())
+                .await
+                .expect("construct parser")
+                .with_schema(&inventory_schema())
+                .expect("compile schema")
+                .with_source_name("inventory.csv");
+
+        assert!(parser.next_record().await.expect("valid row").is_some());
+        let error = parser
+            .next_record()
+            .await
+            .expect_err("negative quantity must fail");
+
+        assert_eq!(
+            error.to_string(),
+            "Row 2 column 'qty' violates rule: integer >= 0"
+        );
+        assert!(matches!(
+            &error,
+            CsvIngestError::SchemaViolation { row: 2, column, rule, .. }
+                if column == "qty"
+                    && **rule == crate::CsvRule::Integer { min: Some(0), max: None }
+        ));
+        assert_eq!(error.position().map(Position::line), Some(3));
+        assert_eq!(error.source_name(), Some("inventory.csv"));
+    }
+
+    #[tokio::test]
+    async fn schema_columns_are_required_and_violations_can_be_skipped() {
+        let error =
+            CsvParser::from_reader(Cursor::new(b"sku,qty,price\n"), &[], &CsvOptions::default())
+                .await
+                .expect("construct parser")
+                .with_schema(&inventory_schema())
+                .err()
+                .expect("missing schema column must fail");
+        assert!(matches!(error, CsvIngestError::MissingHeader(header) if header == "status"));
+
+        let options = CsvOptions {
+            flexible: true,
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let input = b"sku,qty,price,status\nAB-10,1,1,open\nAB-2,,1,open\nAB-3,1,1.2.3,open\n\
+AB-4,1,1,pending\nAB-5,1,1\nAB-6,,,closed\n";
+        let summary = summarize_csv_stream(Cursor::new(&input[..]), &[], &options)
+            .await
+            .expect("summarize without schema");
+        assert_eq!(summary.rejected_rows, 0);
+
+        let mut parser = CsvParser::from_reader(Cursor::new(&input[..]), &[], &options)
+            .await
+            .expect("construct parser")
+            .with_schema(&inventory_schema())
+            .expect("compile schema");
+        while parser
+            .next_record()
+            .await
+            .expect("skip violations")
+            .is_some()
+        {}
+
+        let messages: Vec<_> = parser
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Row 1 column 'sku' violates rule: at most 4 characters",
+                "Row 2 column 'qty' violates rule: not empty",
+                "Row 3 column 'price' violates rule: decimal",
+                "Row 4 column 'status' violates rule: one of [\"open\", \"closed\"]",
+                "Row 5 is missing required field: status",
+                "Row 6 column 'qty' violates rule: not empty",
+            ]
+        );
+        assert_eq!(parser.records_read(), 0);
+    }
+
+    #[tokio::test]
+    async fn schemas_require_headers() {
+        let options = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        let error = CsvParser::from_reader(Cursor::new(b"1\n"), &[], &options)
+            .await
+            .expect("construct parser")
+            .with_schema(&inventory_schema())
+            .err()
+            .expect("headerless schema must fail");
+        assert!(matches!(error, CsvIngestError::UnsupportedDialect(_)));
+    }
+
+    const FEED: &[u8] = b"id,price,active,day,seen_at,name\n\
+1,2.50,true,2024-01-02,2024-01-02T03:04:05Z,Ann\n\
+2,3,false,2024-01-03,2024-01-03 10:00:00,\n\
+3,4.25,TRUE,2024-01-04,2024-01-04T11:30:00.5+01:00,Zo\xc3\xab\n";
+
+    #[tokio::test]
+    async fn schemas_are_inferred_from_the_first_records() {
+        use crate::{CsvColumnType, CsvSampling};
+
+        let mut parser = CsvParser::from_reader(Cursor::new(FEED), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser");
+        let inferred = parser
+            .infer_schema(CsvSampling::First(2))
+            .await
+            .expect("infer schema");
+
+        let types: Vec<_> = inferred
+            .columns
+            .iter()
+            .map(|column| (column.name.as_str(), column.column_type, column.nullable))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("id", CsvColumnType::Integer, false),
+                ("price", CsvColumnType::Float, false),
+                ("active", CsvColumnType::Boolean, false),
+                ("day", CsvColumnType::Date, false),
+                ("seen_at", CsvColumnType::Timestamp, false),
+                ("name", CsvColumnType::String, true),
+            ]
+        );
+        assert_eq!(inferred.columns[4].max_width, 20);
+        assert_eq!((inferred.rows_sampled, inferred.rows_read), (2, 2));
+        assert_eq!(parser.records_read(), 2);
+
+        let schema = inferred.to_schema();
+        let mut enforcing = CsvParser::from_reader(Cursor::new(FEED), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .with_schema(&schema)
+            .expect("compile inferred schema");
+        enforcing.next_record().await.expect("first row conforms");
+        enforcing.next_record().await.expect("second row conforms");
+        let error = enforcing
+            .next_record()
+            .await
+            .expect_err("unsampled timestamp is wider than the sample");
+        assert!(matches!(
+            error,
+            CsvIngestError::SchemaViolation { row: 3, column, .. } if column == "seen_at"
+        ));
+    }
+
+    #[tokio::test]
+    async fn reservoir_sampling_reads_every_record() {
+        use crate::{CsvColumnType, CsvSampling};
+
+        let mut parser = CsvParser::from_reader(Cursor::new(FEED), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser");
+        let inferred = parser
+            .infer_schema(CsvSampling::Reservoir { size: 8, seed: 1 })
+            .await
+            .expect("infer schema");
+
+        assert_eq!((inferred.rows_sampled, inferred.rows_read), (3, 3));
+        assert_eq!(inferred.columns[5].column_type, CsvColumnType::String);
+        assert_eq!(inferred.columns[5].max_width, 3);
+        assert!(parser.next_record().await.expect("at end").is_none());
+
+        let headerless = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        let mut parser = CsvParser::from_reader(Cursor::new(FEED), &[], &headerless)
+            .await
+            .expect("construct parser");
+        assert!(matches!(
+            parser.infer_schema(CsvSampling::default()).await,
+            Err(CsvIngestError::UnsupportedDialect(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn projections_yield_compact_records_in_selection_order() {
+        let input = b"sku,qty,note,qty\nA,1,x,2\nB,3,y,4\n";
+        let mut parser =
+            CsvParser::from_reader(Cursor::new(&input[..]), &["note"], &CsvOptions::default())
+                .await
+                .expect("construct parser")
+                .with_projection(&CsvProjection::new([
+                    crate::CsvSelector::from(3),
+                    crate::CsvSelector::from("sku"),
+                    crate::CsvSelector::from("qty"),
+                ]))
+                .expect("resolve projection");
+
+        assert_eq!(
+            parser.headers(),
+            &crate::ByteRecord::from(vec!["qty", "sku", "qty"])
+        );
+        assert_eq!(parser.header_index("sku"), Some(1));
+        assert_eq!(parser.header_index("note"), None);
+        assert_eq!(parser.required_indices(), [2, 3, 0, 1]);
+
+        let record = parser
+            .next_record()
+            .await
+            .expect("read first record")
+            .expect("first record");
+        assert_eq!(record, &crate::ByteRecord::from(vec!["2", "A", "1"]));
+        assert_eq!(record.position().map(Position::byte), Some(17));
+
+        let mut caller_record = crate::ByteRecord::new();
+        assert!(parser
+            .read_record(&mut caller_record)
+            .await
+            .expect("read second record"));
+        assert_eq!(caller_record, crate::ByteRecord::from(vec!["4", "B", "3"]));
+        assert!(parser.next_record().await.expect("finish").is_none());
+    }
+
+    #[tokio::test]
+    async fn projected_columns_are_required_and_schemas_see_full_records() {
+        let error = CsvParser::from_reader(Cursor::new(b"sku\n"), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .with_projection(&CsvProjection::new([1]))
+            .err()
+            .expect("index past the header must fail");
+        assert!(matches!(error, CsvIngestError::MissingHeader(header) if header == "column 1"));
+
+        let options = CsvOptions {
+            flexible: true,
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let schema = crate::CsvSchema {
+            columns: vec![crate::CsvColumnSchema::new(
+                "qty",
+                [crate::CsvRule::NotEmpty],
+            )],
+        };
+        let input = b"sku,qty,note\nA,1,x\nB,,y\nC,2\nD,3,z\n";
+        let mut parser = CsvParser::from_reader(Cursor::new(&input[..]), &[], &options)
+            .await
+            .expect("construct parser")
+            .with_projection(&CsvProjection::new(["note"]))
+            .expect("resolve projection")
+            .with_schema(&schema)
+            .expect("compile schema");
+
+        let mut notes = Vec::new();
+        while let Some(record) = parser.next_record().await.expect("skip rejected rows") {
+            notes.push(record.iter().map(<[u8]>::to_vec).collect::<Vec<_>>());
+        }
+        assert_eq!(notes, [vec![b"x".to_vec()], vec![b"z".to_vec()]]);
+        let messages: Vec<_> = parser
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Row 2 column 'qty' violates rule: not empty",
+                "Row 3 is missing required field: note",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn headerless_projections_select_by_index() {
+        let options = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        let error = CsvParser::from_reader(Cursor::new(b"a,b\n"), &[], &options)
+            .await
+            .expect("construct parser")
+            .with_projection(&CsvProjection::new(["a"]))
+            .err()
+            .expect("names need headers");
+        assert!(matches!(error, CsvIngestError::UnsupportedDialect(_)));
+
+        let mut parser = CsvParser::from_reader(Cursor::new(b"a,b\nc,d\n"), &[], &options)
+            .await
+            .expect("construct parser")
+            .with_projection(&CsvProjection::new([1]))
+            .expect("resolve projection");
+        assert!(parser.headers().is_empty());
+        let record = parser
+            .next_record()
+            .await
+            .expect("read record")
+            .expect("record");
+        assert_eq!(record, &crate::ByteRecord::from(vec!["b"]));
+
+        let mut parser = CsvParser::from_reader(Cursor::new(b"a,b\n"), &[], &options)
+            .await
+            .expect("construct parser")
+            .with_projection(&CsvProjection::new([2]))
+            .expect("resolve projection");
+        let error = parser
+            .next_record()
+            .await
+            .expect_err("selected column past the row must fail");
+        assert_eq!(
+            error.to_string(),
+            "Row 1 is missing required field: column 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn filters_skip_rows_before_schema_rules() {
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let schema = crate::CsvSchema {
+            columns: vec![crate::CsvColumnSchema::new(
+                "qty",
+                [crate::CsvRule::Integer {
+                    min: None,
+                    max: None,
+                }],
+            )],
+        };
+        let input = b"sku,country,qty\nA,US,1\nB,CA,bad\nC,US,x\nD,MX\nE,US,2\n";
+        let mut parser = CsvParser::from_reader(Cursor::new(&input[..]), &[], &options)
+            .await
+            .expect("construct parser")
+            .with_projection(&CsvProjection::new(["sku"]))
+            .expect("resolve projection")
+            .with_filter(CsvFilter::equals("country", "US"))
+            .expect("resolve filter")
+            .with_schema(&schema)
+            .expect("compile schema");
+
+        let first = parser
+            .next_record()
+            .await
+            .expect("read first record")
+            .expect("first record");
+        assert_eq!(first, &crate::ByteRecord::from(vec!["A"]));
+        assert_eq!(parser.required_indices(), [0, 1, 2]);
+
+        let summary = parser.into_summary().await.expect("summarize");
+        assert_eq!(summary.headers, ["sku"]);
+        assert_eq!(summary.row_count, 2);
+        assert_eq!(summary.scanned_rows, 5);
+        assert_eq!(summary.filtered_rows, 1);
+        assert_eq!(summary.rejected_rows, 2);
+        let messages: Vec<_> = summary
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Row 3 column 'qty' violates rule: integer",
+                "Ragged row: got 2 fields, expected 3",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn filter_columns_are_required_headers() {
+        let error = CsvParser::from_reader(Cursor::new(b"sku\n"), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .with_filter(CsvFilter::equals("country", "US"))
+            .err()
+            .expect("missing filter column must fail");
+        assert!(matches!(error, CsvIngestError::MissingHeader(header) if header == "country"));
+
+        let headerless = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        let mut parser = CsvParser::from_reader(Cursor::new(b"a,1\nb,2\n"), &[], &headerless)
+            .await
+            .expect("construct parser")
+            .with_filter(CsvFilter::new(1, |field| field != b"1"))
+            .expect("indices resolve without headers");
+        let record = parser
+            .next_record()
+            .await
+            .expect("read record")
+            .expect("record");
+        assert_eq!(record, &crate::ByteRecord::from(vec!["b", "2"]));
+        assert_eq!(parser.filtered_rows(), 1);
+        assert_eq!(parser.records_read(), 1);
+    }
+
     #[tokio::test]
     async fn into_inner_returns_the_reader() {
         let parser = CsvParser::from_reader(Cursor::new(b"sku\nA\n"), &[], &CsvOptions::default())
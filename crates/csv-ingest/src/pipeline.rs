@@ -0,0 +1,918 @@
+use crate::parser::check_required_headers;
+use crate::{
+    reader_from_path, ByteRecord, CsvDiagnostic, CsvErrorPolicy, CsvHeaderMode, CsvIngestError,
+    CsvIngestSummary, CsvMeta, CsvOptions, CsvParser, CsvResult, CsvTerminator, Position,
+};
+use bytes::{Bytes, BytesMut};
+use csv_async::AsyncReaderBuilder;
+use futures_util::FutureExt;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc as tokio_mpsc;
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// How a pipelined summary divides work between the reader and parser workers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvPipelineConfig {
+    /// The number of parser threads. Defaults to one fewer than the available
+    /// parallelism, leaving a core to decompress and transcode.
+    pub workers: Option<NonZeroUsize>,
+    /// The target size of a parsed block, in decoded bytes. Blocks end on a
+    /// record boundary, so a block holds at least one whole record.
+    pub block_size: usize,
+}
+
+impl Default for CsvPipelineConfig {
+    fn default() -> Self {
+        Self {
+            workers: None,
+            block_size: 1 << 20,
+        }
+    }
+}
+
+/// Summarize a CSV stream with reading and parsing on separate cores.
+///
+/// The calling task reads `reader`, which runs any decompression and
+/// transcoding set up by [`crate::build_csv_reader`], and cuts the decoded
+/// bytes into blocks of whole records. Parser threads summarize the blocks
+/// concurrently and their results are combined in input order, so the
+/// summary, or the first error, is the one [`crate::summarize_csv_stream`]
+/// returns for the same input. Record-level errors carry the same row numbers
+/// and positions.
+pub async fn summarize_csv_stream_pipelined<R>(
+    reader: R,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    config: &CsvPipelineConfig,
+) -> CsvResult<CsvIngestSummary>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    summarize_pipelined(reader, required_headers, options, config, None).await
+}
+
+/// Summarize a local CSV path as [`summarize_csv_stream_pipelined`] does and
+/// return the transport metadata used.
+///
+/// Record-level errors name the file, as with [`crate::summarize_csv_path`].
+pub async fn summarize_csv_path_pipelined(
+    path: &Path,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    config: &CsvPipelineConfig,
+) -> CsvResult<(CsvIngestSummary, CsvMeta)> {
+    let (reader, meta) = reader_from_path(path).await?;
+    let summary = summarize_pipelined(
+        reader,
+        required_headers,
+        options,
+        config,
+        Some(&meta.name_hint),
+    )
+    .await?;
+    Ok((summary, meta))
+}
+
+async fn summarize_pipelined<R>(
+    mut reader: R,
+    required_headers: &[&str],
+    options: &CsvOptions,
+    config: &CsvPipelineConfig,
+    source_name: Option<&str>,
+) -> CsvResult<CsvIngestSummary>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    check_required_headers(required_headers, options)?;
+    options.validate()?;
+    if config.block_size == 0 {
+        return Err(CsvIngestError::UnsupportedDialect(
+            "pipeline blocks must hold at least one byte".to_string(),
+        ));
+    }
+
+    // The first record, a header or the record that fixes the row width, is
+    // parsed here so that workers can validate every later record alike.
+    let mut splitter = RecordSplitter::new(options);
+    let mut buffer = BytesMut::new();
+    let mut scanned = 0;
+    let lead_end = loop {
+        if let Some(end) = splitter.find_end(&buffer[scanned..]) {
+            break scanned + end;
+        }
+        scanned = buffer.len();
+        buffer.reserve(config.block_size);
+        if reader.read_buf(&mut buffer).await? == 0 {
+            break buffer.len();
+        }
+    };
+    let lead = buffer.split_to(lead_end);
+    let lead = read_lead(&lead, required_headers, options)
+        .map_err(|error| error.with_source_name(source_name))?;
+    let mut merger = Merger::new(lead.start, options, source_name);
+    merger.scanned = lead.scanned;
+    merger.accepted = lead.accepted;
+
+    let workers = config.workers.map_or_else(
+        || {
+            thread::available_parallelism()
+                .map_or(1, |parallelism| parallelism.get().saturating_sub(1).max(1))
+        },
+        NonZeroUsize::get,
+    );
+    let parser = Arc::new(BlockParser {
+        options: *options,
+        expected_width: lead.expected_width,
+        required: lead.required,
+    });
+    let mut pool = WorkerPool::start(workers, parser)?;
+
+    let mut scanned = 0;
+    let mut last_end = None;
+    let read_error = loop {
+        while pool.in_flight() >= 2 * workers {
+            let outcome = pool.next().await;
+            merger.push(outcome)?;
+        }
+        while let Some(outcome) = pool.try_next() {
+            merger.push(outcome)?;
+        }
+
+        buffer.reserve(config.block_size);
+        match reader.read_buf(&mut buffer).await {
+            Ok(0) => {
+                if !buffer.is_empty() {
+                    pool.send(buffer.split().freeze())?;
+                }
+                break None;
+            }
+            Ok(_) => {
+                while let Some(end) = splitter.find_end(&buffer[scanned..]) {
+                    scanned += end;
+                    last_end = Some(scanned);
+                }
+                scanned = buffer.len();
+                if let Some(end) = last_end.filter(|&end| end >= config.block_size) {
+                    pool.send(buffer.split_to(end).freeze())?;
+                    scanned -= end;
+                    last_end = None;
+                }
+            }
+            Err(error) => {
+                // Records completed before the failure are still summarized
+                // first, as the streaming parser would.
+                if let Some(end) = last_end {
+                    pool.send(buffer.split_to(end).freeze())?;
+                }
+                break Some(error);
+            }
+        }
+    };
+
+    while pool.in_flight() > 0 {
+        let outcome = pool.next().await;
+        merger.push(outcome)?;
+    }
+    if let Some(error) = read_error {
+        return Err(error.into());
+    }
+    merger.finish(lead.headers)
+}
+
+/// What the first record tells the workers, and where the body starts.
+struct Lead {
+    headers: Vec<String>,
+    expected_width: Option<u64>,
+    required: Vec<(usize, String)>,
+    start: Position,
+    scanned: u64,
+    accepted: u64,
+}
+
+/// Parse the header, or the first record of headerless input, as the
+/// streaming parser would.
+fn read_lead(lead: &[u8], required_headers: &[&str], options: &CsvOptions) -> CsvResult<Lead> {
+    ready(async {
+        let mut parser = CsvParser::from_reader(lead, required_headers, options).await?;
+        let headers = parser
+            .headers()
+            .iter()
+            .map(|header| std::str::from_utf8(header).map(str::to_string))
+            .collect::<Result<Vec<_>, _>>()?;
+        let width = match options.headers {
+            CsvHeaderMode::Present => Some(parser.headers().len()),
+            CsvHeaderMode::Absent => parser.next_record().await?.map(ByteRecord::len),
+        };
+        Ok(Lead {
+            headers,
+            expected_width: width
+                .filter(|_| !options.flexible)
+                .map(|width| width as u64),
+            required: parser
+                .required_indices()
+                .iter()
+                .copied()
+                .zip(required_headers.iter().map(|header| header.to_string()))
+                .collect(),
+            start: parser.position().clone(),
+            scanned: parser.rows_scanned(),
+            accepted: parser.records_read(),
+        })
+    })
+}
+
+/// Complete a future over in-memory input, which never waits.
+fn ready<T>(future: impl std::future::Future<Output = T>) -> T {
+    future
+        .now_or_never()
+        .expect("in-memory CSV input is always ready")
+}
+
+/// Tracks the CSV state machine across reads to find where records end.
+///
+/// This follows `csv_core`, so a record ends exactly where the streaming
+/// parser ends it: just after the first terminator byte outside quotes.
+struct RecordSplitter {
+    options: CsvOptions,
+    state: ScanState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    RecordStart,
+    FieldStart,
+    InField,
+    InQuoted,
+    /// A quote inside a quoted field, which closes it unless doubled.
+    QuoteInQuoted,
+    Escaped,
+}
+
+impl RecordSplitter {
+    fn new(options: &CsvOptions) -> Self {
+        Self {
+            options: *options,
+            state: ScanState::RecordStart,
+        }
+    }
+
+    /// Scan `bytes`, which continue the bytes already scanned, and return the
+    /// offset just past the first record end in them.
+    fn find_end(&mut self, bytes: &[u8]) -> Option<usize> {
+        let options = self.options;
+        let quote = |byte| options.quoting && byte == options.quote;
+        for (index, &byte) in bytes.iter().enumerate() {
+            let state = match self.state {
+                ScanState::RecordStart if options.is_terminator(byte) => ScanState::RecordStart,
+                ScanState::RecordStart | ScanState::FieldStart if quote(byte) => {
+                    ScanState::InQuoted
+                }
+                ScanState::InQuoted if quote(byte) => ScanState::QuoteInQuoted,
+                ScanState::InQuoted if options.quoting && options.escape == Some(byte) => {
+                    ScanState::Escaped
+                }
+                ScanState::InQuoted => ScanState::InQuoted,
+                ScanState::Escaped => ScanState::InQuoted,
+                ScanState::QuoteInQuoted if options.double_quote && quote(byte) => {
+                    ScanState::InQuoted
+                }
+                _ if byte == options.delimiter => ScanState::FieldStart,
+                _ if options.is_terminator(byte) => {
+                    self.state = ScanState::RecordStart;
+                    return Some(index + 1);
+                }
+                _ => ScanState::InField,
+            };
+            self.state = state;
+        }
+        None
+    }
+}
+
+/// The per-record checks a worker applies, resolved from the first record.
+struct BlockParser {
+    options: CsvOptions,
+    expected_width: Option<u64>,
+    required: Vec<(usize, String)>,
+}
+
+/// What a worker found in one block, positioned relative to the block.
+struct BlockOutcome {
+    scanned: u64,
+    accepted: u64,
+    errors: Vec<CsvIngestError>,
+    end: Position,
+}
+
+impl BlockParser {
+    fn parse(&self, block: &[u8]) -> CsvResult<BlockOutcome> {
+        // `csv_core` strips a BOM at the start of its input, which is only the
+        // start of the stream for the first block. A leading terminator, read
+        // as an empty line, keeps the BOM in the record.
+        let prefixed;
+        let (input, byte_shift, line_shift) = if block.starts_with(UTF8_BOM) {
+            let terminator = match self.options.terminator {
+                CsvTerminator::CrLf => b'\r',
+                CsvTerminator::Any(byte) => byte,
+            };
+            prefixed = [&[terminator][..], block].concat();
+            (&prefixed[..], 1, u64::from(terminator == b'\n'))
+        } else {
+            (block, 0, 0)
+        };
+        // The first record starts before the prefix; everything after it is shifted.
+        let unshift = |position: &Position| {
+            let mut unshifted = position.clone();
+            if position.byte() > 0 {
+                unshifted
+                    .set_byte(position.byte() - byte_shift)
+                    .set_line(position.line() - line_shift);
+            }
+            unshifted
+        };
+
+        let mut builder = AsyncReaderBuilder::new();
+        self.options.configure_reader(&mut builder)?;
+        builder.has_headers(false).flexible(true);
+        let mut reader = builder.create_reader(input);
+        let mut record = ByteRecord::new();
+        let skips = self.options.error_policy.skips();
+        let mut outcome = BlockOutcome {
+            scanned: 0,
+            accepted: 0,
+            errors: Vec::new(),
+            end: Position::new(),
+        };
+        while ready(reader.read_byte_record(&mut record))? {
+            outcome.scanned += 1;
+            let position = record.position().map(unshift);
+            let actual = record.len() as u64;
+            let error = match self.expected_width {
+                Some(expected) if expected != actual => Some(CsvIngestError::RaggedRow {
+                    row: Some(outcome.scanned),
+                    expected,
+                    actual,
+                    position,
+                    source_name: None,
+                }),
+                _ => self
+                    .required
+                    .iter()
+                    .find(|(index, _)| record.get(*index).is_none())
+                    .map(|(_, header)| CsvIngestError::MissingRequiredField {
+                        row: outcome.scanned,
+                        header: header.clone(),
+                        position,
+                        source_name: None,
+                    }),
+            };
+            match error {
+                Some(error) => {
+                    outcome.errors.push(error);
+                    if !skips {
+                        break;
+                    }
+                }
+                None => outcome.accepted += 1,
+            }
+        }
+        outcome.end = unshift(reader.position());
+        Ok(outcome)
+    }
+}
+
+/// Parser threads fed blocks in sequence, whose outcomes arrive in any order.
+struct WorkerPool {
+    blocks: Option<mpsc::Sender<(u64, Bytes)>>,
+    outcomes: tokio_mpsc::UnboundedReceiver<(u64, CsvResult<BlockOutcome>)>,
+    sent: u64,
+    received: u64,
+    pending: BTreeMap<u64, CsvResult<BlockOutcome>>,
+}
+
+impl WorkerPool {
+    fn start(workers: usize, parser: Arc<BlockParser>) -> CsvResult<Self> {
+        let (blocks, queue) = mpsc::channel::<(u64, Bytes)>();
+        let queue = Arc::new(Mutex::new(queue));
+        let (results, outcomes) = tokio_mpsc::unbounded_channel();
+        for _ in 0..workers {
+            let queue = Arc::clone(&queue);
+            let results = results.clone();
+            let parser = Arc::clone(&parser);
+            thread::Builder::new()
+                .name("csv-ingest-parser".to_string())
+                .spawn(move || loop {
+                    let next = queue.lock().map(|queue| queue.recv());
+                    let Ok(Ok((sequence, block))) = next else {
+                        return;
+                    };
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(|| parser.parse(&block)))
+                        .unwrap_or(Err(CsvIngestError::PipelineWorkerPanicked));
+                    if results.send((sequence, outcome)).is_err() {
+                        return;
+                    }
+                })?;
+        }
+        Ok(Self {
+            blocks: Some(blocks),
+            outcomes,
+            sent: 0,
+            received: 0,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    fn in_flight(&self) -> usize {
+        (self.sent - self.received) as usize
+    }
+
+    fn send(&mut self, block: Bytes) -> CsvResult<()> {
+        let sent = self
+            .blocks
+            .as_ref()
+            .is_some_and(|blocks| blocks.send((self.sent, block)).is_ok());
+        if !sent {
+            return Err(CsvIngestError::PipelineWorkerPanicked);
+        }
+        self.sent += 1;
+        Ok(())
+    }
+
+    /// Wait for the outcome of the oldest block still in flight.
+    async fn next(&mut self) -> CsvResult<BlockOutcome> {
+        loop {
+            if let Some(outcome) = self.pending.remove(&self.received) {
+                self.received += 1;
+                return outcome;
+            }
+            match self.outcomes.recv().await {
+                Some((sequence, outcome)) => {
+                    self.pending.insert(sequence, outcome);
+                }
+                None => return Err(CsvIngestError::PipelineWorkerPanicked),
+            }
+        }
+    }
+
+    /// Take the outcome of the oldest block in flight if it is already known.
+    fn try_next(&mut self) -> Option<CsvResult<BlockOutcome>> {
+        while let Ok((sequence, outcome)) = self.outcomes.try_recv() {
+            self.pending.insert(sequence, outcome);
+        }
+        let outcome = self.pending.remove(&self.received)?;
+        self.received += 1;
+        Some(outcome)
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // Closing the queue lets idle workers exit; busy ones exit after their block.
+        self.blocks.take();
+    }
+}
+
+/// Combines block outcomes in input order into stream-wide counts and errors.
+struct Merger<'a> {
+    start: Position,
+    policy: CsvErrorPolicy,
+    max_diagnostics: usize,
+    source_name: Option<&'a str>,
+    scanned: u64,
+    accepted: u64,
+    rejected: u64,
+    diagnostics: Vec<CsvDiagnostic>,
+}
+
+impl<'a> Merger<'a> {
+    fn new(start: Position, options: &CsvOptions, source_name: Option<&'a str>) -> Self {
+        Self {
+            start,
+            policy: options.error_policy,
+            max_diagnostics: options.max_diagnostics,
+            source_name,
+            scanned: 0,
+            accepted: 0,
+            rejected: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, outcome: CsvResult<BlockOutcome>) -> CsvResult<()> {
+        let outcome = outcome?;
+        for error in outcome.errors {
+            let error = self.relocate(error).with_source_name(self.source_name);
+            if !self.policy.skips() {
+                return Err(error);
+            }
+            self.rejected += 1;
+            if self.diagnostics.len() < self.max_diagnostics {
+                self.diagnostics.push(CsvDiagnostic::new(&error));
+            }
+            self.policy
+                .check_count(self.rejected, error.row().unwrap_or_default())?;
+        }
+        self.scanned += outcome.scanned;
+        self.accepted += outcome.accepted;
+        self.start = self.absolute(&outcome.end);
+        Ok(())
+    }
+
+    /// Convert a block's row number and position into stream-wide ones.
+    fn relocate(&self, mut error: CsvIngestError) -> CsvIngestError {
+        match &mut error {
+            CsvIngestError::RaggedRow { row, position, .. } => {
+                *row = row.map(|row| row + self.scanned);
+                *position = position.as_ref().map(|position| self.absolute(position));
+            }
+            CsvIngestError::MissingRequiredField { row, position, .. } => {
+                *row += self.scanned;
+                *position = position.as_ref().map(|position| self.absolute(position));
+            }
+            _ => {}
+        }
+        error
+    }
+
+    fn absolute(&self, position: &Position) -> Position {
+        let mut absolute = Position::new();
+        absolute
+            .set_byte(self.start.byte() + position.byte())
+            .set_line(self.start.line() + position.line() - 1)
+            .set_record(self.start.record() + position.record());
+        absolute
+    }
+
+    fn finish(self, headers: Vec<String>) -> CsvResult<CsvIngestSummary> {
+        self.policy.check_finished(self.rejected, self.scanned)?;
+        Ok(CsvIngestSummary {
+            row_count: self.accepted,
+            headers,
+            scanned_rows: self.scanned,
+            filtered_rows: 0,
+            rejected_rows: self.rejected,
+            diagnostics: self.diagnostics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_csv_reader, summarize_csv_stream};
+    use proptest::prelude::*;
+    use std::io::{self, Cursor};
+    use std::pin::Pin;
+    use std::sync::OnceLock;
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+
+    const BLOCK_SIZES: [usize; 4] = [1, 3, 16, 1 << 20];
+
+    fn runtime() -> &'static tokio::runtime::Runtime {
+        static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+        RUNTIME.get_or_init(|| tokio::runtime::Runtime::new().expect("create test runtime"))
+    }
+
+    fn config(block_size: usize, workers: usize) -> CsvPipelineConfig {
+        CsvPipelineConfig {
+            workers: NonZeroUsize::new(workers),
+            block_size,
+        }
+    }
+
+    /// Render both outcomes, since errors do not implement `PartialEq`.
+    async fn outcomes(
+        input: &[u8],
+        required_headers: &[&str],
+        options: &CsvOptions,
+        config: &CsvPipelineConfig,
+    ) -> (String, String) {
+        let streaming =
+            summarize_csv_stream(Cursor::new(input.to_vec()), required_headers, options).await;
+        let pipelined = summarize_csv_stream_pipelined(
+            Cursor::new(input.to_vec()),
+            required_headers,
+            options,
+            config,
+        )
+        .await;
+        (format!("{streaming:?}"), format!("{pipelined:?}"))
+    }
+
+    async fn assert_matches(input: &[u8], required_headers: &[&str], options: &CsvOptions) {
+        for block_size in BLOCK_SIZES {
+            for workers in [1, 3] {
+                let (streaming, pipelined) = outcomes(
+                    input,
+                    required_headers,
+                    options,
+                    &config(block_size, workers),
+                )
+                .await;
+                assert_eq!(
+                    pipelined, streaming,
+                    "input={input:?} block_size={block_size} workers={workers}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn quoted_multiline_records_match_streaming() {
+        let input =
+            b"id,note\r\n1,\"a\r\nb\"\r\n\r\n2,\"say \"\"hi\"\"\"\r\n3,plain\r\n\n4,\"x,y\"";
+        assert_matches(input, &["id", "note"], &CsvOptions::default()).await;
+        assert_matches(b"", &[], &CsvOptions::default()).await;
+        assert_matches(b"\n\n", &["id"], &CsvOptions::default()).await;
+        assert_matches(b"id,note", &["id"], &CsvOptions::default()).await;
+    }
+
+    #[tokio::test]
+    async fn skipped_rows_and_budgets_match_streaming() {
+        let input = b"a,b\n1,2\n3\n4,5,6\n\n7,8\n9\n10,11\n";
+        for error_policy in [
+            CsvErrorPolicy::FailFast,
+            CsvErrorPolicy::Skip,
+            CsvErrorPolicy::SkipUpTo(1),
+            CsvErrorPolicy::SkipUpToPercent(40),
+        ] {
+            for flexible in [false, true] {
+                let options = CsvOptions {
+                    flexible,
+                    error_policy,
+                    ..CsvOptions::default()
+                };
+                assert_matches(input, &["b"], &options).await;
+            }
+        }
+        let capped = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            max_diagnostics: 1,
+            ..CsvOptions::default()
+        };
+        assert_matches(input, &["b"], &capped).await;
+    }
+
+    #[tokio::test]
+    async fn custom_dialects_and_headerless_input_match_streaming() {
+        let escaped = CsvOptions {
+            delimiter: b';',
+            terminator: CsvTerminator::Any(b'|'),
+            quote: b'\'',
+            escape: Some(b'\\'),
+            double_quote: false,
+            trim: crate::CsvTrim::All,
+            ..CsvOptions::default()
+        };
+        assert_matches(b" a ; b |'x\\'|y';2||3;'4|'|5", &["a"], &escaped).await;
+
+        let headerless = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        assert_matches(b"\n1,2\n3\n4,5\n", &[], &headerless).await;
+    }
+
+    #[tokio::test]
+    async fn byte_order_marks_only_start_the_stream() {
+        let input = b"\xef\xbb\xbfa,b\n\xef\xbb\xbf1,2\n\xef\xbb\xbf3\n";
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        assert_matches(input, &[], &options).await;
+        let crlf = b"a\r\n\xef\xbb\xbf1\r\n\xef\xbb\xbf2\r\n";
+        assert_matches(crlf, &[], &options).await;
+        let newline = CsvOptions {
+            terminator: CsvTerminator::Any(b'\n'),
+            ..options
+        };
+        assert_matches(input, &[], &newline).await;
+    }
+
+    #[tokio::test]
+    async fn header_errors_match_streaming() {
+        assert_matches(b"a,b\n1,2\n", &["c"], &CsvOptions::default()).await;
+        assert_matches(b"a,\xff\n1,2\n", &[], &CsvOptions::default()).await;
+        let headerless = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            ..CsvOptions::default()
+        };
+        assert_matches(b"1,2\n", &["a"], &headerless).await;
+    }
+
+    /// Yields its input in small reads, then fails.
+    struct FailingReader {
+        input: Cursor<Vec<u8>>,
+    }
+
+    impl AsyncRead for FailingReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let position = self.input.position() as usize;
+            if position == self.input.get_ref().len() {
+                return Poll::Ready(Err(io::Error::other("connection reset")));
+            }
+            let end = (position + 5.min(buf.remaining())).min(self.input.get_ref().len());
+            buf.put_slice(&self.input.get_ref()[position..end]);
+            self.input.set_position(end as u64);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn read_failures_follow_the_records_before_them() {
+        for (input, error_policy) in [
+            (&b"a,b\n1,2\n3,4\n5,"[..], CsvErrorPolicy::FailFast),
+            (&b"a,b\n1,2\n3\n5,"[..], CsvErrorPolicy::FailFast),
+            (&b"a,b\n1,2\n3\n5,"[..], CsvErrorPolicy::Skip),
+        ] {
+            let options = CsvOptions {
+                error_policy,
+                ..CsvOptions::default()
+            };
+            let failing = || FailingReader {
+                input: Cursor::new(input.to_vec()),
+            };
+            let streaming = summarize_csv_stream(failing(), &[], &options).await;
+            for block_size in BLOCK_SIZES {
+                let pipelined = summarize_csv_stream_pipelined(
+                    failing(),
+                    &[],
+                    &options,
+                    &config(block_size, 2),
+                )
+                .await;
+                assert_eq!(format!("{pipelined:?}"), format!("{streaming:?}"));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn compressed_transcoded_input_matches_streaming() {
+        use async_compression::tokio::write::ZstdEncoder;
+        use tokio::io::AsyncWriteExt;
+
+        let mut plain = b"city,temp\n".to_vec();
+        for index in 0..2_000 {
+            plain.extend_from_slice(b"M\xfcnchen ");
+            plain.extend_from_slice(format!("{index},{}\n", index % 7).as_bytes());
+        }
+        plain.extend_from_slice(b"Z\xfcrich\n");
+        let mut encoder = ZstdEncoder::new(Vec::new());
+        encoder.write_all(&plain).await.expect("compress");
+        encoder.shutdown().await.expect("finish zstd stream");
+        let compressed = encoder.into_inner();
+        let meta = CsvMeta {
+            name_hint: "weather.csv.zst".to_string(),
+            charset: encoding_rs::WINDOWS_1252,
+            ..CsvMeta::default()
+        };
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+
+        let (reader, _) =
+            build_csv_reader(Cursor::new(compressed.clone()), meta.clone()).expect("build reader");
+        let streaming = summarize_csv_stream(reader, &["city"], &options)
+            .await
+            .expect("summarize stream");
+        let (reader, _) = build_csv_reader(Cursor::new(compressed), meta).expect("build reader");
+        let pipelined =
+            summarize_csv_stream_pipelined(reader, &["city"], &options, &config(4_096, 4))
+                .await
+                .expect("summarize pipelined");
+
+        assert_eq!(pipelined, streaming);
+        assert_eq!(pipelined.row_count, 2_000);
+        assert_eq!(pipelined.rejected_rows, 1);
+    }
+
+    #[tokio::test]
+    async fn path_errors_name_the_file() {
+        use std::io::Write;
+
+        let mut file = tempfile::Builder::new()
+            .suffix(".csv")
+            .tempfile()
+            .expect("create fixture");
+        file.write_all(b"a,b\n1,2\n3\n").expect("write fixture");
+        let streaming = crate::summarize_csv_path(file.path(), &[], &CsvOptions::default())
+            .await
+            .map(|(summary, _)| summary);
+        let pipelined = summarize_csv_path_pipelined(
+            file.path(),
+            &[],
+            &CsvOptions::default(),
+            &CsvPipelineConfig::default(),
+        )
+        .await
+        .map(|(summary, _)| summary);
+
+        assert_eq!(format!("{pipelined:?}"), format!("{streaming:?}"));
+        assert!(pipelined.expect_err("ragged row").source_name().is_some());
+    }
+
+    #[test]
+    fn empty_blocks_are_rejected() {
+        let error = runtime()
+            .block_on(summarize_csv_stream_pipelined(
+                Cursor::new(b"a\n1\n"),
+                &[],
+                &CsvOptions::default(),
+                &config(0, 1),
+            ))
+            .expect_err("blocks cannot be empty");
+        assert!(matches!(error, CsvIngestError::UnsupportedDialect(_)));
+    }
+
+    fn input_strategy() -> impl Strategy<Value = Vec<u8>> {
+        let byte = prop_oneof![
+            6 => Just(b'a'),
+            3 => Just(b','),
+            2 => Just(b'"'),
+            2 => Just(b'\n'),
+            1 => Just(b'\r'),
+            1 => Just(b'\\'),
+            1 => Just(b';'),
+            1 => Just(0xef),
+            1 => Just(0xbb),
+            1 => Just(0xbf),
+        ];
+        prop::collection::vec(byte, 0..80)
+    }
+
+    fn options_strategy() -> impl Strategy<Value = CsvOptions> {
+        (
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            // Other terminators leave `csv_core` line counts dependent on buffering.
+            prop_oneof![Just(CsvTerminator::CrLf), Just(CsvTerminator::Any(b'\n'))],
+            prop_oneof![
+                Just(CsvErrorPolicy::FailFast),
+                Just(CsvErrorPolicy::Skip),
+                Just(CsvErrorPolicy::SkipUpTo(2)),
+            ],
+        )
+            .prop_map(
+                |(headers, flexible, quoting, escape, terminator, error_policy)| CsvOptions {
+                    headers: if headers {
+                        CsvHeaderMode::Present
+                    } else {
+                        CsvHeaderMode::Absent
+                    },
+                    flexible,
+                    quoting,
+                    escape: escape.then_some(b'\\'),
+                    double_quote: !escape,
+                    terminator,
+                    error_policy,
+                    ..CsvOptions::default()
+                },
+            )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig {
+            cases: 256,
+            max_shrink_iters: 4_096,
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn generated_input_matches_streaming(
+            input in input_strategy(),
+            options in options_strategy(),
+            block_size in 1usize..12,
+            workers in 1usize..4,
+        ) {
+            let required: &[&str] = if options.headers == CsvHeaderMode::Present {
+                &["a"]
+            } else {
+                &[]
+            };
+            let (streaming, pipelined) = runtime().block_on(outcomes(
+                &input,
+                required,
+                &options,
+                &config(block_size, workers),
+            ));
+            prop_assert_eq!(pipelined, streaming);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{ByteRecord, CsvIngestError, CsvResult};
+
+/// One column kept by a [`CsvProjection`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CsvSelector {
+    /// The first column with this header.
+    Header(String),
+    /// The column at this zero-based index.
+    Index(usize),
+}
+
+impl From<&str> for CsvSelector {
+    fn from(header: &str) -> Self {
+        Self::Header(header.to_string())
+    }
+}
+
+impl From<String> for CsvSelector {
+    fn from(header: String) -> Self {
+        Self::Header(header)
+    }
+}
+
+impl From<usize> for CsvSelector {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+/// The columns to keep from each record, in output order.
+///
+/// A projected record contains only the selected fields, in selection order;
+/// a column may be selected more than once. Selected columns are validated like
+/// required headers: an absent header returns [`CsvIngestError::MissingHeader`]
+/// and a record that ends before a selected column returns
+/// [`CsvIngestError::MissingRequiredField`]. Required headers and schemas still
+/// apply to the full record.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvProjection {
+    pub columns: Vec<CsvSelector>,
+}
+
+impl CsvProjection {
+    /// Select `columns` by header name or index.
+    pub fn new<C: Into<CsvSelector>>(columns: impl IntoIterator<Item = C>) -> Self {
+        Self {
+            columns: columns.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// A projection resolved to field indices.
+pub(crate) struct CompiledProjection {
+    columns: Vec<(usize, String)>,
+}
+
+impl CompiledProjection {
+    /// Resolve `projection` against `headers`, or against bare indices when
+    /// `headers` is `None` because the input has no header record.
+    pub(crate) fn new<H: AsRef<[u8]>>(
+        projection: &CsvProjection,
+        headers: Option<&[H]>,
+    ) -> CsvResult<Self> {
+        let columns = projection
+            .columns
+            .iter()
+            .map(|selector| match (selector, headers) {
+                (CsvSelector::Header(name), Some(headers)) => headers
+                    .iter()
+                    .position(|header| header.as_ref() == name.as_bytes())
+                    .map(|index| (index, name.clone()))
+                    .ok_or_else(|| CsvIngestError::MissingHeader(name.clone())),
+                (CsvSelector::Header(_), None) => Err(CsvIngestError::UnsupportedDialect(
+                    "columns cannot be selected by header when headers are absent".to_string(),
+                )),
+                (CsvSelector::Index(index), Some(headers)) => headers
+                    .get(*index)
+                    .map(|header| {
+                        (
+                            *index,
+                            String::from_utf8_lossy(header.as_ref()).into_owned(),
+                        )
+                    })
+                    .ok_or_else(|| CsvIngestError::MissingHeader(format!("column {index}"))),
+                (CsvSelector::Index(index), None) => Ok((*index, format!("column {index}"))),
+            })
+            .collect::<CsvResult<_>>()?;
+        Ok(Self { columns })
+    }
+
+    /// Return each selected field index with the name used in errors, in output order.
+    pub(crate) fn columns(&self) -> &[(usize, String)] {
+        &self.columns
+    }
+
+    /// Return the source index of the projected field at `field`.
+    pub(crate) fn source_index(&self, field: usize) -> Option<usize> {
+        self.columns.get(field).map(|(index, _)| *index)
+    }
+
+    /// Replace `target` with the selected fields of `source`, keeping its position.
+    pub(crate) fn project(&self, source: &ByteRecord, target: &mut ByteRecord) {
+        target.clear();
+        for (index, _) in &self.columns {
+            target.push_field(source.get(*index).unwrap_or_default());
+        }
+        target.set_position(source.position().cloned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    #[test]
+    fn selectors_resolve_names_and_indices_in_selection_order() {
+        let headers = ["sku", "qty", "sku"];
+        let projection = CsvProjection::new([
+            CsvSelector::from(2),
+            CsvSelector::from("sku"),
+            CsvSelector::from("qty".to_string()),
+        ]);
+        let compiled =
+            CompiledProjection::new(&projection, Some(&headers[..])).expect("resolve projection");
+
+        assert_eq!(
+            compiled.columns(),
+            [
+                (2, "sku".to_string()),
+                (0, "sku".to_string()),
+                (1, "qty".to_string()),
+            ]
+        );
+        assert_eq!(compiled.source_index(2), Some(1));
+        assert_eq!(compiled.source_index(3), None);
+    }
+
+    #[test]
+    fn unresolvable_selectors_fail() {
+        let headers = ["sku"];
+        let missing = |selector: CsvSelector| {
+            CompiledProjection::new(&CsvProjection::new([selector]), Some(&headers[..]))
+                .err()
+                .expect("selector must fail")
+        };
+        assert!(
+            matches!(missing("qty".into()), CsvIngestError::MissingHeader(name) if name == "qty")
+        );
+        assert!(
+            matches!(missing(1.into()), CsvIngestError::MissingHeader(name) if name == "column 1")
+        );
+
+        let headerless = CompiledProjection::new::<&str>(&CsvProjection::new(["sku"]), None);
+        assert!(matches!(
+            headerless,
+            Err(CsvIngestError::UnsupportedDialect(_))
+        ));
+        let compiled = CompiledProjection::new::<&str>(&CsvProjection::new([4]), None)
+            .expect("indices resolve without headers");
+        assert_eq!(compiled.columns(), [(4, "column 4".to_string())]);
+    }
+
+    #[test]
+    fn projected_records_are_compact_and_keep_their_position() {
+        let compiled =
+            CompiledProjection::new(&CsvProjection::new([2, 0, 2]), Some(&["a", "b", "c"][..]))
+                .expect("resolve projection");
+        let mut source = ByteRecord::from(vec!["1", "2", "3"]);
+        source.set_position(Some(Position::new().set_byte(9).set_record(1).clone()));
+        let mut target = ByteRecord::from(vec!["stale", "fields", "here", "too"]);
+
+        compiled.project(&source, &mut target);
+
+        assert_eq!(target, ByteRecord::from(vec!["3", "1", "3"]));
+        assert_eq!(target.position().map(Position::byte), Some(9));
+    }
+}
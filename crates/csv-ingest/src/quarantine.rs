@@ -0,0 +1,311 @@
+use crate::{CsvIngestError, Position};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Receives rows rejected by a skipping [`crate::CsvErrorPolicy`].
+///
+/// `raw` is the record exactly as it appeared in the parsed input, without its
+/// record terminator. Rows are delivered in input order. With
+/// [`crate::CsvErrorPolicy::FailFast`] no row is rejected, so nothing is
+/// delivered.
+pub trait CsvQuarantine {
+    /// Store one rejected record.
+    fn quarantine(
+        &mut self,
+        raw: &[u8],
+        position: Option<&Position>,
+        error: &CsvIngestError,
+    ) -> io::Result<()>;
+
+    /// Flush buffered rows once the input has been read.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<Q: CsvQuarantine + ?Sized> CsvQuarantine for &mut Q {
+    fn quarantine(
+        &mut self,
+        raw: &[u8],
+        position: Option<&Position>,
+        error: &CsvIngestError,
+    ) -> io::Result<()> {
+        (**self).quarantine(raw, position, error)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+/// A [`CsvQuarantine`] that writes rejected rows as CSV.
+///
+/// The output has the columns `row,byte,line,record,error,raw`. Position
+/// columns are empty when the position is unknown, and `raw` holds the original
+/// record bytes as a quoted field.
+pub struct CsvQuarantineWriter<W: Write> {
+    writer: W,
+}
+
+impl CsvQuarantineWriter<BufWriter<File>> {
+    /// Create or truncate a quarantine file at `path`.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> CsvQuarantineWriter<W> {
+    /// Write the header record and return a writer for rejected rows.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(b"row,byte,line,record,error,raw\n")?;
+        Ok(Self { writer })
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> CsvQuarantine for CsvQuarantineWriter<W> {
+    fn quarantine(
+        &mut self,
+        raw: &[u8],
+        position: Option<&Position>,
+        error: &CsvIngestError,
+    ) -> io::Result<()> {
+        if let Some(row) = error.row() {
+            write!(self.writer, "{row}")?;
+        }
+        match position {
+            Some(position) => write!(
+                self.writer,
+                ",{},{},{},",
+                position.byte(),
+                position.line(),
+                position.record()
+            )?,
+            None => self.writer.write_all(b",,,,")?,
+        }
+        write_quoted(&mut self.writer, error.to_string().as_bytes())?;
+        self.writer.write_all(b",")?;
+        write_quoted(&mut self.writer, raw)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn write_quoted<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    for (index, part) in bytes.split(|&byte| byte == b'"').enumerate() {
+        if index > 0 {
+            writer.write_all(b"\"\"")?;
+        }
+        writer.write_all(part)?;
+    }
+    writer.write_all(b"\"")
+}
+
+/// Remove the terminators `csv_async` includes around a record's byte range.
+pub(crate) fn trim_record(raw: &[u8], is_terminator: impl Fn(u8) -> bool) -> &[u8] {
+    let start = raw
+        .iter()
+        .position(|&byte| !is_terminator(byte))
+        .unwrap_or(raw.len());
+    let end = raw
+        .iter()
+        .rposition(|&byte| !is_terminator(byte))
+        .map_or(start, |index| index + 1);
+    &raw[start..end]
+}
+
+/// Retains the bytes read from a source since a given offset so a rejected
+/// record's original bytes can be quarantined.
+///
+/// Recording starts enabled so bytes buffered while reading the header are
+/// retained. It is turned off when no quarantine is installed.
+pub(crate) struct TapReader<R> {
+    inner: R,
+    recording: bool,
+    buffer: Vec<u8>,
+    /// The index in `buffer` of the first retained byte. Discarded bytes are
+    /// removed only once they fill half the buffer, so each byte is moved at
+    /// most once on average.
+    head: usize,
+    /// The absolute source offset of `buffer[head]`.
+    buffer_start: u64,
+    /// The number of retained bytes moved by compaction.
+    #[cfg(test)]
+    moved: usize,
+}
+
+impl<R> TapReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            recording: true,
+            buffer: Vec::new(),
+            head: 0,
+            buffer_start: 0,
+            #[cfg(test)]
+            moved: 0,
+        }
+    }
+
+    pub(crate) fn stop_recording(&mut self) {
+        self.recording = false;
+        self.buffer = Vec::new();
+        self.head = 0;
+    }
+
+    /// Drop retained bytes before the absolute source offset `offset`.
+    pub(crate) fn discard_before(&mut self, offset: u64) {
+        let discard = offset
+            .saturating_sub(self.buffer_start)
+            .min(self.retained().len() as u64);
+        self.head += discard as usize;
+        self.buffer_start += discard;
+        if self.head > self.buffer.len() / 2 {
+            #[cfg(test)]
+            {
+                self.moved += self.buffer.len() - self.head;
+            }
+            self.buffer.drain(..self.head);
+            self.head = 0;
+        }
+    }
+
+    fn retained(&self) -> &[u8] {
+        &self.buffer[self.head..]
+    }
+
+    /// Return the retained bytes in the absolute source range `start..end`.
+    ///
+    /// Bytes that were discarded or never recorded are omitted.
+    pub(crate) fn slice(&self, start: u64, end: u64) -> &[u8] {
+        let retained = self.retained();
+        let start = start
+            .saturating_sub(self.buffer_start)
+            .min(retained.len() as u64);
+        let end = end
+            .saturating_sub(self.buffer_start)
+            .min(retained.len() as u64);
+        &retained[start as usize..end.max(start) as usize]
+    }
+
+    #[cfg(test)]
+    pub(crate) fn moved(&self) -> usize {
+        self.moved
+    }
+
+    pub(crate) fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for TapReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if this.recording {
+            if let Poll::Ready(Ok(())) = &poll {
+                this.buffer.extend_from_slice(&buf.filled()[filled..]);
+            }
+        }
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    fn ragged_error(row: u64) -> CsvIngestError {
+        CsvIngestError::RaggedRow {
+            row: Some(row),
+            expected: 2,
+            actual: 1,
+            position: None,
+            source_name: None,
+        }
+    }
+
+    #[test]
+    fn writer_quotes_errors_and_raw_bytes() {
+        let mut writer = CsvQuarantineWriter::new(Vec::new()).expect("write header");
+        let position = Position::new()
+            .set_byte(10)
+            .set_line(2)
+            .set_record(1)
+            .clone();
+        writer
+            .quarantine(b"A,\"x\ny\"", Some(&position), &ragged_error(1))
+            .expect("quarantine positioned row");
+        writer
+            .quarantine(&[0xff], None, &ragged_error(2))
+            .expect("quarantine unpositioned row");
+        writer.finish().expect("flush");
+
+        let output = writer.into_inner().expect("into inner");
+        assert_eq!(
+            output,
+            b"row,byte,line,record,error,raw\n\
+1,10,2,1,\"Ragged row: got 1 fields, expected 2\",\"A,\"\"x\ny\"\"\"\n\
+2,,,,\"Ragged row: got 1 fields, expected 2\",\"\xff\"\n"
+        );
+    }
+
+    #[test]
+    fn file_writer_creates_a_csv_file() {
+        let directory = tempfile::tempdir().expect("create directory");
+        let path = directory.path().join("rejected.csv");
+        let mut writer = CsvQuarantineWriter::create(&path).expect("create quarantine");
+        let mut sink: &mut dyn CsvQuarantine = &mut writer;
+        (&mut sink)
+            .quarantine(b"B", None, &ragged_error(3))
+            .expect("quarantine row");
+        (&mut sink).finish().expect("finish");
+
+        let contents = std::fs::read(&path).expect("read quarantine");
+        assert!(contents.ends_with(b"3,,,,\"Ragged row: got 1 fields, expected 2\",\"B\"\n"));
+    }
+
+    #[test]
+    fn record_trimming_removes_only_surrounding_terminators() {
+        let is_crlf = |byte| matches!(byte, b'\r' | b'\n');
+        assert_eq!(trim_record(b"\n\nA,B\r", is_crlf), b"A,B");
+        assert_eq!(trim_record(b"\"a\nb\"\n", is_crlf), b"\"a\nb\"");
+        assert_eq!(trim_record(b"\r\n", is_crlf), b"");
+    }
+
+    #[tokio::test]
+    async fn tap_reader_retains_bytes_from_the_last_discard() {
+        let mut tap = TapReader::new(&b"abcdefgh"[..]);
+        let mut first = [0; 5];
+        tap.read_exact(&mut first).await.expect("read prefix");
+        tap.discard_before(2);
+        assert_eq!(tap.slice(2, 5), b"cde");
+        assert_eq!(tap.slice(0, 3), b"c");
+
+        tap.stop_recording();
+        let mut rest = Vec::new();
+        tap.read_to_end(&mut rest).await.expect("read rest");
+        assert_eq!(rest, b"fgh");
+        assert_eq!(tap.slice(2, 8), b"");
+        assert_eq!(tap.into_inner(), b"");
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{ByteRecord, CsvIngestError, CsvResult};
+
+/// A column read by a [`CsvRecord`] view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvField {
+    /// The header naming the column.
+    pub header: &'static str,
+    /// Whether the header must be present. `Option` fields are not required.
+    pub required: bool,
+}
+
+/// A borrowed view over the fields of one [`ByteRecord`].
+///
+/// Usually implemented with `#[derive(CsvRecord)]` from the `derive` feature.
+/// Resolve the view's columns once with [`CsvParser::resolve_columns`], then
+/// read views with [`CsvParser::next_view`] or [`CsvRecord::from_record`].
+///
+/// [`CsvParser::resolve_columns`]: crate::CsvParser::resolve_columns
+/// [`CsvParser::next_view`]: crate::CsvParser::next_view
+pub trait CsvRecord<'r>: Sized {
+    /// The view's columns, in field order.
+    const FIELDS: &'static [CsvField];
+
+    /// Build a view of `record` without copying its fields.
+    ///
+    /// `columns` must have been resolved for this type.
+    fn from_record(columns: &CsvColumns, record: &'r ByteRecord) -> CsvResult<Self>;
+}
+
+/// Column indices of a [`CsvRecord`] view, resolved once against a header record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvColumns {
+    fields: &'static [CsvField],
+    indices: Box<[Option<usize>]>,
+    /// The number of header records before the first data row.
+    pub(crate) header_records: u64,
+}
+
+impl CsvColumns {
+    /// Resolve `fields` to the first column with each header.
+    ///
+    /// `headers` is taken to be record zero, so errors number the record after
+    /// it as row 1. Returns [`CsvIngestError::MissingHeader`] for an absent required field.
+    pub fn resolve(fields: &'static [CsvField], headers: &ByteRecord) -> CsvResult<Self> {
+        let indices = fields
+            .iter()
+            .map(|field| {
+                let index = headers
+                    .iter()
+                    .position(|header| header == field.header.as_bytes());
+                if index.is_none() && field.required {
+                    return Err(CsvIngestError::MissingHeader(field.header.to_string()));
+                }
+                Ok(index)
+            })
+            .collect::<CsvResult<_>>()?;
+        Ok(Self {
+            fields,
+            indices,
+            header_records: 1,
+        })
+    }
+
+    /// Return the view's columns, in field order.
+    pub fn fields(&self) -> &'static [CsvField] {
+        self.fields
+    }
+
+    /// Return the column index of the view field at `field`, if the header exists.
+    pub fn index(&self, field: usize) -> Option<usize> {
+        self.indices.get(field).copied().flatten()
+    }
+
+    /// Return a required field's bytes.
+    ///
+    /// Returns [`CsvIngestError::MissingRequiredField`] if `record` ends before
+    /// the column. Its row is counted from the record's position;
+    /// [`CsvParser::next_view`] replaces it with the parser's own row count.
+    ///
+    /// [`CsvParser::next_view`]: crate::CsvParser::next_view
+    pub fn bytes<'r>(&self, record: &'r ByteRecord, field: usize) -> CsvResult<&'r [u8]> {
+        self.optional_bytes(record, field)?
+            .ok_or_else(|| CsvIngestError::MissingRequiredField {
+                row: self.row(record),
+                header: self.header(field),
+                position: record.position().cloned(),
+                source_name: None,
+            })
+    }
+
+    /// Return a required field as UTF-8 text.
+    ///
+    /// Returns [`CsvIngestError::InvalidValue`] naming the column if the value
+    /// is not valid UTF-8.
+    pub fn str<'r>(&self, record: &'r ByteRecord, field: usize) -> CsvResult<&'r str> {
+        self.text(record, field, self.bytes(record, field)?)
+    }
+
+    /// Return an optional field's bytes, or `None` if its header or value is absent.
+    ///
+    /// An empty value is `Some(b"")`.
+    pub fn optional_bytes<'r>(
+        &self,
+        record: &'r ByteRecord,
+        field: usize,
+    ) -> CsvResult<Option<&'r [u8]>> {
+        Ok(self.index(field).and_then(|index| record.get(index)))
+    }
+
+    /// Return an optional field as UTF-8 text.
+    pub fn optional_str<'r>(
+        &self,
+        record: &'r ByteRecord,
+        field: usize,
+    ) -> CsvResult<Option<&'r str>> {
+        self.optional_bytes(record, field)?
+            .map(|value| self.text(record, field, value))
+            .transpose()
+    }
+
+    fn text<'r>(&self, record: &ByteRecord, field: usize, value: &'r [u8]) -> CsvResult<&'r str> {
+        std::str::from_utf8(value).map_err(|_| CsvIngestError::InvalidValue {
+            row: self.row(record),
+            column: self.header(field),
+            expected: "UTF-8 string".to_string(),
+            position: record.position().cloned(),
+            source_name: None,
+        })
+    }
+
+    fn header(&self, field: usize) -> String {
+        self.fields
+            .get(field)
+            .map_or_else(String::new, |field| field.header.to_string())
+    }
+
+    /// Return the one-based data row of `record`, counted from its position.
+    fn row(&self, record: &ByteRecord) -> u64 {
+        record.position().map_or(0, |position| {
+            (position.record() + 1).saturating_sub(self.header_records)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    const FIELDS: &[CsvField] = &[
+        CsvField {
+            header: "sku",
+            required: true,
+        },
+        CsvField {
+            header: "note",
+            required: false,
+        },
+    ];
+
+    #[test]
+    fn resolves_first_matching_columns() {
+        let headers = ByteRecord::from(vec!["note", "sku", "sku"]);
+        let columns = CsvColumns::resolve(FIELDS, &headers).expect("resolve columns");
+
+        assert_eq!(columns.fields(), FIELDS);
+        assert_eq!(columns.index(0), Some(1));
+        assert_eq!(columns.index(1), Some(0));
+        assert_eq!(columns.index(2), None);
+    }
+
+    #[test]
+    fn missing_required_header_fails_but_optional_header_does_not() {
+        let error = CsvColumns::resolve(FIELDS, &ByteRecord::from(vec!["note"]))
+            .expect_err("missing required header must fail");
+        assert!(matches!(error, CsvIngestError::MissingHeader(header) if header == "sku"));
+
+        let columns = CsvColumns::resolve(FIELDS, &ByteRecord::from(vec!["sku"]))
+            .expect("optional header may be absent");
+        let record = ByteRecord::from(vec!["A"]);
+        assert_eq!(columns.optional_str(&record, 1).expect("read note"), None);
+    }
+
+    #[test]
+    fn field_accessors_borrow_from_the_record() {
+        let columns = CsvColumns::resolve(FIELDS, &ByteRecord::from(vec!["sku", "note"]))
+            .expect("resolve columns");
+        let record = ByteRecord::from(vec!["A", ""]);
+
+        assert_eq!(columns.str(&record, 0).expect("read sku"), "A");
+        assert_eq!(columns.bytes(&record, 0).expect("read sku"), b"A");
+        assert_eq!(
+            columns.optional_bytes(&record, 1).expect("read note"),
+            Some(&b""[..])
+        );
+
+        let short = ByteRecord::from(vec!["B"]);
+        assert_eq!(columns.optional_str(&short, 1).expect("read note"), None);
+    }
+
+    #[test]
+    fn accessors_report_short_rows_and_invalid_utf8() {
+        let columns = CsvColumns::resolve(FIELDS, &ByteRecord::from(vec!["note", "sku"]))
+            .expect("resolve columns");
+        let mut short = ByteRecord::from(vec!["only note"]);
+        short.set_position(Some(Position::new().set_record(4).clone()));
+
+        let error = columns.str(&short, 0).expect_err("short row must fail");
+        assert!(matches!(
+            error,
+            CsvIngestError::MissingRequiredField { row: 4, ref header, .. } if header == "sku"
+        ));
+
+        let mut invalid = ByteRecord::from(vec![&b"\xff"[..], b"A"]);
+        invalid.set_position(Some(Position::new().set_byte(20).set_record(2).clone()));
+        let error = columns
+            .optional_str(&invalid, 1)
+            .expect_err("invalid utf-8 must fail");
+        assert!(matches!(
+            error,
+            CsvIngestError::InvalidValue { row: 2, ref column, ref expected, .. }
+                if column == "note" && expected == "UTF-8 string"
+        ));
+        assert_eq!(error.position().map(Position::byte), Some(20));
+    }
+
+    #[test]
+    fn headerless_rows_count_the_first_record() {
+        let mut columns = CsvColumns::resolve(FIELDS, &ByteRecord::from(vec!["sku", "note"]))
+            .expect("resolve columns");
+        columns.header_records = 0;
+        let mut record = ByteRecord::from(vec![&b"\xff"[..]]);
+        record.set_position(Some(Position::new().set_record(0).clone()));
+
+        assert!(matches!(
+            columns.str(&record, 0),
+            Err(CsvIngestError::InvalidValue { row: 1, .. })
+        ));
+    }
+}
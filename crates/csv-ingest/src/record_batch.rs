@@ -0,0 +1,697 @@
+use crate::inference::SchemaInference;
+use crate::stream::Lent;
+use crate::{
+    ByteRecord, CsvColumnType, CsvHeaderMode, CsvIngestError, CsvIngestSummary, CsvParser,
+    CsvResult, Position,
+};
+use arrow::array::{Array, ArrayRef, BinaryArray, BinaryBuilder, StringArray};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use futures_core::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tokio::io::AsyncRead;
+
+/// How records are converted into Arrow [`RecordBatch`]es.
+#[derive(Debug, Clone)]
+pub struct CsvArrowOptions {
+    /// The most rows in one batch.
+    pub batch_size: usize,
+    /// The output columns and their types.
+    ///
+    /// Fields are matched to columns by header name, or by position when
+    /// headers are absent, and a field whose column is missing from a short
+    /// row is null. When unset, the schema is inferred from the first
+    /// `batch_size` records: every field is nullable, types are those of
+    /// [`CsvColumnType`], and headerless columns are named `column_0`,
+    /// `column_1`, and so on.
+    pub schema: Option<SchemaRef>,
+    /// Field values, such as `NULL` or `\\N`, that are read as null in every
+    /// column. They are compared with the field after trimming.
+    pub null_tokens: Vec<String>,
+}
+
+impl Default for CsvArrowOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 8_192,
+            schema: None,
+            null_tokens: Vec::new(),
+        }
+    }
+}
+
+impl CsvArrowOptions {
+    pub(crate) fn validate(&self) -> CsvResult<()> {
+        if self.batch_size == 0 {
+            return Err(CsvIngestError::UnsupportedDialect(
+                "record batches must hold at least one row".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Record batches read by `fast_local_record_batches`, in file order.
+#[derive(Debug, Clone)]
+pub struct CsvRecordBatches {
+    pub schema: SchemaRef,
+    pub batches: Vec<RecordBatch>,
+    pub summary: CsvIngestSummary,
+}
+
+/// Reads [`RecordBatch`]es from a [`CsvParser`].
+///
+/// Created by [`CsvParser::into_record_batches`]. Records are validated,
+/// filtered, and projected by the parser as usual, so the parser's counts and
+/// diagnostics remain available through [`CsvRecordBatchReader::parser`].
+pub struct CsvRecordBatchReader<R> {
+    parser: CsvParser<R>,
+    builder: BatchBuilder,
+    batch_size: usize,
+    /// Records read to infer the schema that have not been returned yet.
+    sampled: std::vec::IntoIter<(ByteRecord, u64)>,
+    record: ByteRecord,
+}
+
+/// A stream of the [`RecordBatch`]es of a [`CsvRecordBatchReader`].
+///
+/// Created by [`CsvRecordBatchReader::into_stream`]. The parser's counts and
+/// diagnostics remain available through [`CsvRecordBatchStream::parser`].
+pub struct CsvRecordBatchStream<'r, R> {
+    reader: Lent<'r, Box<CsvRecordBatchReader<R>>, CsvResult<Option<RecordBatch>>>,
+    schema: SchemaRef,
+    done: bool,
+}
+
+impl<R> CsvParser<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    /// Read the remaining records as Arrow record batches.
+    ///
+    /// Empty fields are null unless their column is a string or binary type,
+    /// and fields equal to one of `options.null_tokens` are always null.
+    /// A value that cannot be converted to its column's type returns
+    /// [`CsvIngestError::InvalidValue`]. When `options.schema` is unset, the
+    /// records used to infer it are read before this returns and are still
+    /// returned in the first batch.
+    pub async fn into_record_batches(
+        mut self,
+        options: &CsvArrowOptions,
+    ) -> CsvResult<CsvRecordBatchReader<R>> {
+        options.validate()?;
+        let headers = (self.options().headers == CsvHeaderMode::Present).then(|| {
+            self.headers()
+                .iter()
+                .map(|header| String::from_utf8_lossy(header).into_owned())
+                .collect::<Vec<_>>()
+        });
+        let mut sampled = Vec::new();
+        let schema = match &options.schema {
+            Some(schema) => Arc::clone(schema),
+            None => {
+                while sampled.len() < options.batch_size {
+                    let mut record = ByteRecord::new();
+                    if !self.read_record(&mut record).await? {
+                        break;
+                    }
+                    sampled.push((record, self.rows_scanned()));
+                }
+                infer_schema(
+                    headers.as_deref(),
+                    sampled.iter().map(|(record, _)| record),
+                    &options.null_tokens,
+                )
+            }
+        };
+        let indices = resolve_fields(&schema, headers.as_deref())?;
+        Ok(CsvRecordBatchReader {
+            parser: self,
+            builder: BatchBuilder::new(schema, indices, &options.null_tokens),
+            batch_size: options.batch_size,
+            sampled: sampled.into_iter(),
+            record: ByteRecord::new(),
+        })
+    }
+}
+
+impl<R> CsvRecordBatchReader<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    /// Return the schema of every batch.
+    pub fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.builder.schema)
+    }
+
+    /// Read the next batch of up to `batch_size` rows, or `None` at the end of input.
+    pub async fn next_batch(&mut self) -> CsvResult<Option<RecordBatch>> {
+        while self.builder.len() < self.batch_size {
+            if let Some((record, row)) = self.sampled.next() {
+                self.builder
+                    .push(|index| record.get(index), row, record.position().cloned());
+                continue;
+            }
+            if !self.parser.read_record(&mut self.record).await? {
+                break;
+            }
+            let row = self.parser.rows_scanned();
+            let record = &self.record;
+            self.builder
+                .push(|index| record.get(index), row, record.position().cloned());
+        }
+        if self.builder.len() == 0 {
+            return Ok(None);
+        }
+        self.builder
+            .finish()
+            .map(Some)
+            .map_err(|error| error.with_source_name(self.parser.source_name()))
+    }
+
+    /// Return the parser, for its counts and diagnostics.
+    pub fn parser(&self) -> &CsvParser<R> {
+        &self.parser
+    }
+
+    /// Unwrap the reader and return its parser.
+    ///
+    /// Records read to infer the schema but not yet returned are discarded.
+    pub fn into_parser(self) -> CsvParser<R> {
+        self.parser
+    }
+
+    /// Turn the reader into a stream of the batches
+    /// [`CsvRecordBatchReader::next_batch`] would return. An error is yielded
+    /// unchanged and ends the stream.
+    pub fn into_stream<'r>(self) -> CsvRecordBatchStream<'r, R>
+    where
+        R: 'r,
+    {
+        CsvRecordBatchStream {
+            schema: self.schema(),
+            reader: Lent::new(Box::new(self)),
+            done: false,
+        }
+    }
+}
+
+impl<R> CsvRecordBatchStream<'_, R> {
+    /// Return the schema of every batch.
+    pub fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    /// Return the parser, for its counts and diagnostics.
+    ///
+    /// Returns `None` only while a batch is being read, after polling the
+    /// stream returned [`Poll::Pending`] and before it yields the batch.
+    pub fn parser(&self) -> Option<&CsvParser<R>> {
+        self.reader.get().map(|reader| &reader.parser)
+    }
+
+    /// Unwrap the stream and return its parser, for example to call
+    /// [`CsvParser::into_summary`].
+    ///
+    /// Returns `None`, dropping the parser, if a batch is being read.
+    pub fn into_parser(self) -> Option<CsvParser<R>> {
+        self.reader.into_inner().map(|reader| reader.parser)
+    }
+}
+
+impl<'r, R> Stream for CsvRecordBatchStream<'r, R>
+where
+    R: AsyncRead + Unpin + Send + 'r,
+{
+    type Item = CsvResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let batch = ready!(this.reader.poll_read(cx, |mut reader| {
+            Box::pin(async move {
+                let batch = reader.next_batch().await;
+                (reader, batch)
+            })
+        }));
+        let batch = batch.transpose();
+        this.done = !matches!(batch, Some(Ok(_)));
+        Poll::Ready(batch)
+    }
+}
+
+/// Infer a nullable Arrow schema from records with `headers`, or with
+/// `column_N` names sized to the widest record when headers are absent.
+/// Null tokens are inferred like empty values.
+pub(crate) fn infer_schema<'r>(
+    headers: Option<&[String]>,
+    sample: impl IntoIterator<Item = &'r ByteRecord> + Clone,
+    null_tokens: &[String],
+) -> SchemaRef {
+    let names = headers.map_or_else(
+        || {
+            let width = sample.clone().into_iter().map(ByteRecord::len).max();
+            (0..width.unwrap_or_default())
+                .map(|index| format!("column_{index}"))
+                .collect()
+        },
+        <[String]>::to_vec,
+    );
+    let mut inference = SchemaInference::new(names.iter().map(String::as_bytes));
+    for record in sample {
+        if null_tokens.is_empty() {
+            inference.observe(record);
+            continue;
+        }
+        let record: ByteRecord = record
+            .iter()
+            .map(|field| {
+                if is_null_token(null_tokens, field) {
+                    &[][..]
+                } else {
+                    field
+                }
+            })
+            .collect();
+        inference.observe(&record);
+    }
+    let fields: Vec<_> = inference
+        .finish(0)
+        .columns
+        .into_iter()
+        .map(|column| Field::new(column.name, arrow_type(column.column_type), true))
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+fn is_null_token(null_tokens: &[String], value: &[u8]) -> bool {
+    null_tokens.iter().any(|token| token.as_bytes() == value)
+}
+
+fn arrow_type(column_type: CsvColumnType) -> DataType {
+    match column_type {
+        CsvColumnType::Integer => DataType::Int64,
+        CsvColumnType::Float => DataType::Float64,
+        CsvColumnType::Boolean => DataType::Boolean,
+        CsvColumnType::Date => DataType::Date32,
+        CsvColumnType::Timestamp => {
+            DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+        }
+        CsvColumnType::String => DataType::Utf8,
+    }
+}
+
+/// Resolve each field of `schema` to a column index, by name against
+/// `headers` or by position when headers are absent.
+pub(crate) fn resolve_fields(schema: &Schema, headers: Option<&[String]>) -> CsvResult<Vec<usize>> {
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(position, field)| match headers {
+            Some(headers) => headers
+                .iter()
+                .position(|header| header == field.name())
+                .ok_or_else(|| CsvIngestError::MissingHeader(field.name().clone())),
+            None => Ok(position),
+        })
+        .collect()
+}
+
+/// Buffers the raw values of each output column until a batch is finished.
+pub(crate) struct BatchBuilder {
+    schema: SchemaRef,
+    columns: Vec<ColumnBuilder>,
+    null_tokens: Vec<String>,
+    /// The row number and position of each buffered row, for errors.
+    rows: Vec<(u64, Option<Position>)>,
+}
+
+struct ColumnBuilder {
+    index: usize,
+    values: BinaryBuilder,
+    empty_is_null: bool,
+}
+
+impl BatchBuilder {
+    /// Build batches of `schema`, reading each field from the column at the same position in `indices`.
+    pub(crate) fn new(schema: SchemaRef, indices: Vec<usize>, null_tokens: &[String]) -> Self {
+        let columns = indices
+            .into_iter()
+            .zip(schema.fields())
+            .map(|(index, field)| ColumnBuilder {
+                index,
+                values: BinaryBuilder::new(),
+                empty_is_null: !matches!(
+                    field.data_type(),
+                    DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary
+                ),
+            })
+            .collect();
+        Self {
+            schema,
+            columns,
+            null_tokens: null_tokens.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Buffer one row, whose fields are returned by `field`.
+    pub(crate) fn push<'f>(
+        &mut self,
+        field: impl Fn(usize) -> Option<&'f [u8]>,
+        row: u64,
+        position: Option<Position>,
+    ) {
+        for column in &mut self.columns {
+            let value = field(column.index).filter(|value| {
+                !(value.is_empty() && column.empty_is_null
+                    || is_null_token(&self.null_tokens, value))
+            });
+            column.values.append_option(value);
+        }
+        self.rows.push((row, position));
+    }
+
+    /// Convert the buffered rows into a batch and start a new one.
+    pub(crate) fn finish(&mut self) -> CsvResult<RecordBatch> {
+        let rows = std::mem::take(&mut self.rows);
+        let columns = self
+            .columns
+            .iter_mut()
+            .zip(self.schema.fields())
+            .map(|(column, field)| convert(column.values.finish(), field, &rows))
+            .collect::<CsvResult<Vec<_>>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        Ok(RecordBatch::try_new_with_options(
+            Arc::clone(&self.schema),
+            columns,
+            &options,
+        )?)
+    }
+}
+
+/// Convert raw values to `field`'s type, locating the first value that fails.
+fn convert(
+    values: BinaryArray,
+    field: &Field,
+    rows: &[(u64, Option<Position>)],
+) -> CsvResult<ArrayRef> {
+    let data_type = field.data_type();
+    let invalid = |index: usize, expected: String| {
+        let (row, position) = rows[index].clone();
+        CsvIngestError::InvalidValue {
+            row,
+            column: field.name().clone(),
+            expected,
+            position,
+            source_name: None,
+        }
+    };
+    let array: ArrayRef = if *data_type == DataType::Binary {
+        Arc::new(values)
+    } else {
+        let strings = StringArray::try_from_binary(values.clone()).map_err(|error| {
+            (0..values.len())
+                .find(|&index| {
+                    values.is_valid(index) && std::str::from_utf8(values.value(index)).is_err()
+                })
+                .map_or(CsvIngestError::Arrow(error), |index| {
+                    invalid(index, data_type.to_string())
+                })
+        })?;
+        if *data_type == DataType::Utf8 {
+            Arc::new(strings)
+        } else {
+            let options = CastOptions {
+                safe: false,
+                ..CastOptions::default()
+            };
+            cast_with_options(&strings, data_type, &options).map_err(|error| {
+                (0..strings.len())
+                    .find(|&index| {
+                        strings.is_valid(index)
+                            && cast_with_options(&strings.slice(index, 1), data_type, &options)
+                                .is_err()
+                    })
+                    .map_or(CsvIngestError::Arrow(error), |index| {
+                        invalid(index, data_type.to_string())
+                    })
+            })?
+        }
+    };
+    if !field.is_nullable() {
+        if let Some(index) = (0..array.len()).find(|&index| array.is_null(index)) {
+            return Err(invalid(index, format!("non-null {data_type}")));
+        }
+    }
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvOptions;
+    use arrow::array::{BooleanArray, Date32Array, Float64Array, Int64Array};
+    use std::io::Cursor;
+
+    async fn parser(input: &'static str, options: &CsvOptions) -> CsvParser<Cursor<&'static [u8]>> {
+        CsvParser::from_reader(Cursor::new(input.as_bytes()), &[], options)
+            .await
+            .expect("construct parser")
+    }
+
+    #[tokio::test]
+    async fn inferred_schemas_cover_every_column_type() {
+        let input = "id,price,active,day,at,name\n\
+                     1,2.5,true,2024-01-31,2024-01-31T10:00:00Z,a\n\
+                     2,,false,2024-02-01,2024-02-01 11:30:00,\n";
+        let mut reader = parser(input, &CsvOptions::default())
+            .await
+            .into_record_batches(&CsvArrowOptions::default())
+            .await
+            .expect("infer schema");
+
+        let types: Vec<_> = reader
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| (field.name().clone(), field.data_type().clone()))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("id".to_string(), DataType::Int64),
+                ("price".to_string(), DataType::Float64),
+                ("active".to_string(), DataType::Boolean),
+                ("day".to_string(), DataType::Date32),
+                (
+                    "at".to_string(),
+                    DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+                ),
+                ("name".to_string(), DataType::Utf8),
+            ]
+        );
+
+        let batch = reader.next_batch().await.expect("read").expect("batch");
+        assert_eq!(batch.num_rows(), 2);
+        let price = batch.column(1).as_any().downcast_ref::<Float64Array>();
+        assert_eq!(price.map(|price| price.null_count()), Some(1));
+        let active = batch.column(2).as_any().downcast_ref::<BooleanArray>();
+        assert_eq!(active.map(|active| active.value(1)), Some(false));
+        let day = batch.column(3).as_any().downcast_ref::<Date32Array>();
+        assert_eq!(day.map(|day| day.value(0)), Some(19_753));
+        let name = batch.column(5).as_any().downcast_ref::<StringArray>();
+        assert_eq!(name.map(|name| name.value(1)), Some(""));
+        assert!(reader.next_batch().await.expect("read").is_none());
+    }
+
+    #[tokio::test]
+    async fn streams_yield_batches_and_keep_the_parser() {
+        use futures::{StreamExt, TryStreamExt};
+
+        let options = CsvOptions {
+            error_policy: crate::CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let arrow = CsvArrowOptions {
+            batch_size: 2,
+            ..CsvArrowOptions::default()
+        };
+        let mut batches = parser("n\n1\n2\n3,x\n4\n5\n", &options)
+            .await
+            .into_record_batches(&arrow)
+            .await
+            .expect("infer schema")
+            .into_stream();
+        assert_eq!(batches.schema().field(0).data_type(), &DataType::Int64);
+        let mut rows = Vec::new();
+        while let Some(batch) = batches.try_next().await.expect("read batch") {
+            rows.push(batch.num_rows());
+        }
+        assert_eq!(rows, [2, 2]);
+        assert!(batches.next().await.is_none());
+        assert_eq!(batches.parser().map(CsvParser::rejected_rows), Some(1));
+        let summary = batches
+            .into_parser()
+            .expect("no read is pending")
+            .into_summary()
+            .await
+            .expect("summarize");
+        assert_eq!(summary.row_count, 4);
+
+        let errors: Vec<_> = parser("n\n1\nx\n", &CsvOptions::default())
+            .await
+            .into_record_batches(&CsvArrowOptions {
+                schema: Some(Arc::new(Schema::new(vec![Field::new(
+                    "n",
+                    DataType::Int64,
+                    true,
+                )]))),
+                ..CsvArrowOptions::default()
+            })
+            .await
+            .expect("resolve schema")
+            .into_stream()
+            .collect()
+            .await;
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0],
+            Err(CsvIngestError::InvalidValue { row: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn supplied_schemas_select_columns_by_name_in_batches() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("qty", DataType::Int64, false),
+            Field::new("sku", DataType::Utf8, true),
+        ]));
+        let options = CsvArrowOptions {
+            batch_size: 2,
+            schema: Some(schema),
+            ..CsvArrowOptions::default()
+        };
+        let mut reader = parser("sku,qty\na,1\nb,2\nc,3\n", &CsvOptions::default())
+            .await
+            .into_record_batches(&options)
+            .await
+            .expect("resolve schema");
+
+        let mut quantities = Vec::new();
+        while let Some(batch) = reader.next_batch().await.expect("read") {
+            assert_eq!(batch.schema().field(0).name(), "qty");
+            let qty = batch.column(0).as_any().downcast_ref::<Int64Array>();
+            quantities.push(qty.map(|qty| qty.values().to_vec()).unwrap_or_default());
+        }
+        assert_eq!(quantities, [vec![1, 2], vec![3]]);
+        assert_eq!(reader.parser().records_read(), 3);
+
+        let missing = Arc::new(Schema::new(vec![Field::new("price", DataType::Utf8, true)]));
+        let error = parser("sku,qty\na,1\n", &CsvOptions::default())
+            .await
+            .into_record_batches(&CsvArrowOptions {
+                schema: Some(missing),
+                ..CsvArrowOptions::default()
+            })
+            .await
+            .err()
+            .expect("unknown field must fail");
+        assert!(matches!(error, CsvIngestError::MissingHeader(name) if name == "price"));
+    }
+
+    #[tokio::test]
+    async fn unconvertible_values_report_their_row_and_position() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("sku", DataType::Utf8, true),
+            Field::new("qty", DataType::Int64, false),
+        ]));
+        let options = CsvArrowOptions {
+            batch_size: 8,
+            schema: Some(Arc::clone(&schema)),
+            ..CsvArrowOptions::default()
+        };
+        let mut reader = parser("sku,qty\na,1\nb,x\n", &CsvOptions::default())
+            .await
+            .with_source_name("orders.csv")
+            .into_record_batches(&options)
+            .await
+            .expect("resolve schema");
+
+        let error = reader.next_batch().await.expect_err("x is not an integer");
+        assert_eq!(error.to_string(), "Row 2 column 'qty' is not a valid Int64");
+        assert_eq!(error.position().map(Position::byte), Some(12));
+        assert_eq!(error.source_name(), Some("orders.csv"));
+
+        let mut reader = parser("sku,qty\na,\n", &CsvOptions::default())
+            .await
+            .into_record_batches(&options)
+            .await
+            .expect("resolve schema");
+        let error = reader.next_batch().await.expect_err("qty is not nullable");
+        assert!(matches!(
+            error,
+            CsvIngestError::InvalidValue { row: 1, ref expected, .. } if expected == "non-null Int64"
+        ));
+    }
+
+    #[tokio::test]
+    async fn null_tokens_are_null_in_every_column_and_ignored_by_inference() {
+        let options = CsvArrowOptions {
+            null_tokens: vec!["NA".to_string(), "\\N".to_string()],
+            ..CsvArrowOptions::default()
+        };
+        let mut reader = parser("id,name\n1,NA\n\\N,b\n", &CsvOptions::default())
+            .await
+            .into_record_batches(&options)
+            .await
+            .expect("infer schema");
+
+        assert_eq!(reader.schema().field(0).data_type(), &DataType::Int64);
+        let batch = reader.next_batch().await.expect("read").expect("batch");
+        let id = batch.column(0).as_any().downcast_ref::<Int64Array>();
+        assert_eq!(id.map(|id| id.iter().collect()), Some(vec![Some(1), None]));
+        let name = batch.column(1).as_any().downcast_ref::<StringArray>();
+        assert_eq!(
+            name.map(|name| name.iter().collect()),
+            Some(vec![None, Some("b")])
+        );
+    }
+
+    #[tokio::test]
+    async fn headerless_columns_are_named_and_matched_by_position() {
+        let options = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            flexible: true,
+            ..CsvOptions::default()
+        };
+        let mut reader = parser("1,a\n2\n", &options)
+            .await
+            .into_record_batches(&CsvArrowOptions::default())
+            .await
+            .expect("infer schema");
+
+        let schema = reader.schema();
+        let names: Vec<_> = schema.fields().iter().map(|field| field.name()).collect();
+        assert_eq!(names, ["column_0", "column_1"]);
+        let batch = reader.next_batch().await.expect("read").expect("batch");
+        assert_eq!(batch.column(1).null_count(), 1);
+
+        let error = CsvArrowOptions {
+            batch_size: 0,
+            ..CsvArrowOptions::default()
+        }
+        .validate();
+        assert!(matches!(error, Err(CsvIngestError::UnsupportedDialect(_))));
+    }
+}
@@ -0,0 +1,617 @@
+use crate::{CsvIngestError, CsvResult};
+use std::fmt;
+
+/// Typed validation rules for named columns.
+///
+/// Build a schema in code or, with the `serde` feature, deserialize one:
+///
+/// ```json
+/// {"columns": [
+///   {"name": "qty", "rules": [{"rule": "not_empty"}, {"rule": "integer", "min": 0}]},
+///   {"name": "status", "rules": [{"rule": "one_of", "values": ["open", "closed"]}]}
+/// ]}
+/// ```
+///
+/// Every schema column must be present in the header and is validated like a
+/// required header. Rules apply to the field after trimming.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvSchema {
+    pub columns: Vec<CsvColumnSchema>,
+}
+
+/// The rules for one column of a [`CsvSchema`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvColumnSchema {
+    /// The header naming the column.
+    pub name: String,
+    /// Rules every value must satisfy, checked in order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: Vec<CsvRule>,
+}
+
+impl CsvColumnSchema {
+    /// Describe the column `name` with `rules`.
+    pub fn new(name: impl Into<String>, rules: impl IntoIterator<Item = CsvRule>) -> Self {
+        Self {
+            name: name.into(),
+            rules: rules.into_iter().collect(),
+        }
+    }
+}
+
+/// A constraint on the values of a [`CsvSchema`] column.
+///
+/// Empty values are null: they violate only [`CsvRule::NotEmpty`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "rule", rename_all = "snake_case")
+)]
+pub enum CsvRule {
+    /// The value must not be empty.
+    NotEmpty,
+    /// A base-10 `i64` with an optional sign, within the inclusive bounds.
+    Integer {
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        min: Option<i64>,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        max: Option<i64>,
+    },
+    /// A base-10 decimal such as `-12`, `3.50`, or `.5`, without an exponent.
+    Decimal,
+    /// A base-10 floating-point number, optionally with an exponent, such as `1.5e-3`.
+    Float,
+    /// `true` or `false`, in any letter case.
+    Boolean,
+    /// A calendar date formatted as `YYYY-MM-DD`.
+    Date,
+    /// A date and time formatted as `YYYY-MM-DDTHH:MM:SS`, with `T` or a space
+    /// as separator, optional fractional seconds, and an optional `Z` or
+    /// `+HH:MM` offset.
+    Timestamp,
+    /// At most `max` characters, or bytes when the value is not UTF-8.
+    MaxWidth { max: usize },
+    /// A regular expression that must match the entire value. Compiling it
+    /// needs the default `schema_regex` feature and otherwise fails with
+    /// `CsvIngestError::InvalidSchema`.
+    Pattern { pattern: String },
+    /// The value must equal one of `values` exactly.
+    OneOf { values: Vec<String> },
+}
+
+impl fmt::Display for CsvRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEmpty => f.write_str("not empty"),
+            Self::Integer { min, max } => {
+                f.write_str("integer")?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, " in {min}..={max}"),
+                    (Some(min), None) => write!(f, " >= {min}"),
+                    (None, Some(max)) => write!(f, " <= {max}"),
+                    (None, None) => Ok(()),
+                }
+            }
+            Self::Decimal => f.write_str("decimal"),
+            Self::Float => f.write_str("float"),
+            Self::Boolean => f.write_str("boolean"),
+            Self::Date => f.write_str("date"),
+            Self::Timestamp => f.write_str("timestamp"),
+            Self::MaxWidth { max } => write!(f, "at most {max} characters"),
+            Self::Pattern { pattern } => write!(f, "pattern {pattern:?}"),
+            Self::OneOf { values } => write!(f, "one of {values:?}"),
+        }
+    }
+}
+
+/// A schema resolved against a header and ready to validate records.
+pub(crate) struct CompiledSchema {
+    columns: Vec<CompiledColumn>,
+}
+
+/// A schema column with its field index and compiled rules.
+pub(crate) struct CompiledColumn {
+    pub(crate) index: usize,
+    pub(crate) name: String,
+    rules: Vec<(CsvRule, Option<Matcher>)>,
+}
+
+/// The compiled form of a `Pattern` rule.
+#[cfg(feature = "schema_regex")]
+type Matcher = regex::bytes::Regex;
+
+/// Without `schema_regex`, no rule compiles to a matcher.
+#[cfg(not(feature = "schema_regex"))]
+type Matcher = std::convert::Infallible;
+
+#[cfg(feature = "schema_regex")]
+fn accepts(matcher: &Matcher, value: &[u8]) -> bool {
+    matcher.is_match(value)
+}
+
+#[cfg(not(feature = "schema_regex"))]
+fn accepts(matcher: &Matcher, _: &[u8]) -> bool {
+    match *matcher {}
+}
+
+impl CompiledSchema {
+    /// Resolve `schema` to the first column with each name and compile its rules.
+    pub(crate) fn new<H: AsRef<[u8]>>(schema: &CsvSchema, headers: &[H]) -> CsvResult<Self> {
+        let mut columns = schema
+            .columns
+            .iter()
+            .map(|column| {
+                let index = headers
+                    .iter()
+                    .position(|header| header.as_ref() == column.name.as_bytes())
+                    .ok_or_else(|| CsvIngestError::MissingHeader(column.name.clone()))?;
+                let rules = column
+                    .rules
+                    .iter()
+                    .map(|rule| Ok((rule.clone(), compile(rule)?)))
+                    .collect::<CsvResult<_>>()?;
+                Ok(CompiledColumn {
+                    index,
+                    name: column.name.clone(),
+                    rules,
+                })
+            })
+            .collect::<CsvResult<Vec<_>>>()?;
+        columns.sort_by_key(|column| column.index);
+        Ok(Self { columns })
+    }
+
+    /// Return the columns in field order.
+    pub(crate) fn columns(&self) -> &[CompiledColumn] {
+        &self.columns
+    }
+}
+
+impl CompiledColumn {
+    /// Check `value` against every rule, reporting the first violation for `row`.
+    pub(crate) fn validate(&self, value: &[u8], row: u64) -> CsvResult<()> {
+        let Some((rule, _)) = self
+            .rules
+            .iter()
+            .find(|(rule, regex)| !satisfies(rule, regex.as_ref(), value))
+        else {
+            return Ok(());
+        };
+        Err(CsvIngestError::SchemaViolation {
+            row,
+            column: self.name.clone(),
+            rule: Box::new(rule.clone()),
+            position: None,
+            source_name: None,
+        })
+    }
+}
+
+fn compile(rule: &CsvRule) -> CsvResult<Option<Matcher>> {
+    match rule {
+        #[cfg(feature = "schema_regex")]
+        CsvRule::Pattern { pattern } => Matcher::new(&format!("^(?:{pattern})$"))
+            .map(Some)
+            .map_err(|error| CsvIngestError::InvalidSchema(error.to_string())),
+        #[cfg(not(feature = "schema_regex"))]
+        CsvRule::Pattern { .. } => Err(CsvIngestError::InvalidSchema(
+            "pattern rules need the schema_regex feature".to_string(),
+        )),
+        _ => Ok(None),
+    }
+}
+
+fn satisfies(rule: &CsvRule, regex: Option<&Matcher>, value: &[u8]) -> bool {
+    if value.is_empty() {
+        return !matches!(rule, CsvRule::NotEmpty);
+    }
+    match rule {
+        CsvRule::NotEmpty => true,
+        CsvRule::Integer { min, max } => std::str::from_utf8(value)
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .is_some_and(|value| {
+                min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+            }),
+        CsvRule::Decimal => is_decimal(value),
+        CsvRule::Float => is_float(value),
+        CsvRule::Boolean => is_boolean(value),
+        CsvRule::Date => is_date(value),
+        CsvRule::Timestamp => is_timestamp(value),
+        CsvRule::MaxWidth { max } => width(value) <= *max,
+        CsvRule::Pattern { .. } => regex.is_some_and(|regex| accepts(regex, value)),
+        CsvRule::OneOf { values } => values.iter().any(|allowed| allowed.as_bytes() == value),
+    }
+}
+
+fn is_decimal(value: &[u8]) -> bool {
+    let unsigned = value.strip_prefix(b"+").or(value.strip_prefix(b"-"));
+    let digits = unsigned.unwrap_or(value);
+    let (whole, fraction) = match digits.iter().position(|&byte| byte == b'.') {
+        Some(point) => (&digits[..point], &digits[point + 1..]),
+        None => (digits, &b""[..]),
+    };
+    (!whole.is_empty() || !fraction.is_empty())
+        && whole.iter().all(u8::is_ascii_digit)
+        && fraction.iter().all(u8::is_ascii_digit)
+}
+
+pub(crate) fn is_integer(value: &[u8]) -> bool {
+    std::str::from_utf8(value).is_ok_and(|value| value.parse::<i64>().is_ok())
+}
+
+pub(crate) fn is_float(value: &[u8]) -> bool {
+    value
+        .iter()
+        .all(|byte| byte.is_ascii_digit() || b"+-.eE".contains(byte))
+        && std::str::from_utf8(value).is_ok_and(|value| value.parse::<f64>().is_ok())
+}
+
+pub(crate) fn is_boolean(value: &[u8]) -> bool {
+    value.eq_ignore_ascii_case(b"true") || value.eq_ignore_ascii_case(b"false")
+}
+
+pub(crate) fn is_date(value: &[u8]) -> bool {
+    let [y0, y1, y2, y3, b'-', m0, m1, b'-', d0, d1] = *value else {
+        return false;
+    };
+    let (Some(year), Some(month), Some(day)) = (
+        digits(&[y0, y1, y2, y3]),
+        digits(&[m0, m1]),
+        digits(&[d0, d1]),
+    ) else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+pub(crate) fn is_timestamp(value: &[u8]) -> bool {
+    if value.len() < 19 || !is_date(&value[..10]) || !matches!(value[10], b'T' | b' ') {
+        return false;
+    }
+    let time = &value[11..];
+    if !is_time(&time[..8]) {
+        return false;
+    }
+    let mut rest = &time[8..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let digits = fraction
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            return false;
+        }
+        rest = &fraction[digits..];
+    }
+    match rest {
+        [] | [b'Z'] => true,
+        [b'+' | b'-', h0, h1, b':', m0, m1] => matches!(
+            (digits(&[*h0, *h1]), digits(&[*m0, *m1])),
+            (Some(0..=23), Some(0..=59))
+        ),
+        _ => false,
+    }
+}
+
+/// Check `HH:MM:SS`, allowing a leap second.
+fn is_time(value: &[u8]) -> bool {
+    let [h0, h1, b':', m0, m1, b':', s0, s1] = *value else {
+        return false;
+    };
+    matches!(
+        (digits(&[h0, h1]), digits(&[m0, m1]), digits(&[s0, s1])),
+        (Some(0..=23), Some(0..=59), Some(0..=60))
+    )
+}
+
+fn digits(value: &[u8]) -> Option<u32> {
+    value.iter().try_fold(0u32, |total, byte| {
+        byte.is_ascii_digit()
+            .then(|| total * 10 + u32::from(byte - b'0'))
+    })
+}
+
+/// Count characters in UTF-8 values and bytes otherwise.
+pub(crate) fn width(value: &[u8]) -> usize {
+    std::str::from_utf8(value).map_or(value.len(), |value| value.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(rules: impl IntoIterator<Item = CsvRule>) -> CompiledColumn {
+        let schema = CsvSchema {
+            columns: vec![CsvColumnSchema::new("value", rules)],
+        };
+        CompiledSchema::new(&schema, &["value"])
+            .expect("compile schema")
+            .columns
+            .remove(0)
+    }
+
+    fn violated(column: &CompiledColumn, value: &[u8]) -> Option<CsvRule> {
+        match column.validate(value, 7) {
+            Ok(()) => None,
+            Err(CsvIngestError::SchemaViolation { row, rule, .. }) => {
+                assert_eq!(row, 7);
+                Some(*rule)
+            }
+            Err(error) => panic!("unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn integer_rules_check_syntax_and_bounds() {
+        let rule = CsvRule::Integer {
+            min: Some(0),
+            max: Some(10),
+        };
+        let column = column([rule.clone()]);
+
+        for valid in [&b"0"[..], b"+7", b"10", b""] {
+            assert_eq!(violated(&column, valid), None, "{valid:?}");
+        }
+        for invalid in [
+            &b"-1"[..],
+            b"11",
+            b"1.0",
+            b"x",
+            b"\xff",
+            b"99999999999999999999",
+        ] {
+            assert_eq!(
+                violated(&column, invalid),
+                Some(rule.clone()),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decimal_rules_accept_plain_decimals_only() {
+        let column = column([CsvRule::Decimal]);
+
+        for valid in [&b"12"[..], b"-3.50", b"+.5", b"4."] {
+            assert_eq!(violated(&column, valid), None, "{valid:?}");
+        }
+        for invalid in [&b"."[..], b"-", b"1e3", b"1.2.3", b" 1"] {
+            assert_eq!(
+                violated(&column, invalid),
+                Some(CsvRule::Decimal),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn typed_rules_accept_only_their_formats() {
+        type Values = &'static [&'static [u8]];
+        let cases: [(CsvRule, Values, Values); 5] = [
+            (
+                CsvRule::Float,
+                &[b"1", b"-2.5", b"1e-3", b".5E+2"],
+                &[b"inf", b"NaN", b"1,5", b"e"],
+            ),
+            (
+                CsvRule::Boolean,
+                &[b"true", b"FALSE", b"True"],
+                &[b"yes", b"1", b"t"],
+            ),
+            (
+                CsvRule::Date,
+                &[b"2024-02-29", b"1999-12-31"],
+                &[
+                    b"2023-02-29",
+                    b"2024-13-01",
+                    b"2024-04-31",
+                    b"2024-1-01",
+                    b"20240101",
+                ],
+            ),
+            (
+                CsvRule::Timestamp,
+                &[
+                    b"2024-01-02T03:04:05",
+                    b"2024-01-02 03:04:05.123456Z",
+                    b"2024-12-31T23:59:60-08:00",
+                ],
+                &[
+                    b"2024-01-02",
+                    b"2024-01-02T24:00:00",
+                    b"2024-01-02T03:04:05.",
+                    b"2024-01-02T03:04:05+5:00",
+                    b"2024-01-02T03:04:05+05:60",
+                    b"2024-01-02x03:04:05",
+                ],
+            ),
+            (
+                CsvRule::MaxWidth { max: 3 },
+                &[b"abc", b"\xc3\xa9t\xc3\xa9"],
+                &[b"abcd", b"\xff\xff\xff\xff"],
+            ),
+        ];
+
+        for (rule, valid, invalid) in cases {
+            let column = column([rule.clone()]);
+            for value in valid {
+                assert_eq!(violated(&column, value), None, "{rule}: {value:?}");
+            }
+            for value in invalid {
+                assert_eq!(
+                    violated(&column, value),
+                    Some(rule.clone()),
+                    "{rule}: {value:?}"
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "schema_regex")]
+    #[test]
+    fn pattern_rules_match_the_whole_value() {
+        let rule = CsvRule::Pattern {
+            pattern: "[A-Z]{3}-\\d+".to_string(),
+        };
+        let column = column([rule.clone()]);
+
+        assert_eq!(violated(&column, b"ABC-12"), None);
+        assert_eq!(violated(&column, b"xABC-12"), Some(rule.clone()));
+        assert_eq!(violated(&column, b"ABC-12x"), Some(rule.clone()));
+        assert_eq!(rule.to_string(), "pattern \"[A-Z]{3}-\\\\d+\"");
+    }
+
+    #[test]
+    fn the_first_violated_rule_is_reported() {
+        let one_of = CsvRule::OneOf {
+            values: vec!["open".to_string(), "closed".to_string()],
+        };
+        let column = column([CsvRule::NotEmpty, one_of.clone()]);
+
+        assert_eq!(violated(&column, b"open"), None);
+        assert_eq!(violated(&column, b""), Some(CsvRule::NotEmpty));
+        assert_eq!(violated(&column, b"Open"), Some(one_of));
+    }
+
+    #[test]
+    fn compilation_reports_missing_headers() {
+        let schema = CsvSchema {
+            columns: vec![CsvColumnSchema::new("b", []), CsvColumnSchema::new("a", [])],
+        };
+        let compiled = CompiledSchema::new(&schema, &["a", "b", "a"]).expect("compile schema");
+        let indices: Vec<_> = compiled
+            .columns()
+            .iter()
+            .map(|column| column.index)
+            .collect();
+        assert_eq!(indices, [0, 1]);
+
+        assert!(matches!(
+            CompiledSchema::new(&schema, &["a"]),
+            Err(CsvIngestError::MissingHeader(header)) if header == "b"
+        ));
+    }
+
+    #[test]
+    fn compilation_reports_invalid_patterns() {
+        let invalid = CsvSchema {
+            columns: vec![CsvColumnSchema::new(
+                "a",
+                [CsvRule::Pattern {
+                    pattern: "(".to_string(),
+                }],
+            )],
+        };
+        assert!(matches!(
+            CompiledSchema::new(&invalid, &["a"]),
+            Err(CsvIngestError::InvalidSchema(_))
+        ));
+    }
+
+    #[test]
+    fn rules_render_for_error_messages() {
+        let rendered: Vec<_> = [
+            CsvRule::NotEmpty,
+            CsvRule::Integer {
+                min: None,
+                max: None,
+            },
+            CsvRule::Integer {
+                min: Some(1),
+                max: Some(2),
+            },
+            CsvRule::Integer {
+                min: Some(0),
+                max: None,
+            },
+            CsvRule::Integer {
+                min: None,
+                max: Some(9),
+            },
+            CsvRule::Decimal,
+            CsvRule::Float,
+            CsvRule::Boolean,
+            CsvRule::Date,
+            CsvRule::Timestamp,
+            CsvRule::MaxWidth { max: 8 },
+            CsvRule::Pattern {
+                pattern: "a+".to_string(),
+            },
+            CsvRule::OneOf {
+                values: vec!["x".to_string()],
+            },
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+        assert_eq!(
+            rendered,
+            [
+                "not empty",
+                "integer",
+                "integer in 1..=2",
+                "integer >= 0",
+                "integer <= 9",
+                "decimal",
+                "float",
+                "boolean",
+                "date",
+                "timestamp",
+                "at most 8 characters",
+                "pattern \"a+\"",
+                "one of [\"x\"]",
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn schemas_round_trip_through_serde() {
+        let json = r#"{"columns":[{"name":"qty","rules":[{"rule":"not_empty"},{"rule":"integer","min":0}]},{"name":"status","rules":[{"rule":"one_of","values":["open","closed"]}]},{"name":"note"}]}"#;
+        let schema: CsvSchema = serde_json::from_str(json).expect("deserialize schema");
+
+        assert_eq!(
+            schema.columns[0],
+            CsvColumnSchema::new(
+                "qty",
+                [
+                    CsvRule::NotEmpty,
+                    CsvRule::Integer {
+                        min: Some(0),
+                        max: None,
+                    },
+                ],
+            )
+        );
+        assert!(schema.columns[2].rules.is_empty());
+        let round_trip: CsvSchema =
+            serde_json::from_str(&serde_json::to_string(&schema).expect("serialize schema"))
+                .expect("deserialize round trip");
+        assert_eq!(round_trip, schema);
+
+        let typed = vec![CsvRule::Timestamp, CsvRule::MaxWidth { max: 3 }];
+        assert_eq!(
+            serde_json::to_string(&typed).expect("serialize rules"),
+            r#"[{"rule":"timestamp"},{"rule":"max_width","max":3}]"#
+        );
+    }
+}
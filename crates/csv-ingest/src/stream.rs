@@ -0,0 +1,359 @@
+use crate::{ByteRecord, CsvIngestError, CsvParser, CsvResult};
+use futures_core::Stream;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::AsyncRead;
+
+/// A read that owns the stream's state until it finishes, then returns it.
+pub(crate) type Read<'r, S, T> = Pin<Box<dyn Future<Output = (S, T)> + Send + 'r>>;
+
+/// State that is lent to one read at a time.
+///
+/// A read borrows the parser across await points, so a stream cannot hold the
+/// parser and its pending read side by side. The state is moved into each read
+/// and handed back with the item, which keeps it reachable between items.
+pub(crate) struct Lent<'r, S, T> {
+    idle: Option<S>,
+    reading: Option<Read<'r, S, T>>,
+}
+
+impl<'r, S, T> Lent<'r, S, T> {
+    pub(crate) fn new(state: S) -> Self {
+        Self {
+            idle: Some(state),
+            reading: None,
+        }
+    }
+
+    /// Return the state, or `None` while a read is pending.
+    pub(crate) fn get(&self) -> Option<&S> {
+        self.idle.as_ref()
+    }
+
+    /// Return the state, or `None` if a read is pending.
+    pub(crate) fn into_inner(self) -> Option<S> {
+        self.idle
+    }
+
+    /// Poll the pending read, first starting one with `read` if none is.
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        read: impl FnOnce(S) -> Read<'r, S, T>,
+    ) -> Poll<T> {
+        if self.reading.is_none() {
+            let state = self.idle.take().expect("state is idle between reads");
+            self.reading = Some(read(state));
+        }
+        let reading = self.reading.as_mut().expect("a read is pending");
+        let (state, item) = ready!(reading.as_mut().poll(cx));
+        self.reading = None;
+        self.idle = Some(state);
+        Poll::Ready(item)
+    }
+}
+
+/// A stream of the records remaining in a [`CsvParser`].
+///
+/// Created by [`CsvParser::into_stream`]. The parser's counts and diagnostics
+/// remain available through [`CsvRecordStream::parser`].
+pub struct CsvRecordStream<'r, R> {
+    parser: Lent<'r, Box<CsvParser<R>>, Option<CsvResult<ByteRecord>>>,
+    done: bool,
+}
+
+/// A batch, and the error that ended it early, if any.
+type Batch = (Vec<ByteRecord>, Option<CsvIngestError>);
+
+/// A stream of batches of the records remaining in a [`CsvParser`].
+///
+/// Created by [`CsvParser::into_batch_stream`]. The parser's counts and
+/// diagnostics remain available through [`CsvBatchStream::parser`].
+pub struct CsvBatchStream<'r, R> {
+    parser: Lent<'r, Box<CsvParser<R>>, Batch>,
+    size: NonZeroUsize,
+    /// The error that ended a partial batch, yielded after it.
+    failed: Option<CsvIngestError>,
+    done: bool,
+}
+
+impl<R> CsvParser<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    /// Turn the parser into a stream of the remaining records.
+    ///
+    /// Records are the ones [`CsvParser::next_record`] would return, in order
+    /// and with their positions, so filtered and rejected rows are skipped and
+    /// each item is one more record read. An error is yielded unchanged and
+    /// ends the stream.
+    pub fn into_stream<'r>(self) -> CsvRecordStream<'r, R>
+    where
+        R: 'r,
+    {
+        CsvRecordStream {
+            parser: Lent::new(Box::new(self)),
+            done: false,
+        }
+    }
+
+    /// Turn the parser into a stream of the remaining records in batches of
+    /// up to `size`.
+    ///
+    /// Every batch but the last holds exactly `size` records, and the records
+    /// are those [`CsvParser::into_stream`] yields. When reading fails, the
+    /// records read before the error are yielded as a final short batch, then
+    /// the error, unchanged, ends the stream.
+    pub fn into_batch_stream<'r>(self, size: NonZeroUsize) -> CsvBatchStream<'r, R>
+    where
+        R: 'r,
+    {
+        CsvBatchStream {
+            parser: Lent::new(Box::new(self)),
+            size,
+            failed: None,
+            done: false,
+        }
+    }
+}
+
+impl<R> CsvRecordStream<'_, R> {
+    /// Return the parser, for its counts and diagnostics.
+    ///
+    /// Returns `None` only while a record is being read, after polling the
+    /// stream returned [`Poll::Pending`] and before it yields the record.
+    pub fn parser(&self) -> Option<&CsvParser<R>> {
+        self.parser.get().map(|parser| &**parser)
+    }
+
+    /// Unwrap the stream and return its parser, for example to call
+    /// [`CsvParser::into_summary`].
+    ///
+    /// Returns `None`, dropping the parser, if a record is being read.
+    pub fn into_parser(self) -> Option<CsvParser<R>> {
+        self.parser.into_inner().map(|parser| *parser)
+    }
+}
+
+impl<'r, R> Stream for CsvRecordStream<'r, R>
+where
+    R: AsyncRead + Unpin + Send + 'r,
+{
+    type Item = CsvResult<ByteRecord>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let item = ready!(this.parser.poll_read(cx, |mut parser| {
+            Box::pin(async move {
+                let item = parser.next_record().await.map(|record| record.cloned());
+                (parser, item.transpose())
+            })
+        }));
+        this.done = !matches!(item, Some(Ok(_)));
+        Poll::Ready(item)
+    }
+}
+
+impl<R> CsvBatchStream<'_, R> {
+    /// Return the parser, for its counts and diagnostics.
+    ///
+    /// Returns `None` only while a batch is being read, after polling the
+    /// stream returned [`Poll::Pending`] and before it yields the batch.
+    pub fn parser(&self) -> Option<&CsvParser<R>> {
+        self.parser.get().map(|parser| &**parser)
+    }
+
+    /// Unwrap the stream and return its parser, for example to call
+    /// [`CsvParser::into_summary`].
+    ///
+    /// Returns `None`, dropping the parser, if a batch is being read.
+    pub fn into_parser(self) -> Option<CsvParser<R>> {
+        self.parser.into_inner().map(|parser| *parser)
+    }
+}
+
+impl<'r, R> Stream for CsvBatchStream<'r, R>
+where
+    R: AsyncRead + Unpin + Send + 'r,
+{
+    type Item = CsvResult<Vec<ByteRecord>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(error) = this.failed.take() {
+            this.done = true;
+            return Poll::Ready(Some(Err(error)));
+        }
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let size = this.size.get();
+        let (batch, error) = ready!(this.parser.poll_read(cx, |mut parser| {
+            Box::pin(async move {
+                let mut batch = Vec::with_capacity(size.min(1024));
+                while batch.len() < size {
+                    match parser.next_record().await {
+                        Ok(Some(record)) => batch.push(record.clone()),
+                        Ok(None) => break,
+                        Err(error) => return (parser, (batch, Some(error))),
+                    }
+                }
+                (parser, (batch, None))
+            })
+        }));
+        this.done = batch.len() < size;
+        if batch.is_empty() {
+            this.done = true;
+            return Poll::Ready(error.map(Err));
+        }
+        this.failed = error;
+        Poll::Ready(Some(Ok(batch)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CsvErrorPolicy, CsvFilter, CsvOptions};
+    use futures::{StreamExt, TryStreamExt};
+    use std::io::Cursor;
+
+    async fn parser(input: &'static str, options: &CsvOptions) -> CsvParser<Cursor<&'static str>> {
+        CsvParser::from_reader(Cursor::new(input), &[], options)
+            .await
+            .expect("construct parser")
+    }
+
+    fn first_fields(records: &[ByteRecord]) -> Vec<&[u8]> {
+        records
+            .iter()
+            .map(|record| record.get(0).expect("first field"))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn records_match_next_record_including_skipped_rows() {
+        let input = "id,country\n1,US\n2,CA\n3\n4,US\n";
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let mut expected = parser(input, &options)
+            .await
+            .with_filter(CsvFilter::equals("country", "US"))
+            .expect("resolve filter");
+        let mut positions = Vec::new();
+        while let Some(record) = expected.next_record().await.expect("read record") {
+            positions.push(record.position().cloned());
+        }
+
+        let records: Vec<_> = parser(input, &options)
+            .await
+            .with_filter(CsvFilter::equals("country", "US"))
+            .expect("resolve filter")
+            .into_stream()
+            .try_collect()
+            .await
+            .expect("stream records");
+
+        assert_eq!(first_fields(&records), [b"1", b"4"]);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record.position().cloned())
+                .collect::<Vec<_>>(),
+            positions
+        );
+        assert_eq!(expected.records_read(), records.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn batches_hold_up_to_the_requested_size() {
+        let batches: Vec<Vec<ByteRecord>> = parser("n\n1\n2\n3\n4\n5\n", &CsvOptions::default())
+            .await
+            .into_batch_stream(NonZeroUsize::new(2).expect("nonzero size"))
+            .try_collect()
+            .await
+            .expect("stream batches");
+
+        let sizes: Vec<_> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [2, 2, 1]);
+        assert_eq!(
+            first_fields(&batches.concat()),
+            [b"1", b"2", b"3", b"4", b"5"]
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_end_the_stream_unchanged() {
+        let input = "a,b\n1,2\n3\n4,5\n";
+        let items: Vec<_> = parser(input, &CsvOptions::default())
+            .await
+            .into_stream()
+            .collect()
+            .await;
+        assert_eq!(items.len(), 2);
+        assert!(matches!(
+            items[1],
+            Err(CsvIngestError::RaggedRow { row: Some(2), .. })
+        ));
+
+        let batches: Vec<_> = parser(input, &CsvOptions::default())
+            .await
+            .into_batch_stream(NonZeroUsize::new(10).expect("nonzero size"))
+            .collect()
+            .await;
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].as_ref().map(Vec::len).ok(), Some(1));
+        assert!(matches!(
+            batches[1],
+            Err(CsvIngestError::RaggedRow { row: Some(2), .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn empty_input_yields_nothing() {
+        let mut batches = parser("a\n", &CsvOptions::default())
+            .await
+            .into_batch_stream(NonZeroUsize::new(4).expect("nonzero size"));
+        assert!(batches.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn parsers_keep_their_counts_after_streaming() {
+        let input = "id,country\n1,US\n2\n3,CA\n4,US\n";
+        let options = CsvOptions {
+            error_policy: CsvErrorPolicy::Skip,
+            ..CsvOptions::default()
+        };
+        let mut records = parser(input, &options).await.into_stream();
+        assert_eq!(records.parser().map(CsvParser::records_read), Some(0));
+        let first = records
+            .next()
+            .await
+            .expect("a record")
+            .expect("read record");
+        assert_eq!(first_fields(&[first]), [b"1"]);
+        assert_eq!(records.parser().map(CsvParser::records_read), Some(1));
+        while records.try_next().await.expect("read record").is_some() {}
+        let streamed = records.into_parser().expect("no read is pending");
+        assert_eq!(streamed.records_read(), 3);
+        assert_eq!(streamed.rejected_rows(), 1);
+        assert_eq!(streamed.diagnostics().len(), 1);
+        let summary = streamed.into_summary().await.expect("summarize");
+        assert_eq!(summary.row_count, 3);
+
+        let mut batches = parser(input, &options)
+            .await
+            .into_batch_stream(NonZeroUsize::new(2).expect("nonzero size"));
+        while batches.try_next().await.expect("read batch").is_some() {}
+        let batches = batches.parser().expect("no read is pending");
+        assert_eq!(batches.records_read(), 3);
+        assert_eq!(batches.rejected_rows(), 1);
+    }
+}
@@ -0,0 +1,566 @@
+use crate::schema::{is_float, is_integer};
+use crate::{ByteRecord, CsvIngestError, CsvOptions, CsvResult, CsvTerminator};
+use async_compression::tokio::write::{GzipEncoder, ZstdEncoder};
+use encoding_rs::{EncoderResult, Encoding};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Buffered output is handed to the underlying writer once it reaches this size.
+const FLUSH_THRESHOLD: usize = 64 * 1024;
+
+/// Which fields [`CsvWriter`] encloses in quotes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CsvQuoteStyle {
+    /// Quote only fields that contain the delimiter, the quote, or a record
+    /// terminator, and the lone field of a record holding one empty field.
+    #[default]
+    Minimal,
+    /// Quote every field.
+    Always,
+    /// Quote every field that is not an integer or decimal, as well as those
+    /// [`CsvQuoteStyle::Minimal`] would quote.
+    NonNumeric,
+}
+
+/// The compression [`CsvWriter`] applies to its output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CsvWriteCompression {
+    #[default]
+    None,
+    /// Gzip at its default level.
+    Gzip,
+    /// Zstandard at its default level.
+    Zstd,
+}
+
+/// How [`CsvWriter`] encodes records, beyond the dialect in [`CsvOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvWriteOptions {
+    pub quote_style: CsvQuoteStyle,
+    pub compression: CsvWriteCompression,
+    /// The character encoding of the output (defaults to UTF-8).
+    ///
+    /// Fields are expected to be UTF-8 and are transcoded when this is another
+    /// encoding. UTF-16 cannot be written.
+    pub charset: &'static Encoding,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        Self {
+            quote_style: CsvQuoteStyle::default(),
+            compression: CsvWriteCompression::default(),
+            charset: encoding_rs::UTF_8,
+        }
+    }
+}
+
+/// Writes records to an [`AsyncWrite`] in the dialect of a [`CsvOptions`].
+///
+/// The delimiter, record terminator, quote, escape, and `double_quote` settings
+/// are honored so that a [`crate::CsvParser`] built with the same options reads
+/// the records back. A [`CsvTerminator::CrLf`] dialect writes `\r\n`. Header
+/// mode, trimming, and the error policy only affect reading; write a header
+/// record with [`CsvWriter::write_record`] like any other.
+///
+/// Output is buffered, compressed, and transcoded as configured by
+/// [`CsvWriteOptions`]. It is complete only once [`CsvWriter::finish`] returns.
+pub struct CsvWriter<W> {
+    sink: Sink<W>,
+    options: CsvOptions,
+    quote_style: CsvQuoteStyle,
+    encoder: Option<encoding_rs::Encoder>,
+    record: Vec<u8>,
+    buffer: Vec<u8>,
+    records_written: u64,
+}
+
+impl<W> CsvWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Validate the dialect and output settings and wrap `writer`.
+    pub fn new(writer: W, options: &CsvOptions, write: &CsvWriteOptions) -> CsvResult<Self> {
+        options.validate()?;
+        if options.quoting && !options.double_quote && options.escape.is_none() {
+            return Err(CsvIngestError::UnsupportedDialect(
+                "writing quoted fields requires double_quote or an escape".to_string(),
+            ));
+        }
+        let encoder = if write.charset == encoding_rs::UTF_8 {
+            None
+        } else {
+            if write.charset.output_encoding() != write.charset {
+                return Err(CsvIngestError::UnsupportedDialect(format!(
+                    "{} cannot be used as an output charset",
+                    write.charset.name()
+                )));
+            }
+            if !dialect_bytes(options).all(|byte| byte.is_ascii()) {
+                return Err(CsvIngestError::UnsupportedDialect(
+                    "transcoded output requires ASCII delimiter, quote, escape, and terminator"
+                        .to_string(),
+                ));
+            }
+            Some(write.charset.new_encoder())
+        };
+        let sink = match write.compression {
+            CsvWriteCompression::None => Sink::Plain(writer),
+            CsvWriteCompression::Gzip => Sink::Gzip(GzipEncoder::new(writer)),
+            CsvWriteCompression::Zstd => Sink::Zstd(ZstdEncoder::new(writer)),
+        };
+        Ok(Self {
+            sink,
+            options: *options,
+            quote_style: write.quote_style,
+            encoder,
+            record: Vec::new(),
+            buffer: Vec::with_capacity(FLUSH_THRESHOLD),
+            records_written: 0,
+        })
+    }
+
+    /// The number of records written so far.
+    pub fn records_written(&self) -> u64 {
+        self.records_written
+    }
+
+    /// Write one record of fields followed by the record terminator.
+    ///
+    /// Returns [`CsvIngestError::UnencodableOutput`] when a field cannot be
+    /// represented in the output charset, and
+    /// [`CsvIngestError::UnsupportedDialect`] when a field needs quotes but
+    /// quoting is disabled. Nothing of a rejected record is written.
+    pub async fn write_record<I, T>(&mut self, record: I) -> CsvResult<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.record.clear();
+        let mut fields = 0;
+        for field in record {
+            if fields > 0 {
+                self.record.push(self.options.delimiter);
+            }
+            self.encode_field(field.as_ref())?;
+            fields += 1;
+        }
+        if fields == 1 && self.record.is_empty() && self.options.quoting {
+            // A bare terminator would be read back as a blank line, not a record.
+            self.record.extend([self.options.quote; 2]);
+        }
+        match self.options.terminator {
+            CsvTerminator::CrLf => self.record.extend_from_slice(b"\r\n"),
+            CsvTerminator::Any(byte) => self.record.push(byte),
+        }
+        self.records_written += 1;
+
+        match &mut self.encoder {
+            None => self.buffer.extend_from_slice(&self.record),
+            Some(encoder) => {
+                let start = self.buffer.len();
+                let encoded = std::str::from_utf8(&self.record)
+                    .is_ok_and(|text| encode(encoder, text, &mut self.buffer, false));
+                if !encoded {
+                    self.buffer.truncate(start);
+                    // The escape sequences of a stateful encoding went with
+                    // the truncated bytes, so start over in its initial state.
+                    *encoder = encoder.encoding().new_encoder();
+                    let error = CsvIngestError::UnencodableOutput {
+                        record: self.records_written,
+                        encoding: encoder.encoding().name(),
+                    };
+                    self.records_written -= 1;
+                    return Err(error);
+                }
+            }
+        }
+        if self.buffer.len() >= FLUSH_THRESHOLD {
+            self.write_buffer().await?;
+        }
+        Ok(())
+    }
+
+    /// Write the fields of a parsed record.
+    pub async fn write_byte_record(&mut self, record: &ByteRecord) -> CsvResult<()> {
+        self.write_record(record.iter()).await
+    }
+
+    /// Write buffered records through to the underlying writer and flush it.
+    ///
+    /// Compressed output flushes the current block, which costs some
+    /// compression ratio when done often.
+    pub async fn flush(&mut self) -> CsvResult<()> {
+        self.write_buffer().await?;
+        self.sink.flush().await?;
+        Ok(())
+    }
+
+    /// Complete the output and return the underlying writer.
+    ///
+    /// Buffered records are written, the compressed stream is finished, and
+    /// the writer is shut down.
+    pub async fn finish(mut self) -> CsvResult<W> {
+        if let Some(encoder) = &mut self.encoder {
+            // Stateful encodings may need to return to their initial state.
+            encode(encoder, "", &mut self.buffer, true);
+        }
+        self.write_buffer().await?;
+        self.sink.shutdown().await?;
+        Ok(match self.sink {
+            Sink::Plain(writer) => writer,
+            Sink::Gzip(encoder) => encoder.into_inner(),
+            Sink::Zstd(encoder) => encoder.into_inner(),
+        })
+    }
+
+    async fn write_buffer(&mut self) -> CsvResult<()> {
+        self.sink.write_all(&self.buffer).await?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn encode_field(&mut self, field: &[u8]) -> CsvResult<()> {
+        let options = &self.options;
+        let separates = |byte: u8| {
+            byte == options.delimiter
+                || byte == b'\r'
+                || byte == b'\n'
+                || options.is_terminator(byte)
+        };
+        if !options.quoting {
+            if field.iter().any(|&byte| separates(byte)) {
+                return Err(CsvIngestError::UnsupportedDialect(format!(
+                    "record {} has a field that needs quotes, but quoting is disabled",
+                    self.records_written + 1
+                )));
+            }
+            self.record.extend_from_slice(field);
+            return Ok(());
+        }
+        let needs_quotes = field
+            .iter()
+            .any(|&byte| byte == options.quote || separates(byte));
+        let quoted = match self.quote_style {
+            CsvQuoteStyle::Minimal => needs_quotes,
+            CsvQuoteStyle::Always => true,
+            CsvQuoteStyle::NonNumeric => needs_quotes || !(is_integer(field) || is_float(field)),
+        };
+        if !quoted {
+            self.record.extend_from_slice(field);
+            return Ok(());
+        }
+
+        self.record.push(options.quote);
+        for &byte in field {
+            if byte == options.quote {
+                match options.escape {
+                    Some(escape) if !options.double_quote => self.record.push(escape),
+                    _ => self.record.push(options.quote),
+                }
+            } else if options.escape == Some(byte) {
+                // The reader treats an escape inside quotes as escaping the next byte.
+                self.record.push(byte);
+            }
+            self.record.push(byte);
+        }
+        self.record.push(options.quote);
+        Ok(())
+    }
+}
+
+/// The bytes a dialect writes between and around fields.
+fn dialect_bytes(options: &CsvOptions) -> impl Iterator<Item = u8> {
+    let terminator = match options.terminator {
+        CsvTerminator::CrLf => None,
+        CsvTerminator::Any(byte) => Some(byte),
+    };
+    [options.delimiter]
+        .into_iter()
+        .chain(options.quoting.then_some(options.quote))
+        .chain(options.escape)
+        .chain(terminator)
+}
+
+/// Append `text` in the encoder's charset, returning `false` at the first
+/// character the charset cannot represent.
+fn encode(encoder: &mut encoding_rs::Encoder, text: &str, out: &mut Vec<u8>, last: bool) -> bool {
+    let mut remaining = text;
+    loop {
+        out.reserve(remaining.len() + 16);
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(remaining, out, last);
+        remaining = &remaining[read..];
+        match result {
+            EncoderResult::InputEmpty => return true,
+            EncoderResult::OutputFull => continue,
+            EncoderResult::Unmappable(_) => return false,
+        }
+    }
+}
+
+/// The underlying writer, optionally behind a compressing encoder.
+enum Sink<W> {
+    Plain(W),
+    Gzip(GzipEncoder<W>),
+    Zstd(ZstdEncoder<W>),
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for Sink<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(writer) => Pin::new(writer).poll_write(cx, buf),
+            Self::Gzip(encoder) => Pin::new(encoder).poll_write(cx, buf),
+            Self::Zstd(encoder) => Pin::new(encoder).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(writer) => Pin::new(writer).poll_flush(cx),
+            Self::Gzip(encoder) => Pin::new(encoder).poll_flush(cx),
+            Self::Zstd(encoder) => Pin::new(encoder).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(writer) => Pin::new(writer).poll_shutdown(cx),
+            Self::Gzip(encoder) => Pin::new(encoder).poll_shutdown(cx),
+            Self::Zstd(encoder) => Pin::new(encoder).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_csv_reader, CsvHeaderMode, CsvMeta, CsvParser};
+    use std::io::Cursor;
+
+    async fn written(
+        records: &[&[&str]],
+        options: &CsvOptions,
+        write: &CsvWriteOptions,
+    ) -> CsvResult<Vec<u8>> {
+        let mut writer = CsvWriter::new(Vec::new(), options, write)?;
+        for record in records {
+            writer.write_record(*record).await?;
+        }
+        writer.finish().await
+    }
+
+    async fn read_back(input: Vec<u8>, options: &CsvOptions, meta: CsvMeta) -> Vec<Vec<String>> {
+        let (reader, _) = build_csv_reader(Cursor::new(input), meta).expect("build reader");
+        let options = CsvOptions {
+            headers: CsvHeaderMode::Absent,
+            flexible: true,
+            ..*options
+        };
+        let mut parser = CsvParser::from_reader(reader, &[], &options)
+            .await
+            .expect("construct parser");
+        let mut records = Vec::new();
+        while let Some(record) = parser.next_record().await.expect("read record") {
+            records.push(
+                record
+                    .iter()
+                    .map(|field| String::from_utf8_lossy(field).into_owned())
+                    .collect(),
+            );
+        }
+        records
+    }
+
+    #[tokio::test]
+    async fn quote_styles_choose_which_fields_are_quoted() {
+        let records: &[&[&str]] = &[&["id", "note"], &["1", "a,b"], &["2.5", "say \"hi\""]];
+        let mut outputs = Vec::new();
+        for quote_style in [
+            CsvQuoteStyle::Minimal,
+            CsvQuoteStyle::Always,
+            CsvQuoteStyle::NonNumeric,
+        ] {
+            let write = CsvWriteOptions {
+                quote_style,
+                ..CsvWriteOptions::default()
+            };
+            let output = written(records, &CsvOptions::default(), &write)
+                .await
+                .expect("write records");
+            outputs.push(String::from_utf8(output).expect("UTF-8 output"));
+        }
+
+        assert_eq!(
+            outputs,
+            [
+                "id,note\r\n1,\"a,b\"\r\n2.5,\"say \"\"hi\"\"\"\r\n",
+                "\"id\",\"note\"\r\n\"1\",\"a,b\"\r\n\"2.5\",\"say \"\"hi\"\"\"\r\n",
+                "\"id\",\"note\"\r\n1,\"a,b\"\r\n2.5,\"say \"\"hi\"\"\"\r\n",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_dialects_round_trip_through_the_parser() {
+        let options = CsvOptions {
+            delimiter: b';',
+            terminator: CsvTerminator::Any(b'|'),
+            quote: b'\'',
+            escape: Some(b'\\'),
+            double_quote: false,
+            ..CsvOptions::default()
+        };
+        let records: &[&[&str]] = &[
+            &["it's", "a;b", "x|y"],
+            &["back\\slash", "", "plain"],
+            &[""],
+        ];
+        let output = written(records, &options, &CsvWriteOptions::default())
+            .await
+            .expect("write records");
+
+        assert_eq!(
+            output,
+            b"'it\\'s';'a;b';'x|y'|back\\slash;;plain|''|".to_vec()
+        );
+        assert_eq!(
+            read_back(output, &options, CsvMeta::default()).await,
+            records
+                .iter()
+                .map(|record| record.iter().map(|field| field.to_string()).collect())
+                .collect::<Vec<Vec<_>>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn compressed_transcoded_output_reads_back() {
+        let records: &[&[&str]] = &[&["city", "temp"], &["München", "4"], &["Zürich", "-1"]];
+        for (compression, name_hint) in [
+            (CsvWriteCompression::Gzip, "out.csv.gz"),
+            (CsvWriteCompression::Zstd, "out.csv.zst"),
+        ] {
+            let write = CsvWriteOptions {
+                compression,
+                charset: encoding_rs::WINDOWS_1252,
+                ..CsvWriteOptions::default()
+            };
+            let output = written(records, &CsvOptions::default(), &write)
+                .await
+                .expect("write records");
+            let meta = CsvMeta {
+                name_hint: name_hint.to_string(),
+                charset: encoding_rs::WINDOWS_1252,
+                ..CsvMeta::default()
+            };
+
+            let read = read_back(output, &CsvOptions::default(), meta).await;
+            assert_eq!(read[1], ["München", "4"]);
+            assert_eq!(read[2], ["Zürich", "-1"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn unencodable_records_are_rejected_whole() {
+        let write = CsvWriteOptions {
+            charset: encoding_rs::WINDOWS_1252,
+            ..CsvWriteOptions::default()
+        };
+        let mut writer =
+            CsvWriter::new(Vec::new(), &CsvOptions::default(), &write).expect("valid options");
+        writer.write_record(["a"]).await.expect("encodable");
+        let error = writer
+            .write_record(["b", "東京"])
+            .await
+            .expect_err("windows-1252 has no kanji");
+        assert!(matches!(
+            error,
+            CsvIngestError::UnencodableOutput {
+                record: 2,
+                encoding: "windows-1252"
+            }
+        ));
+        writer.write_record(["c"]).await.expect("encodable");
+
+        assert_eq!(writer.records_written(), 2);
+        assert_eq!(writer.finish().await.expect("finish"), b"a\r\nc\r\n");
+    }
+
+    #[tokio::test]
+    async fn stateful_encoders_restart_after_a_rejected_record() {
+        let write = CsvWriteOptions {
+            charset: encoding_rs::ISO_2022_JP,
+            ..CsvWriteOptions::default()
+        };
+        let mut writer =
+            CsvWriter::new(Vec::new(), &CsvOptions::default(), &write).expect("valid options");
+        writer
+            .write_record(["¥☃"])
+            .await
+            .expect_err("ISO-2022-JP has no snowman");
+        writer.write_record(["¥1"]).await.expect("encodable");
+        let output = writer.finish().await.expect("finish");
+
+        let meta = CsvMeta {
+            charset: encoding_rs::ISO_2022_JP,
+            ..CsvMeta::default()
+        };
+        assert_eq!(
+            read_back(output, &CsvOptions::default(), meta).await,
+            [["¥1"]]
+        );
+    }
+
+    #[test]
+    fn unwritable_settings_are_rejected() {
+        let unescaped = CsvOptions {
+            double_quote: false,
+            ..CsvOptions::default()
+        };
+        let utf16 = CsvWriteOptions {
+            charset: encoding_rs::UTF_16LE,
+            ..CsvWriteOptions::default()
+        };
+
+        for (options, write) in [
+            (unescaped, CsvWriteOptions::default()),
+            (CsvOptions::default(), utf16),
+        ] {
+            assert!(matches!(
+                CsvWriter::new(Vec::new(), &options, &write),
+                Err(CsvIngestError::UnsupportedDialect(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn unquoted_dialects_reject_fields_that_need_quotes() {
+        let options = CsvOptions {
+            quoting: false,
+            ..CsvOptions::default()
+        };
+        let error = written(
+            &[&["a\"b"], &["a,b"]],
+            &options,
+            &CsvWriteOptions::default(),
+        )
+        .await
+        .expect_err("the delimiter cannot be escaped");
+        assert!(matches!(error, CsvIngestError::UnsupportedDialect(_)));
+    }
+}
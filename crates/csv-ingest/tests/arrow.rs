@@ -0,0 +1,120 @@
+#![cfg(feature = "arrow")]
+
+use arrow::array::{Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use csv_ingest::{CsvArrowOptions, CsvFilter, CsvIngestError, CsvOptions, CsvParser};
+use std::io::Cursor;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn filtered_records_stream_as_typed_batches() {
+    let input = "sku,qty,country\na,1,US\nb,2,CA\nc,,US\nd,4,US\n";
+    let parser = CsvParser::from_reader(Cursor::new(input), &[], &CsvOptions::default())
+        .await
+        .expect("construct parser")
+        .with_filter(CsvFilter::equals("country", "US"))
+        .expect("resolve filter");
+    let options = CsvArrowOptions {
+        batch_size: 2,
+        schema: Some(Arc::new(Schema::new(vec![
+            Field::new("sku", DataType::Utf8, false),
+            Field::new("qty", DataType::Int64, true),
+        ]))),
+        ..CsvArrowOptions::default()
+    };
+    let mut reader = parser
+        .into_record_batches(&options)
+        .await
+        .expect("resolve schema");
+
+    let mut skus = Vec::new();
+    let mut quantities = Vec::new();
+    while let Some(batch) = reader.next_batch().await.expect("read batch") {
+        let sku = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .expect("sku is a string column");
+        let qty = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .expect("qty is an integer column");
+        skus.extend(sku.iter().flatten().map(str::to_string));
+        quantities.extend(qty.iter());
+    }
+
+    assert_eq!(skus, ["a", "c", "d"]);
+    assert_eq!(quantities, [Some(1), None, Some(4)]);
+    assert_eq!(reader.parser().filtered_rows(), 1);
+}
+
+#[tokio::test]
+async fn unconvertible_values_stop_the_stream() {
+    let options = CsvArrowOptions {
+        schema: Some(Arc::new(Schema::new(vec![Field::new(
+            "qty",
+            DataType::Int64,
+            true,
+        )]))),
+        ..CsvArrowOptions::default()
+    };
+    let mut reader =
+        CsvParser::from_reader(Cursor::new("qty\n1\n1.5\n"), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser")
+            .into_record_batches(&options)
+            .await
+            .expect("resolve schema");
+
+    let error = reader
+        .next_batch()
+        .await
+        .expect_err("1.5 is not an integer");
+    assert!(matches!(
+        error,
+        CsvIngestError::InvalidValue { row: 2, ref column, .. } if column == "qty"
+    ));
+}
+
+#[cfg(feature = "fast_local")]
+#[test]
+fn fast_local_batches_cover_every_accepted_row() {
+    use csv_ingest::{fast_local_record_batches, FastLocalConfig};
+    use std::io::Write;
+
+    let mut file = tempfile::NamedTempFile::new().expect("create fixture");
+    writeln!(file, "id,name").expect("write header");
+    for id in 0..1_000 {
+        writeln!(file, "{id},n{id}").expect("write row");
+    }
+    let output = fast_local_record_batches(
+        file.path(),
+        &["id"],
+        &CsvOptions::default(),
+        None,
+        &CsvArrowOptions {
+            batch_size: 64,
+            ..CsvArrowOptions::default()
+        },
+        FastLocalConfig::default(),
+    )
+    .expect("read batches");
+
+    assert_eq!(output.summary.row_count, 1_000);
+    assert_eq!(output.schema.field(0).data_type(), &DataType::Int64);
+    let ids: Vec<i64> = output
+        .batches
+        .iter()
+        .flat_map(|batch| {
+            let ids = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .expect("id is an integer column");
+            ids.values().to_vec()
+        })
+        .collect();
+    assert_eq!(ids, (0..1_000).collect::<Vec<_>>());
+    assert!(output.batches.iter().all(|batch| batch.num_rows() <= 64));
+}
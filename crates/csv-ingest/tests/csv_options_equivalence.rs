@@ -1,7 +1,7 @@
 #![cfg(feature = "fast_local")]
 
 use csv_ingest::{
-    fast_local_process, summarize_csv_stream, CsvHeaderMode, CsvIngestError, CsvOptions, CsvResult,
+    fast_local_process, summarize_csv_stream, CsvHeaderMode, CsvIngestError, CsvOptions,
     CsvTerminator, CsvTrim,
 };
 use std::io::{Cursor, Write};
@@ -102,24 +102,25 @@ async fn strict_and_flexible_row_width_behavior_matches() -> anyhow::Result<()>
 }
 
 #[tokio::test]
-async fn fast_local_rejects_quoted_data_instead_of_disagreeing() -> anyhow::Result<()> {
-    let contents = b"sku,value\nA,\"quoted,value\"\n";
-    let streaming = summarize_csv_stream(
-        Cursor::new(contents.to_vec()),
+async fn quoted_data_matches() -> anyhow::Result<()> {
+    let contents = b"sku,\"value\"\r\nA,\"quoted,value\"\r\nB,\"two\r\nlines \"\"here\"\"\"\r\n";
+    let (streaming, fast) = parse_both(contents, &["value"], &CsvOptions::default()).await?;
+    assert_eq!(streaming, fast);
+    assert_eq!(streaming.row_count, 2);
+    assert_eq!(streaming.headers, ["sku", "value"]);
+
+    let escaped = CsvOptions {
+        escape: Some(b'\\'),
+        double_quote: false,
+        ..CsvOptions::default()
+    };
+    let (streaming, fast) = parse_both(
+        b"sku,value\nA,\"say \\\"hi\\\",\n\"\nB,2\n",
         &["sku"],
-        &CsvOptions::default(),
+        &escaped,
     )
     .await?;
-    assert_eq!(streaming.row_count, 1);
-
-    let mut file = NamedTempFile::new()?;
-    file.write_all(contents)?;
-    let result: CsvResult<_> =
-        fast_local_process(file.path(), &["sku"], &CsvOptions::default(), false, None);
-    assert!(matches!(
-        result,
-        Err(CsvIngestError::UnsupportedDialect(message))
-            if message.contains("only unquoted CSV")
-    ));
+    assert_eq!(streaming, fast);
+    assert_eq!(streaming.row_count, 2);
     Ok(())
 }
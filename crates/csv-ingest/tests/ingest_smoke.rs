@@ -99,7 +99,8 @@ async fn extra_fields_respect_fixed_and_flexible_modes() -> anyhow::Result<()> {
         CsvIngestError::RaggedRow {
             row: Some(1),
             expected: 2,
-            actual: 3
+            actual: 3,
+            ..
         }
     ));
 
@@ -0,0 +1,287 @@
+#![cfg(feature = "parallel_decompress")]
+
+use csv_ingest::{
+    reader_from_path_parallel, summarize_csv_stream, CompressedStreamError, CsvDecompressConfig,
+    CsvOptions, CsvParser, TrailingDataPolicy,
+};
+use flate2::write::GzEncoder;
+use std::io::{Cursor, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use tokio::io::AsyncReadExt;
+
+fn sample() -> Vec<u8> {
+    let mut data = b"sku,qty\n".to_vec();
+    for row in 0..5_000 {
+        data.extend_from_slice(format!("sku-{row},\"{}\"\n", row % 97).as_bytes());
+    }
+    data
+}
+
+fn write(dir: &TempDir, name: &str, bytes: &[u8]) -> PathBuf {
+    let path = dir.path().join(name);
+    std::fs::write(&path, bytes).expect("write fixture");
+    path
+}
+
+/// Compress `data` as one gzip member per `chunk` bytes.
+fn gzip_members(data: &[u8], chunk: usize) -> Vec<u8> {
+    data.chunks(chunk)
+        .flat_map(|chunk| {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(chunk).expect("write gzip input");
+            encoder.finish().expect("finish gzip member")
+        })
+        .collect()
+}
+
+/// Compress `data` as one zstd frame per `chunk` bytes.
+fn zstd_frames(data: &[u8], chunk: usize) -> Vec<u8> {
+    data.chunks(chunk)
+        .flat_map(|chunk| zstd::encode_all(chunk, 3).expect("compress frame"))
+        .collect()
+}
+
+fn config(workers: usize) -> CsvDecompressConfig {
+    CsvDecompressConfig {
+        workers: NonZeroUsize::new(workers),
+        segment_size: 4_096,
+        ..CsvDecompressConfig::default()
+    }
+}
+
+async fn read_all(path: &Path, config: &CsvDecompressConfig) -> Vec<u8> {
+    let (mut reader, meta) = reader_from_path_parallel(path, config)
+        .await
+        .expect("open parallel reader");
+    assert_eq!(
+        Some(meta.name_hint.as_str()),
+        path.file_name().and_then(|name| name.to_str())
+    );
+    let mut decoded = Vec::new();
+    reader
+        .read_to_end(&mut decoded)
+        .await
+        .expect("read decoded");
+    decoded
+}
+
+#[tokio::test]
+async fn parallel_readers_match_the_uncompressed_file() {
+    let dir = TempDir::new().expect("create temp dir");
+    let expected = sample();
+    let files = [
+        write(&dir, "members.csv.gz", &gzip_members(&expected, 10_000)),
+        write(
+            &dir,
+            "single.csv.gz",
+            &gzip_members(&expected, expected.len()),
+        ),
+        write(&dir, "frames.csv.zst", &zstd_frames(&expected, 10_000)),
+        write(&dir, "plain.csv", &expected),
+    ];
+
+    for path in &files {
+        for workers in [1, 2, 4] {
+            assert_eq!(read_all(path, &config(workers)).await, expected, "{path:?}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn parallel_readers_feed_the_streaming_parser() {
+    let dir = TempDir::new().expect("create temp dir");
+    let expected = sample();
+    let path = write(&dir, "rows.csv.zst", &zstd_frames(&expected, 7_000));
+    let options = CsvOptions::default();
+
+    let (reader, meta) = reader_from_path_parallel(&path, &config(3))
+        .await
+        .expect("open parallel reader");
+    let mut parser = CsvParser::from_reader(reader, &["sku"], &options)
+        .await
+        .expect("construct parser")
+        .with_source_name(&meta.name_hint);
+    let mut last = None;
+    while let Some(record) = parser.next_record().await.expect("read record") {
+        last = record.get(0).map(<[u8]>::to_vec);
+    }
+    assert_eq!(last.as_deref(), Some(&b"sku-4999"[..]));
+
+    let (reader, _) = reader_from_path_parallel(&path, &config(3))
+        .await
+        .expect("open parallel reader");
+    let summary = summarize_csv_stream(reader, &["sku"], &options)
+        .await
+        .expect("summarize parallel reader");
+    let serial = summarize_csv_stream(Cursor::new(expected), &["sku"], &options)
+        .await
+        .expect("summarize plain input");
+    assert_eq!(summary.row_count, serial.row_count);
+    assert_eq!(summary.headers, serial.headers);
+}
+
+#[tokio::test]
+async fn corrupt_members_are_read_errors() {
+    let dir = TempDir::new().expect("create temp dir");
+    let mut compressed = gzip_members(&sample(), 10_000);
+    compressed.truncate(compressed.len() - 3);
+    let path = write(&dir, "truncated.csv.gz", &compressed);
+
+    let (mut reader, _) = reader_from_path_parallel(&path, &config(2))
+        .await
+        .expect("open parallel reader");
+    let mut decoded = Vec::new();
+    let error = reader
+        .read_to_end(&mut decoded)
+        .await
+        .expect_err("a truncated member must fail");
+    assert_eq!(
+        error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<CompressedStreamError>()),
+        Some(&CompressedStreamError::Truncated {
+            compression: "gzip",
+            offset: compressed.len() as u64,
+        })
+    );
+}
+
+#[tokio::test]
+async fn trailing_data_is_reported_by_the_parallel_reader() {
+    let dir = TempDir::new().expect("create temp dir");
+    let expected = sample();
+    let compressed = gzip_members(&expected, 10_000);
+    let mut padded = compressed.clone();
+    padded.extend_from_slice(b"\0\0 trailing junk");
+    let path = write(&dir, "padded.csv.gz", &padded);
+
+    for workers in [1, 3] {
+        let warn = CsvDecompressConfig {
+            trailing_data: TrailingDataPolicy::Warn,
+            ..config(workers)
+        };
+        let (mut reader, meta) = reader_from_path_parallel(&path, &warn)
+            .await
+            .expect("open parallel reader");
+        let mut decoded = Vec::new();
+        reader
+            .read_to_end(&mut decoded)
+            .await
+            .expect("read decoded");
+        assert_eq!(decoded, expected);
+        assert_eq!(meta.trailing_data.offset(), Some(compressed.len() as u64));
+
+        let (mut reader, _) = reader_from_path_parallel(&path, &config(workers))
+            .await
+            .expect("open parallel reader");
+        reader
+            .read_to_end(&mut Vec::new())
+            .await
+            .expect_err("trailing data must fail by default");
+    }
+}
+
+#[cfg(feature = "fast_local")]
+#[test]
+fn compressed_fast_local_counts_match_the_uncompressed_file() {
+    use csv_ingest::{
+        fast_local_process, fast_local_process_compressed, CsvErrorPolicy, FastLocalConfig,
+    };
+
+    let dir = TempDir::new().expect("create temp dir");
+    let mut expected = sample();
+    expected.extend_from_slice(b"ragged\n");
+    expected.extend_from_slice(b"last,1\n");
+    let plain = write(&dir, "rows.csv", &expected);
+    let files = [
+        write(&dir, "rows.csv.gz", &gzip_members(&expected, 9_000)),
+        write(&dir, "rows.csv.zst", &zstd_frames(&expected, 9_000)),
+    ];
+    let options = CsvOptions {
+        quote: b'\'',
+        error_policy: CsvErrorPolicy::Skip,
+        ..CsvOptions::default()
+    };
+
+    let (summary, crc) =
+        fast_local_process(&plain, &["sku"], &options, true, None).expect("process plain file");
+    assert_eq!(summary.rejected_rows, 1);
+    let windowed = || FastLocalConfig {
+        workers: NonZeroUsize::new(2),
+        min_chunk_size: 64,
+        window_size: NonZeroUsize::new(1_000),
+        ..FastLocalConfig::default()
+    };
+    for path in &files {
+        for workers in [1, 3] {
+            for parse in [FastLocalConfig::default(), windowed()] {
+                let (compressed, compressed_crc) = fast_local_process_compressed(
+                    path,
+                    &["sku"],
+                    &options,
+                    true,
+                    None,
+                    parse,
+                    &config(workers),
+                )
+                .expect("process compressed file");
+                assert_eq!(compressed, summary, "{path:?}");
+                assert_eq!(compressed_crc, crc, "{path:?}");
+            }
+        }
+    }
+
+    let (limited, _) = fast_local_process_compressed(
+        &files[0],
+        &["sku"],
+        &options,
+        false,
+        Some(10),
+        windowed(),
+        &config(2),
+    )
+    .expect("process limited rows");
+    assert_eq!(limited.row_count, 10);
+}
+
+#[cfg(feature = "fast_local")]
+#[test]
+fn compressed_fast_local_stops_decoding_with_the_parse() {
+    use csv_ingest::{fast_local_process_compressed, CsvIngestError, FastLocalConfig};
+
+    let dir = TempDir::new().expect("create temp dir");
+    let data = sample();
+    let path = write(&dir, "rows.csv.gz", &gzip_members(&data, 2_000));
+    let error = fast_local_process_compressed(
+        &path,
+        &["price"],
+        &CsvOptions::default(),
+        false,
+        None,
+        FastLocalConfig::default(),
+        &config(2),
+    )
+    .expect_err("missing header must fail");
+    assert!(matches!(error, CsvIngestError::MissingHeader(header) if header == "price"));
+
+    let mut corrupt = gzip_members(&data, 2_000);
+    let len = corrupt.len();
+    corrupt.truncate(len - 100);
+    let path = write(&dir, "truncated.csv.gz", &corrupt);
+    let windowed = FastLocalConfig {
+        window_size: NonZeroUsize::new(1_000),
+        ..FastLocalConfig::default()
+    };
+    fast_local_process_compressed(
+        &path,
+        &["sku"],
+        &CsvOptions::default(),
+        false,
+        None,
+        windowed,
+        &config(2),
+    )
+    .expect_err("truncated input must fail");
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "parquet")]
+
+use arrow::array::{Array, StringArray};
+use async_compression::tokio::write::GzipEncoder;
+use bytes::Bytes;
+use csv_ingest::{build_csv_reader, CsvMeta, CsvOptions, CsvParquetOptions, CsvParser};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::io::Cursor;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn compressed_transcoded_input_is_written_as_parquet() -> anyhow::Result<()> {
+    let mut encoder = GzipEncoder::new(Vec::new());
+    encoder
+        .write_all(b"city,temp\nM\xfcnchen,4\nZ\xfcrich,\n")
+        .await?;
+    encoder.shutdown().await?;
+    let (reader, _meta) = build_csv_reader(
+        Cursor::new(encoder.into_inner()),
+        CsvMeta {
+            name_hint: "weather.csv.gz".to_string(),
+            charset: encoding_rs::WINDOWS_1252,
+            ..CsvMeta::default()
+        },
+    )?;
+
+    let mut file = Vec::new();
+    let summary = CsvParser::from_reader(reader, &["city"], &CsvOptions::default())
+        .await?
+        .write_parquet(&mut file, &CsvParquetOptions::default())
+        .await?;
+    assert_eq!(summary.row_count, 2);
+
+    let batches = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file))?
+        .build()?
+        .collect::<Result<Vec<_>, _>>()?;
+    let cities = batches[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("city is a string column");
+    assert_eq!(
+        cities.iter().collect::<Vec<_>>(),
+        [Some("München"), Some("Zürich")]
+    );
+    assert_eq!(batches[0].column(1).null_count(), 1);
+    Ok(())
+}
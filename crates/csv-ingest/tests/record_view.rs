@@ -0,0 +1,134 @@
+#![cfg(feature = "derive")]
+
+use csv_ingest::{CsvField, CsvIngestError, CsvOptions, CsvParser, CsvRecord};
+use std::io::Cursor;
+
+#[derive(Debug, PartialEq, CsvRecord)]
+struct Item<'a> {
+    sku: &'a str,
+    #[csv(rename = "raw value")]
+    value: &'a [u8],
+    note: Option<&'a str>,
+    missing: Option<&'a [u8]>,
+}
+
+#[test]
+fn derived_fields_describe_headers_and_requirements() {
+    assert_eq!(
+        Item::FIELDS,
+        [
+            CsvField {
+                header: "sku",
+                required: true,
+            },
+            CsvField {
+                header: "raw value",
+                required: true,
+            },
+            CsvField {
+                header: "note",
+                required: false,
+            },
+            CsvField {
+                header: "missing",
+                required: false,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn views_borrow_fields_by_header_name() {
+    let input = b"note,raw value,sku\nfirst,\xff,A\n,2,B\n";
+    let mut parser = CsvParser::from_reader(Cursor::new(&input[..]), &[], &CsvOptions::default())
+        .await
+        .expect("construct parser");
+    let columns = parser.resolve_columns::<Item>().expect("resolve columns");
+    assert_eq!(parser.required_indices(), [2, 1]);
+
+    let mut views = Vec::new();
+    while let Some(item) = parser.next_view::<Item>(&columns).await.expect("read view") {
+        views.push((
+            item.sku.to_string(),
+            item.value.to_vec(),
+            item.note.map(str::to_string),
+            item.missing.is_some(),
+        ));
+    }
+    assert_eq!(
+        views,
+        [
+            (
+                "A".to_string(),
+                b"\xff".to_vec(),
+                Some("first".to_string()),
+                false
+            ),
+            ("B".to_string(), b"2".to_vec(), Some(String::new()), false),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn view_fields_become_required_headers() {
+    let mut parser =
+        CsvParser::from_reader(Cursor::new(b"sku,note\nA,x\n"), &[], &CsvOptions::default())
+            .await
+            .expect("construct parser");
+    let error = parser
+        .resolve_columns::<Item>()
+        .expect_err("missing required view header must fail");
+    assert!(matches!(error, CsvIngestError::MissingHeader(header) if header == "raw value"));
+
+    let options = CsvOptions {
+        flexible: true,
+        ..CsvOptions::default()
+    };
+    let mut parser =
+        CsvParser::from_reader(Cursor::new(b"sku,raw value\nA,1\nB\n"), &["sku"], &options)
+            .await
+            .expect("construct parser");
+    let columns = parser.resolve_columns::<Item>().expect("resolve columns");
+    assert_eq!(parser.required_indices(), [0, 1]);
+
+    let first = parser
+        .next_view::<Item>(&columns)
+        .await
+        .expect("read first view")
+        .expect("first view");
+    assert_eq!(first.sku, "A");
+    let error = parser
+        .next_view::<Item>(&columns)
+        .await
+        .expect_err("short row must fail");
+    assert!(matches!(
+        error,
+        CsvIngestError::MissingRequiredField { row: 2, header, .. } if header == "raw value"
+    ));
+}
+
+#[tokio::test]
+async fn invalid_utf8_view_fields_name_their_column_and_row() {
+    let input = b"sku,raw value\nA,1\n\xff,2\n";
+    let mut parser = CsvParser::from_reader(Cursor::new(&input[..]), &[], &CsvOptions::default())
+        .await
+        .expect("construct parser")
+        .with_source_name("items.csv");
+    let columns = parser.resolve_columns::<Item>().expect("resolve columns");
+
+    parser
+        .next_view::<Item>(&columns)
+        .await
+        .expect("read first view")
+        .expect("first view");
+    let error = parser
+        .next_view::<Item>(&columns)
+        .await
+        .expect_err("invalid utf-8 must fail");
+    assert!(matches!(
+        error,
+        CsvIngestError::InvalidValue { row: 2, ref column, .. } if column == "sku"
+    ));
+    assert_eq!(error.source_name(), Some("items.csv"));
+    assert_eq!(error.position().map(|position| position.byte()), Some(18));
+}
@@ -1,6 +1,8 @@
 #![no_main]
 
-use csv_ingest::{fast_local_process, CsvHeaderMode, CsvOptions, CsvTerminator, CsvTrim};
+use csv_ingest::{
+    fast_local_process, CsvErrorPolicy, CsvHeaderMode, CsvOptions, CsvTerminator, CsvTrim,
+};
 use libfuzzer_sys::fuzz_target;
 use std::io::Write;
 use tempfile::NamedTempFile;
@@ -22,7 +24,7 @@ fuzz_target!(|data: &[u8]| {
         return;
     }
 
-    let options = options(selector);
+    let options = options(selector, transform);
     let required_headers: &[&str] = if options.headers == CsvHeaderMode::Absent {
         &[]
     } else {
@@ -53,7 +55,7 @@ fuzz_target!(|data: &[u8]| {
     );
 });
 
-fn options(selector: u8) -> CsvOptions {
+fn options(selector: u8, transform: u8) -> CsvOptions {
     let (delimiter, terminator) = match selector & 0b11 {
         0 => (b',', CsvTerminator::CrLf),
         1 => (b'\t', CsvTerminator::Any(b'\n')),
@@ -81,6 +83,13 @@ fn options(selector: u8) -> CsvOptions {
         quote: b'"',
         escape: (selector & 0b1000_0000 != 0).then_some(b'\\'),
         double_quote: selector & 0b10_0000 == 0,
+        error_policy: match transform & 0b11_0000 {
+            0b01_0000 => CsvErrorPolicy::Skip,
+            0b10_0000 => CsvErrorPolicy::SkipUpTo(1),
+            0b11_0000 => CsvErrorPolicy::SkipUpToPercent(50),
+            _ => CsvErrorPolicy::FailFast,
+        },
+        max_diagnostics: usize::from(transform >> 6),
     }
 }
 
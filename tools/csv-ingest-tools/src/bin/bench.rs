@@ -138,6 +138,10 @@ async fn verify_and_count<R: tokio::io::AsyncRead + Unpin + Send>(
         csv_ingest::CsvIngestSummary {
             row_count: parser.records_read(),
             headers,
+            scanned_rows: parser.rows_scanned(),
+            filtered_rows: parser.filtered_rows(),
+            rejected_rows: parser.rejected_rows(),
+            diagnostics: parser.diagnostics().to_vec(),
         },
         crc.finalize(),
     ))